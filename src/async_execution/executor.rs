@@ -0,0 +1,185 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use bevy_ecs::prelude::Resource;
+
+use async_task::Runnable;
+
+use std::{future::Future, sync::Arc};
+
+use super::TaskHandle;
+
+/// A job that polls an async task once. Jobs return quickly unless the future
+/// that they poll blocks.
+pub type ExecutorJob = Box<dyn FnOnce() + Send + 'static>;
+
+/// Implement this trait to run the async tasks of bevy_impulse (async services,
+/// async maps, and async callbacks) on an executor of your choosing. Insert an
+/// [`AsyncTaskExecutor`] resource to use it.
+///
+/// Each time a task is woken up, a job that polls the task will be passed to
+/// [`Self::execute`]. The executor should run that job as soon as it can. The
+/// context that the job runs in is the context that the future of the task
+/// will be polled in, so futures that rely on a particular runtime (e.g. tokio
+/// timers or IO) will work as long as the executor runs the job inside of that
+/// runtime.
+pub trait AsyncExecutor: 'static + Send + Sync {
+    fn execute(&self, job: ExecutorJob);
+}
+
+/// Insert this resource to run async tasks on a custom [`AsyncExecutor`]
+/// instead of the [`AsyncComputeTaskPool`](bevy_tasks::AsyncComputeTaskPool).
+/// Tasks that were spawned before the resource was inserted will continue to
+/// run on the executor that they were spawned on.
+///
+/// This has no effect when the `single_threaded_async` feature is active, since
+/// tasks are not required to be [`Send`] in that case. A warning will be logged
+/// the first time a task is spawned while this resource is being ignored.
+#[derive(Resource, Clone)]
+#[cfg_attr(feature = "single_threaded_async", allow(dead_code))]
+pub struct AsyncTaskExecutor(Arc<dyn AsyncExecutor>);
+
+impl AsyncTaskExecutor {
+    pub fn new(executor: impl AsyncExecutor) -> Self {
+        Self(Arc::new(executor))
+    }
+
+    #[cfg_attr(feature = "single_threaded_async", allow(dead_code))]
+    pub(crate) fn spawn<T>(&self, future: impl Future<Output = T> + Send + 'static) -> TaskHandle<T>
+    where
+        T: Send + 'static,
+    {
+        let executor = Arc::clone(&self.0);
+        let schedule = move |runnable: Runnable| {
+            executor.execute(Box::new(move || {
+                runnable.run();
+            }));
+        };
+        let (runnable, task) = async_task::spawn(future, schedule);
+        runnable.schedule();
+        TaskHandle::new(task)
+    }
+}
+
+impl std::fmt::Debug for AsyncTaskExecutor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncTaskExecutor").finish_non_exhaustive()
+    }
+}
+
+/// An [`AsyncExecutor`] that runs tasks on a [tokio](https://tokio.rs) runtime.
+/// Use this for async services whose futures need a tokio reactor, such as
+/// tokio timers or IO. Like any [`AsyncTaskExecutor`], this is ignored when the
+/// `single_threaded_async` feature is active.
+///
+/// ```
+/// use bevy_impulse::{AsyncTaskExecutor, TokioExecutor};
+///
+/// let runtime = tokio::runtime::Runtime::new().unwrap();
+/// let executor = AsyncTaskExecutor::new(TokioExecutor::new(runtime.handle().clone()));
+/// ```
+#[cfg(feature = "tokio_async")]
+#[derive(Clone, Debug)]
+pub struct TokioExecutor {
+    handle: tokio::runtime::Handle,
+}
+
+#[cfg(feature = "tokio_async")]
+impl TokioExecutor {
+    pub fn new(handle: tokio::runtime::Handle) -> Self {
+        Self { handle }
+    }
+
+    /// Use the tokio runtime that the current thread is running in, if there
+    /// is one.
+    pub fn current() -> Option<Self> {
+        tokio::runtime::Handle::try_current().ok().map(Self::new)
+    }
+}
+
+#[cfg(feature = "tokio_async")]
+impl AsyncExecutor for TokioExecutor {
+    fn execute(&self, job: ExecutorJob) {
+        self.handle.spawn(async move { job() });
+    }
+}
+
+// The executor is not used when the single_threaded_async feature is active.
+#[cfg(all(test, not(feature = "single_threaded_async")))]
+mod tests {
+    use crate::{prelude::*, testing::*, AsyncExecutor, AsyncTaskExecutor, ExecutorJob};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    struct ThreadExecutor {
+        jobs: Arc<AtomicUsize>,
+    }
+
+    impl AsyncExecutor for ThreadExecutor {
+        fn execute(&self, job: ExecutorJob) {
+            self.jobs.fetch_add(1, Ordering::SeqCst);
+            std::thread::spawn(job);
+        }
+    }
+
+    #[test]
+    fn test_custom_executor() {
+        let mut context = TestingContext::minimal_plugins();
+        let jobs = Arc::new(AtomicUsize::new(0));
+        context
+            .app
+            .insert_resource(AsyncTaskExecutor::new(ThreadExecutor {
+                jobs: Arc::clone(&jobs),
+            }));
+
+        let mut promise = context.command(|commands| {
+            commands
+                .provide(2_u32)
+                .map_async(|value| async move { value * 3 })
+                .take_response()
+        });
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some_and(|v| v == 6));
+        assert!(jobs.load(Ordering::SeqCst) > 0);
+    }
+
+    #[cfg(feature = "tokio_async")]
+    #[test]
+    fn test_tokio_executor() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut context = TestingContext::minimal_plugins();
+        context
+            .app
+            .insert_resource(AsyncTaskExecutor::new(crate::TokioExecutor::new(
+                runtime.handle().clone(),
+            )));
+
+        let mut promise = context.command(|commands| {
+            commands
+                .provide(2_u32)
+                .map_async(|value| async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                    value * 3
+                })
+                .take_response()
+        });
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some_and(|v| v == 6));
+    }
+}
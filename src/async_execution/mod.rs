@@ -28,8 +28,11 @@ use single_threaded_execution::SingleThreadedExecutionSender;
 
 pub(crate) use bevy_tasks::Task as TaskHandle;
 
+mod executor;
+pub use executor::*;
+
 #[cfg(not(feature = "single_threaded_async"))]
-pub(crate) type CancelSender = AsyncComputeTaskPoolSender;
+pub(crate) type CancelSender = ExecutorCancelSender;
 
 #[cfg(feature = "single_threaded_async")]
 pub(crate) type CancelSender = SingleThreadedExecutionSender;
@@ -43,20 +46,45 @@ where
 {
     #[cfg(not(feature = "single_threaded_async"))]
     {
+        if let Some(executor) = _world.get_resource::<AsyncTaskExecutor>() {
+            return executor.spawn(future);
+        }
+
         use bevy_tasks::AsyncComputeTaskPool;
         AsyncComputeTaskPool::get().spawn(future)
     }
 
     #[cfg(feature = "single_threaded_async")]
     {
+        if _world.contains_resource::<AsyncTaskExecutor>() {
+            warn_ignored_executor();
+        }
+
         SingleThreadedExecution::get(_world).spawn(future)
     }
 }
 
+/// Tasks are not [`Send`] when the `single_threaded_async` feature is active,
+/// so they cannot be handed to an [`AsyncTaskExecutor`]. Let users know once
+/// instead of silently ignoring the executor that they inserted.
+#[cfg(feature = "single_threaded_async")]
+fn warn_ignored_executor() {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        tracing::warn!(
+            "An AsyncTaskExecutor resource is present but will be ignored because \
+            the single_threaded_async feature of bevy_impulse is active. Async \
+            tasks will run on the single threaded executor instead."
+        );
+    });
+}
+
 pub(crate) fn task_cancel_sender(_world: &mut World) -> CancelSender {
     #[cfg(not(feature = "single_threaded_async"))]
     {
-        AsyncComputeTaskPoolSender
+        ExecutorCancelSender {
+            executor: _world.get_resource::<AsyncTaskExecutor>().cloned(),
+        }
     }
 
     #[cfg(feature = "single_threaded_async")]
@@ -65,11 +93,15 @@ pub(crate) fn task_cancel_sender(_world: &mut World) -> CancelSender {
     }
 }
 
+/// Sends cancellation tasks to the same executor that the task being
+/// cancelled was spawned on.
 #[cfg(not(feature = "single_threaded_async"))]
-pub(crate) struct AsyncComputeTaskPoolSender;
+pub(crate) struct ExecutorCancelSender {
+    executor: Option<AsyncTaskExecutor>,
+}
 
 #[cfg(not(feature = "single_threaded_async"))]
-impl AsyncComputeTaskPoolSender {
+impl ExecutorCancelSender {
     /// This is only used to create a task to cancel an existing task, so we
     /// always detach
     pub(crate) fn send<F>(&self, f: impl FnOnce() -> F)
@@ -77,6 +109,11 @@ impl AsyncComputeTaskPoolSender {
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
+        if let Some(executor) = &self.executor {
+            executor.spawn(f()).detach();
+            return;
+        }
+
         use bevy_tasks::AsyncComputeTaskPool;
         AsyncComputeTaskPool::get().spawn(f()).detach();
     }
@@ -187,26 +187,38 @@ pub trait Accessing: Buffering {
             build,
         );
 
-        let begin_cancel = builder
-            .commands
-            .spawn(())
-            .set_parent(builder.context.scope)
-            .id();
-        self.verify_scope(builder.scope());
-        builder.commands.add(AddOperation::new(
-            None,
-            begin_cancel,
-            BeginCleanupWorkflow::<Self>::new(
-                builder.scope(),
-                self,
-                cancelling_scope_id,
-                conditions.run_on_terminate,
-                conditions.run_on_cancel,
-            ),
-        ));
+        begin_cleanup_workflow(self, cancelling_scope_id, conditions, builder);
     }
 }
 
+/// Add the operation that triggers a cleanup workflow for the current scope of
+/// the builder. The cleanup workflow scope must already exist, and its request
+/// type must be the key type of the buffers.
+pub(crate) fn begin_cleanup_workflow<B: Accessing>(
+    buffers: B,
+    cleanup_scope_id: Entity,
+    conditions: CleanupWorkflowConditions,
+    builder: &mut Builder,
+) {
+    let begin_cancel = builder
+        .commands
+        .spawn(())
+        .set_parent(builder.context.scope)
+        .id();
+    buffers.verify_scope(builder.scope());
+    builder.commands.add(AddOperation::new(
+        None,
+        begin_cancel,
+        BeginCleanupWorkflow::<B>::new(
+            builder.scope(),
+            buffers,
+            cleanup_scope_id,
+            conditions.run_on_terminate,
+            conditions.run_on_cancel,
+        ),
+    ));
+}
+
 impl<T: 'static + Send + Sync> Buffering for Buffer<T> {
     fn verify_scope(&self, scope: Entity) {
         assert_eq!(scope, self.scope());
@@ -197,6 +197,12 @@ impl<'w, 's, 'a> Builder<'w, 's, 'a> {
     /// through the workflow of the scope with a unique session ID. Even if
     /// multiple values are sent in from the same session, they will each be
     /// assigned their own unique session ID while inside of this scope.
+    ///
+    /// If a session of the scope gets cancelled, e.g. because its terminate
+    /// operation can no longer be reached, then the session of the parent scope
+    /// that sent the value in gets cancelled with the same cause. When scopes
+    /// are nested, the cancellation passes out through each of them until it
+    /// reaches the workflow.
     pub fn create_scope<Request, Response, Streams, Settings>(
         &mut self,
         build: impl FnOnce(Scope<Request, Response, Streams>, &mut Builder) -> Settings,
@@ -24,9 +24,9 @@ use bevy_hierarchy::prelude::DespawnRecursiveExt;
 use backtrace::Backtrace;
 
 use crate::{
-    Broken, ConnectionFailure, EntryForScope, ForkTargetStorage, OperationError, OperationResult,
-    OrBroken, ScopeEntryStorage, SingleInputStorage, SingleTargetStorage, StreamTargetMap,
-    UnhandledErrors,
+    report_unhandled_error, Broken, CancelTargetStorage, ConnectionFailure, EntryForScope,
+    ForkTargetStorage, OperationError, OperationLabel, OperationResult, OrBroken,
+    ScopeEntryStorage, SingleInputStorage, SingleTargetStorage, StreamTargetMap,
 };
 
 /// If two nodes have been created, they will each have a unique source and a
@@ -43,19 +43,21 @@ pub(crate) struct Connect {
 impl Command for Connect {
     fn apply(self, world: &mut World) {
         if let Err(OperationError::Broken(backtrace)) = try_connect(self, world) {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .connections
-                .push(ConnectionFailure {
+            report_unhandled_error(
+                ConnectionFailure {
                     original_target: self.original_target,
                     new_target: self.new_target,
                     backtrace: backtrace.unwrap_or_else(Backtrace::new),
-                })
+                },
+                world,
+            )
         }
     }
 }
 
 fn try_connect(connect: Connect, world: &mut World) -> OperationResult {
+    carry_label(connect, world)?;
+
     if let Some(EntryForScope(scope)) = world.get(connect.original_target) {
         // The original target was the entry point of a scope, so we need to
         // handle it a bit differently. Instead of modifying target and input
@@ -108,6 +110,13 @@ fn try_connect(connect: Connect, world: &mut World) -> OperationResult {
             }
         }
 
+        if let Some(mut target) = input_mut.get_mut::<CancelTargetStorage>() {
+            if target.0 == connect.original_target {
+                connection_happened = true;
+                target.0 = connect.new_target;
+            }
+        }
+
         if let Some(mut targets) = input_mut.get_mut::<StreamTargetMap>() {
             for target in targets.anonymous.values_mut() {
                 if *target == connect.original_target {
@@ -125,13 +134,13 @@ fn try_connect(connect: Connect, world: &mut World) -> OperationResult {
         }
 
         if !connection_happened {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .broken
-                .push(Broken {
+            report_unhandled_error(
+                Broken {
                     node: input,
                     backtrace: Some(Backtrace::new()),
-                });
+                },
+                world,
+            );
         }
 
         if let Some(mut new_inputs_mut) = world.get_mut::<SingleInputStorage>(connect.new_target) {
@@ -151,3 +160,21 @@ fn try_connect(connect: Connect, world: &mut World) -> OperationResult {
 
     Ok(())
 }
+
+/// A label may have been given to the original target with [`Chain::label`](crate::Chain::label)
+/// before it got connected, so pass it along to the new target unless the new
+/// target already has a label of its own.
+fn carry_label(connect: Connect, world: &mut World) -> OperationResult {
+    let Some(label) = world
+        .get::<OperationLabel>(connect.original_target)
+        .cloned()
+    else {
+        return Ok(());
+    };
+
+    let mut new_target_mut = world.get_entity_mut(connect.new_target).or_broken()?;
+    if !new_target_mut.contains::<OperationLabel>() {
+        new_target_mut.insert(label);
+    }
+    Ok(())
+}
@@ -17,9 +17,10 @@
 
 use crate::{
     async_execution::{spawn_task, task_cancel_sender},
-    make_stream_buffers_from_world, AddOperation, AsyncCallback, BlockingCallback, Channel,
-    ChannelQueue, Input, ManageDisposal, ManageInput, OperateCallback, OperateTask, OperationError,
-    OperationRoster, OrBroken, ProvideOnce, Provider, Sendish, StreamPack, UnusedStreams,
+    make_stream_buffers_from_world, AddOperation, AsyncCallback, BlockingCallback,
+    CancellationToken, Channel, ChannelQueue, Input, ManageDisposal, ManageInput, OperateCallback,
+    OperateTask, OperationError, OperationRoster, OrBroken, ProvideOnce, Provider, Sendish,
+    StreamPack, UnusedStreams,
 };
 
 use bevy_ecs::{
@@ -199,6 +200,7 @@ impl<'a> CallbackRequest<'a> {
         &mut self,
         session: Entity,
         task: Task,
+        cancellation_token: CancellationToken,
     ) -> Result<(), OperationError>
     where
         Task::Output: Send + Sync,
@@ -218,6 +220,7 @@ impl<'a> CallbackRequest<'a> {
             self.target,
             task,
             cancel_sender,
+            cancellation_token,
             None,
             sender,
         )
@@ -343,6 +346,7 @@ where
             self.system.initialize(input.world);
         }
 
+        let cancellation_token = CancellationToken::new();
         let task = self.system.run(
             AsyncCallback {
                 request,
@@ -350,12 +354,13 @@ where
                 channel,
                 source: input.source,
                 session,
+                cancellation_token: cancellation_token.clone(),
             },
             input.world,
         );
         self.system.apply_deferred(input.world);
 
-        input.give_task::<_, Streams>(session, task)
+        input.give_task::<_, Streams>(session, task, cancellation_token)
     }
 }
 
@@ -487,14 +492,16 @@ where
                 data: request,
             } = input.get_request::<Self::Request>()?;
             let (channel, streams) = input.get_channel::<Streams>(session)?;
+            let cancellation_token = CancellationToken::new();
             let task = (self)(AsyncCallback {
                 request,
                 streams,
                 channel,
                 source: input.source,
                 session,
+                cancellation_token: cancellation_token.clone(),
             });
-            input.give_task::<_, Streams>(session, task)
+            input.give_task::<_, Streams>(session, task, cancellation_token)
         };
         Callback::new(MapCallback { callback })
     }
@@ -22,13 +22,20 @@ use bevy_ecs::{
 
 use backtrace::Backtrace;
 
+use smallvec::SmallVec;
+
 use thiserror::Error as ThisError;
 
-use std::sync::Arc;
+use std::{
+    any::Any,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
 
 use crate::{
-    CancelFailure, Disposal, Filtered, OperationError, OperationResult, OperationRoster,
-    ScopeStorage, Supplanted, UnhandledErrors,
+    cancel_span, record_cancellation, report_unhandled_error, CancelFailure, DisplayFn, Disposal,
+    Filtered, Livelock, OperationError, OperationLabels, OperationResult, OperationRoster,
+    ScopeStorage, Supplanted,
 };
 
 /// Information about the cancellation that occurred.
@@ -40,13 +47,23 @@ pub struct Cancellation {
     /// Cancellations that occurred within cancellation workflows that were
     /// triggered by this cancellation.
     pub while_cancelling: Vec<Cancellation>,
+    /// Labels of the operations that are referred to by the cause. These are
+    /// captured by the workflow when the cancellation is triggered.
+    pub(crate) labels: OperationLabels,
 }
 
 impl Cancellation {
+    /// Labels of the operations that are referred to by the cause of this
+    /// cancellation.
+    pub fn labels(&self) -> &OperationLabels {
+        &self.labels
+    }
+
     pub fn from_cause(cause: CancellationCause) -> Self {
         Self {
             cause: Arc::new(cause),
             while_cancelling: Default::default(),
+            labels: Default::default(),
         }
     }
 
@@ -71,6 +88,7 @@ impl Cancellation {
         TriggeredCancellation {
             cancelled_at_node,
             value,
+            message: None,
         }
         .into()
     }
@@ -110,6 +128,7 @@ impl<T: Into<CancellationCause>> From<T> for Cancellation {
         Cancellation {
             cause: Arc::new(value.into()),
             while_cancelling: Default::default(),
+            labels: Default::default(),
         }
     }
 }
@@ -150,6 +169,12 @@ pub enum CancellationCause {
     /// operations while including the branches that it needs to collect from.
     CircularCollect(CircularCollect),
 
+    /// The flush reached its loop limit while operations for this session kept
+    /// executing, and [`FlushParameters::cancel_livelocked_sessions`][1] is on.
+    ///
+    /// [1]: crate::FlushParameters::cancel_livelocked_sessions
+    Livelock(Livelock),
+
     /// A request became undeliverable because the sender was dropped. This may
     /// indicate that a critical entity within a workflow was manually despawned.
     /// Check to make sure that you are not manually despawning anything that
@@ -174,6 +199,113 @@ pub enum CancellationCause {
     Broken(Broken),
 }
 
+impl CancellationCause {
+    /// Display this cause, including the labels of any operations that it
+    /// refers to.
+    pub fn display_with<'a>(&'a self, labels: &'a OperationLabels) -> impl Display + 'a {
+        DisplayFn(move |f: &mut Formatter<'_>| self.fmt_with_labels(labels, f))
+    }
+
+    fn fmt_with_labels(&self, labels: &OperationLabels, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::TargetDropped(target) => {
+                write!(f, "the target {target:?} of the request was dropped")
+            }
+            Self::Unreachable(unreachability) => {
+                write!(
+                    f,
+                    "the terminal of scope {} became unreachable for session {:?}",
+                    labels.entity(unreachability.scope),
+                    unreachability.session,
+                )?;
+                for disposal in &unreachability.disposals {
+                    write!(f, "; {disposal}")?;
+                }
+                Ok(())
+            }
+            Self::Filtered(filtered) => {
+                write!(
+                    f,
+                    "filtered at operation {}",
+                    labels.entity(filtered.filtered_at_node),
+                )?;
+                if let Some(reason) = &filtered.reason {
+                    write!(f, ": {reason}")?;
+                }
+                Ok(())
+            }
+            Self::Triggered(triggered) => {
+                write!(
+                    f,
+                    "triggered at operation {}",
+                    labels.entity(triggered.cancelled_at_node),
+                )?;
+                if let Some(value) = &triggered.value {
+                    write!(f, ": {value}")?;
+                }
+                Ok(())
+            }
+            Self::Supplanted(supplanted) => write!(
+                f,
+                "the request at operation {} was supplanted by operation {}",
+                labels.entity(supplanted.supplanted_at_node),
+                labels.entity(supplanted.supplanted_by_node),
+            ),
+            Self::InvalidSpan(span) => {
+                write!(f, "invalid span from {}", labels.entity(span.from_point))?;
+                match span.to_point {
+                    Some(to_point) => write!(f, " to {}", labels.entity(to_point)),
+                    None => write!(f, " to the end of the workflow"),
+                }
+            }
+            Self::CircularCollect(circular) => {
+                write!(f, "circular dependency between collect operations")?;
+                for [a, b] in &circular.conflicts {
+                    write!(f, " [{}, {}]", labels.entity(*a), labels.entity(*b))?;
+                }
+                Ok(())
+            }
+            Self::Livelock(livelock) => write!(f, "{livelock}"),
+            Self::Undeliverable => write!(f, "the request became undeliverable"),
+            Self::PoisonedMutexInPromise => write!(f, "the mutex of a promise was poisoned"),
+            Self::Broken(broken) => {
+                write!(f, "operation {} is broken", labels.entity(broken.node))
+            }
+        }
+    }
+
+    /// The operations that are referred to by this cause.
+    pub(crate) fn operations(&self) -> SmallVec<[Entity; 4]> {
+        match self {
+            Self::TargetDropped(_) | Self::Undeliverable | Self::PoisonedMutexInPromise => {
+                SmallVec::new()
+            }
+            Self::Unreachable(unreachability) => SmallVec::from_slice(&[unreachability.scope]),
+            Self::Filtered(filtered) => SmallVec::from_slice(&[filtered.filtered_at_node]),
+            Self::Triggered(triggered) => SmallVec::from_slice(&[triggered.cancelled_at_node]),
+            Self::Supplanted(supplanted) => SmallVec::from_slice(&[
+                supplanted.supplanted_at_node,
+                supplanted.supplanted_by_node,
+            ]),
+            Self::InvalidSpan(span) => [Some(span.from_point), span.to_point]
+                .into_iter()
+                .flatten()
+                .collect(),
+            Self::CircularCollect(circular) => {
+                circular.conflicts.iter().flatten().copied().collect()
+            }
+            Self::Livelock(livelock) => livelock.operations.iter().map(|op| op.operation).collect(),
+            Self::Broken(broken) => SmallVec::from_slice(&[broken.node]),
+        }
+    }
+}
+
+impl Display for CancellationCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.fmt_with_labels(&OperationLabels::default(), f)
+    }
+}
+
 /// A variant of [`CancellationCause`]
 #[derive(Debug)]
 pub struct TriggeredCancellation {
@@ -181,6 +313,9 @@ pub struct TriggeredCancellation {
     pub cancelled_at_node: Entity,
     /// The value that triggered the cancellation, if one was provided.
     pub value: Option<String>,
+    /// The message that triggered the cancellation, if one was provided. This
+    /// can be downcast into the message type of the cancel operation.
+    pub message: Option<Arc<dyn Any + Send + Sync>>,
 }
 
 impl From<TriggeredCancellation> for CancellationCause {
@@ -232,15 +367,23 @@ impl Cancel {
         self
     }
 
-    pub(crate) fn trigger(self, world: &mut World, roster: &mut OperationRoster) {
+    pub(crate) fn trigger(mut self, world: &mut World, roster: &mut OperationRoster) {
+        let operations = self.cancellation.cause.operations();
+        self.cancellation.labels.capture(operations, world);
+        record_cancellation(self.origin, world);
+        let _span = cancel_span(
+            self.origin,
+            self.target,
+            self.session,
+            &self.cancellation.cause,
+            world,
+        )
+        .entered();
         if let Err(failure) = self.try_trigger(world, roster) {
             // We were unable to deliver the cancellation to the intended target.
             // We should move this into the unhandled errors resource so that it
             // does not get lost.
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .cancellations
-                .push(failure);
+            report_unhandled_error(failure, world);
         }
     }
 
@@ -322,6 +465,12 @@ impl From<CircularCollect> for CancellationCause {
     }
 }
 
+impl From<Livelock> for CancellationCause {
+    fn from(value: Livelock) -> Self {
+        CancellationCause::Livelock(value)
+    }
+}
+
 pub trait ManageCancellation {
     /// Have this node emit a signal to cancel the current scope.
     fn emit_cancel(
@@ -346,10 +495,7 @@ impl<'w> ManageCancellation for EntityWorldMut<'w> {
             // procedure. We should move this into the unhandled errors resource
             // so that it does not get lost.
             self.world_scope(move |world| {
-                world
-                    .get_resource_or_insert_with(UnhandledErrors::default)
-                    .cancellations
-                    .push(failure);
+                report_unhandled_error(failure, world);
             });
         }
     }
@@ -364,10 +510,7 @@ impl<'w> ManageCancellation for EntityWorldMut<'w> {
             // procedure. We should move this into the unhandled errors resource
             // so that it does not get lost.
             self.world_scope(move |world| {
-                world
-                    .get_resource_or_insert_with(UnhandledErrors::default)
-                    .cancellations
-                    .push(failure);
+                report_unhandled_error(failure, world);
             });
         }
     }
@@ -382,10 +525,8 @@ pub fn try_emit_broken(
     if let Some(mut source_mut) = world.get_entity_mut(source) {
         source_mut.emit_broken(backtrace, roster);
     } else {
-        world
-            .get_resource_or_insert_with(UnhandledErrors::default)
-            .cancellations
-            .push(CancelFailure {
+        report_unhandled_error(
+            CancelFailure {
                 error: OperationError::Broken(Some(Backtrace::new())),
                 cancel: Cancel {
                     origin: source,
@@ -397,7 +538,9 @@ pub fn try_emit_broken(
                     }
                     .into(),
                 },
-            });
+            },
+            world,
+        );
     }
 }
 
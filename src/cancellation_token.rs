@@ -0,0 +1,296 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use tokio::sync::Notify;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    cmp::Ordering as CmpOrdering,
+    collections::BinaryHeap,
+    sync::{Condvar, Once, OnceLock},
+    time::Instant,
+};
+
+/// Lets an async service, callback, or map find out that the session it is
+/// working on has been cancelled, or that the task is being stopped for any
+/// other reason.
+///
+/// By default a task is dropped as soon as it is stopped, so the future will
+/// never get a chance to react. A task that needs to release external resources
+/// (e.g. tell a door to close) can opt into a grace period with
+/// [`Self::request_grace_period`]. When the task is stopped, this token will be
+/// marked as cancelled and the task will be allowed to keep running until it
+/// finishes or until the grace period runs out, whichever comes first. Any
+/// response that the task produces during its grace period is discarded.
+///
+/// ```
+/// use bevy_impulse::prelude::*;
+/// use std::time::Duration;
+///
+/// async fn open_door(srv: AsyncService<()>) {
+///     srv.cancellation_token.request_grace_period(Duration::from_secs(2));
+///     // ... send the door request ...
+///     srv.cancellation_token.cancelled().await;
+///     // ... send "release door" ...
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationTokenInner>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationTokenInner {
+    cancelled: AtomicBool,
+    grace_period: Mutex<Option<Duration>>,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether the task has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Wait until the task is cancelled. Unless a grace period was requested,
+    /// the task will usually be dropped before this gets a chance to finish.
+    pub async fn cancelled(&self) {
+        let notified = self.inner.notify.notified();
+        tokio::pin!(notified);
+        // Register for the notification before checking the flag so that a
+        // cancellation in between cannot be missed.
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// Ask for the task to be given up to `duration` to clean up after it has
+    /// been cancelled. Calling this again replaces the earlier request.
+    /// Requests made after the task was cancelled have no effect.
+    pub fn request_grace_period(&self, duration: Duration) {
+        *self.inner.grace_period.lock().unwrap() = Some(duration);
+    }
+
+    /// The grace period that was requested for the task, if any.
+    pub fn grace_period(&self) -> Option<Duration> {
+        *self.inner.grace_period.lock().unwrap()
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+        self.inner.notify.notify_waiters();
+    }
+}
+
+/// Wait until the grace period of a cancelled task has run out.
+pub(crate) async fn grace_period_expired(duration: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let (sender, receiver) = futures::channel::oneshot::channel::<()>();
+        GracePeriodTimer::get().schedule(Instant::now() + duration, sender);
+        receiver.await.ok();
+    }
+
+    // Threads are not available on wasm, so tasks are not given a grace
+    // period there.
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = duration;
+    }
+}
+
+/// A single thread that signals the end of every grace period, so cancelling
+/// many tasks at once does not start a thread for each of them.
+#[cfg(not(target_arch = "wasm32"))]
+struct GracePeriodTimer {
+    deadlines: Mutex<BinaryHeap<Deadline>>,
+    changed: Condvar,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GracePeriodTimer {
+    fn get() -> &'static Self {
+        static TIMER: OnceLock<GracePeriodTimer> = OnceLock::new();
+        static STARTED: Once = Once::new();
+        let timer = TIMER.get_or_init(|| GracePeriodTimer {
+            deadlines: Default::default(),
+            changed: Condvar::new(),
+        });
+        STARTED.call_once(|| {
+            std::thread::Builder::new()
+                .name("bevy_impulse grace periods".to_owned())
+                .spawn(|| timer.run())
+                .expect("failed to start the grace period timer thread");
+        });
+        timer
+    }
+
+    fn schedule(&self, at: Instant, sender: futures::channel::oneshot::Sender<()>) {
+        self.deadlines.lock().unwrap().push(Deadline { at, sender });
+        self.changed.notify_one();
+    }
+
+    fn run(&self) {
+        let mut deadlines = self.deadlines.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            while deadlines.peek().is_some_and(|next| next.at <= now) {
+                if let Some(expired) = deadlines.pop() {
+                    expired.sender.send(()).ok();
+                }
+            }
+
+            deadlines = match deadlines.peek() {
+                Some(next) => {
+                    let wait = next.at - now;
+                    self.changed.wait_timeout(deadlines, wait).unwrap().0
+                }
+                None => self.changed.wait(deadlines).unwrap(),
+            };
+        }
+    }
+}
+
+/// A grace period that is waiting to run out. The earliest deadline is the
+/// greatest so that it comes first out of a [`BinaryHeap`].
+#[cfg(not(target_arch = "wasm32"))]
+struct Deadline {
+    at: Instant,
+    sender: futures::channel::oneshot::Sender<()>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PartialEq for Deadline {
+    fn eq(&self, other: &Self) -> bool {
+        self.at == other.at
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Eq for Deadline {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PartialOrd for Deadline {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Ord for Deadline {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        other.at.cmp(&self.at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_cancellation_grace_period() {
+        let mut context = TestingContext::minimal_plugins();
+        let released = Arc::new(Mutex::new(false));
+        let (started_sender, started_receiver) = futures::channel::oneshot::channel::<()>();
+        let started_sender = Arc::new(Mutex::new(Some(started_sender)));
+        let started_receiver = Arc::new(Mutex::new(Some(started_receiver)));
+
+        let door = {
+            let released = Arc::clone(&released);
+            context.command(|commands| {
+                commands.spawn_service(move |In(srv): AsyncServiceInput<()>| {
+                    let released = Arc::clone(&released);
+                    let started = started_sender.lock().unwrap().take();
+                    async move {
+                        srv.cancellation_token
+                            .request_grace_period(Duration::from_secs(5));
+                        if let Some(started) = started {
+                            started.send(()).ok();
+                        }
+                        srv.cancellation_token.cancelled().await;
+                        assert!(srv.cancellation_token.is_cancelled());
+                        *released.lock().unwrap() = true;
+                    }
+                })
+            })
+        };
+
+        // The workflow terminates as soon as the door task has started, which
+        // stops the door task while it is still waiting.
+        let workflow = context.spawn_io_workflow(|scope: Scope<(), ()>, builder| {
+            scope.input.chain(builder).fork_clone((
+                |chain: Chain<()>| chain.then(door).unused(),
+                |chain: Chain<()>| {
+                    chain
+                        .map_async(move |_| {
+                            let started = started_receiver.lock().unwrap().take();
+                            async move {
+                                if let Some(started) = started {
+                                    started.await.ok();
+                                }
+                            }
+                        })
+                        .connect(scope.terminate)
+                },
+            ));
+        });
+
+        let mut promise =
+            context.command(|commands| commands.request((), workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some());
+
+        for _ in 0..100 {
+            if *released.lock().unwrap() {
+                break;
+            }
+            context.run(1);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(*released.lock().unwrap());
+        assert!(context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_grace_periods_share_a_timer() {
+        let expired = (0..1000).map(|i| {
+            let duration = Duration::from_millis(i % 50);
+            async move {
+                let start = std::time::Instant::now();
+                super::grace_period_expired(duration).await;
+                start.elapsed() >= duration
+            }
+        });
+        let waited = futures::executor::block_on(futures::future::join_all(expired));
+        assert!(waited.into_iter().all(|waited| waited));
+    }
+}
@@ -21,16 +21,16 @@ use bevy_ecs::prelude::Entity;
 
 use smallvec::SmallVec;
 
-use std::error::Error;
+use std::{error::Error, sync::Arc};
 
 use crate::{
     make_option_branching, make_result_branching, Accessing, AddOperation, AsMap, Buffer,
     BufferKey, BufferKeys, Bufferable, Buffering, Builder, Collect, CreateCancelFilter,
     CreateDisposalFilter, ForkTargetStorage, Gate, GateRequest, InputSlot, IntoAsyncMap,
     IntoBlockingCallback, IntoBlockingMap, Node, Noop, OperateBufferAccess, OperateCancel,
-    OperateDynamicGate, OperateQuietCancel, OperateSplit, OperateStaticGate, Output, ProvideOnce,
-    Provider, Scope, ScopeSettings, Sendish, Service, Spread, StreamPack, StreamTargetMap, Trim,
-    TrimBranch, UnusedTarget,
+    OperateDynamicGate, OperateQuietCancel, OperateSplit, OperateStaticGate, OperationLabel,
+    Output, ProvideOnce, Provider, Scope, ScopeSettings, Sendish, Service, Spread, StreamPack,
+    StreamTargetMap, Trim, TrimBranch, UnusedTarget,
 };
 
 pub mod fork_clone_builder;
@@ -727,6 +727,32 @@ impl<'w, 's, 'a, 'b, T: 'static + Send + Sync> Chain<'w, 's, 'a, 'b, T> {
     pub fn target(&self) -> Entity {
         self.target
     }
+
+    /// Give a human-readable [label](OperationLabel) to the next operation
+    /// that gets chained on, i.e. the operation that will receive the latest
+    /// output of this chain. If the chain gets [connected](Self::connect) to
+    /// an input slot instead, the label goes to the operation of that input
+    /// slot, unless that operation already has a label.
+    ///
+    /// ```
+    /// use bevy_impulse::prelude::*;
+    ///
+    /// fn build(scope: Scope<u32, u32>, builder: &mut Builder) {
+    ///     scope
+    ///         .input
+    ///         .chain(builder)
+    ///         .label("double")
+    ///         .map_block(|value| 2 * value)
+    ///         .connect(scope.terminate);
+    /// }
+    /// ```
+    pub fn label(self, label: impl Into<Arc<str>>) -> Self {
+        self.builder
+            .commands
+            .entity(self.target)
+            .insert(OperationLabel::new(label));
+        self
+    }
 }
 
 impl<'w, 's, 'a, 'b, T, E> Chain<'w, 's, 'a, 'b, Result<T, E>>
@@ -26,7 +26,9 @@ use tokio::sync::mpsc::{
 
 use std::sync::Arc;
 
-use crate::{OperationError, OperationRoster, Promise, Provider, RequestExt, StreamPack};
+use crate::{
+    BackpressurePolicy, OperationError, OperationRoster, Promise, Provider, RequestExt, StreamPack,
+};
 
 /// Provides asynchronous access to the [`World`], allowing you to issue queries
 /// or commands and then await the result.
@@ -85,9 +87,19 @@ impl Channel {
                 source,
                 session,
                 sender,
+                backpressure: BackpressurePolicy::Unbounded,
             }),
         }
     }
+
+    /// Set the [`BackpressurePolicy`] that stream channels created from this
+    /// channel will start with.
+    pub(crate) fn with_stream_backpressure(mut self, policy: BackpressurePolicy) -> Self {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.backpressure = policy;
+        }
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -95,6 +107,7 @@ pub struct InnerChannel {
     pub(crate) source: Entity,
     pub(crate) session: Entity,
     pub(crate) sender: TokioSender<ChannelItem>,
+    pub(crate) backpressure: BackpressurePolicy,
 }
 
 impl InnerChannel {
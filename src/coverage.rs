@@ -0,0 +1,376 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+//! Find out which parts of your workflows have been exercised.
+//!
+//! Add a [`CoveragePlugin`] to your app, insert a [`WorkflowCoverage`]
+//! resource, or use
+//! [`TestingContext::enable_coverage`](crate::testing::TestingContext::enable_coverage)
+//! to start counting how many inputs each operation receives. Afterwards use
+//! [`WorkflowCoverage::report`] to see which labeled operations, fork branches,
+//! and streams were reached and which were never reached.
+
+use bevy_app::{App, Last, Plugin};
+use bevy_ecs::{
+    prelude::{Entity, Resource, World},
+    world::EntityWorldMut,
+};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use crate::{
+    remove_despawned_system, retain_spawned, ForkTargetStorage, GetBufferedSessionsFn,
+    OperationLabel, StreamTargetMap, UnusedTarget,
+};
+
+/// Starts recording coverage with a [`WorkflowCoverage`] resource that
+/// forgets each operation once it is despawned, e.g. when an impulse chain
+/// finishes or a workflow is dropped.
+#[derive(Default)]
+pub struct CoveragePlugin {}
+
+impl Plugin for CoveragePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorkflowCoverage>().add_systems(
+            Last,
+            remove_despawned_system(WorkflowCoverage::remove_despawned),
+        );
+    }
+}
+
+/// Insert this resource to record which operations receive inputs. Nothing is
+/// recorded while this resource is absent.
+#[derive(Resource, Default, Debug)]
+pub struct WorkflowCoverage {
+    inputs: HashMap<Entity, usize>,
+}
+
+impl WorkflowCoverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many inputs an operation has received since recording began.
+    pub fn inputs_received(&self, operation: Entity) -> usize {
+        self.inputs.get(&operation).copied().unwrap_or(0)
+    }
+
+    /// Forget everything that has been recorded so far.
+    pub fn clear(&mut self) {
+        self.inputs.clear();
+    }
+
+    /// Free the counts of despawned operations, which no report includes.
+    pub fn remove_despawned(&mut self, world: &World) {
+        retain_spawned(&mut self.inputs, world);
+    }
+
+    /// Create a report for every [labeled](OperationLabel) operation that
+    /// currently exists in the world. Operations that were created from a
+    /// diagram are labeled by their op id, so a diagram is fully covered by
+    /// this report. Operations of Rust workflows only appear if they were given
+    /// a label.
+    ///
+    /// If multiple operations share the same label, e.g. because the same
+    /// diagram was built more than once, their coverage is combined.
+    pub fn report(&self, world: &mut World) -> CoverageReport {
+        let mut query = world.query::<(
+            Entity,
+            &OperationLabel,
+            Option<&ForkTargetStorage>,
+            Option<&StreamTargetMap>,
+            Option<&GetBufferedSessionsFn>,
+        )>();
+
+        let mut report = CoverageReport::default();
+        for (operation, label, fork, streams, buffer) in query.iter(world) {
+            let coverage = report.operations.entry(label.to_string()).or_default();
+            coverage.reached += self.inputs_received(operation);
+
+            // The targets of a buffer are its listeners rather than branches.
+            let branches = fork
+                .filter(|fork| buffer.is_none() && fork.0.len() > 1)
+                .map(|fork| fork.0.as_slice())
+                .unwrap_or(&[]);
+            for (index, target) in branches.iter().enumerate() {
+                let reached = self.inputs_received(*target);
+                match coverage.branches.get_mut(index) {
+                    Some(branch) => branch.reached += reached,
+                    None => coverage.branches.push(BranchCoverage {
+                        index,
+                        target: world.get::<OperationLabel>(*target).map(|l| l.to_string()),
+                        reached,
+                    }),
+                }
+            }
+
+            let Some(streams) = streams else {
+                continue;
+            };
+            let named = streams
+                .named
+                .iter()
+                .map(|(name, (_, target))| (Some(name.to_string()), *target));
+            let anonymous = streams.anonymous.values().map(|target| (None, *target));
+            for (name, target) in named.chain(anonymous) {
+                if world.get::<UnusedTarget>(target).is_some() {
+                    // Nothing is listening to this stream, so there is nothing
+                    // to cover.
+                    continue;
+                }
+
+                let reached = self.inputs_received(target);
+                match coverage.streams.iter_mut().find(|s| s.name == name) {
+                    Some(stream) => stream.reached += reached,
+                    None => coverage.streams.push(StreamCoverage { name, reached }),
+                }
+            }
+        }
+
+        for coverage in report.operations.values_mut() {
+            coverage.streams.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+
+        report
+    }
+}
+
+/// Coverage of the labeled operations in a world, keyed by their labels.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub operations: BTreeMap<String, OperationCoverage>,
+}
+
+impl CoverageReport {
+    /// Iterate over the labels of operations that never received an input.
+    pub fn unreached(&self) -> impl Iterator<Item = &str> {
+        self.operations
+            .iter()
+            .filter(|(_, coverage)| coverage.reached == 0)
+            .map(|(label, _)| label.as_str())
+    }
+
+    /// Iterate over the fork branches that were never taken, along with the
+    /// label of the operation that they branch out of.
+    pub fn untaken_branches(&self) -> impl Iterator<Item = (&str, &BranchCoverage)> {
+        self.operations.iter().flat_map(|(label, coverage)| {
+            coverage
+                .branches
+                .iter()
+                .filter(|branch| branch.reached == 0)
+                .map(|branch| (label.as_str(), branch))
+        })
+    }
+
+    /// Iterate over the streams that never produced a message, along with the
+    /// label of the operation that they come out of.
+    pub fn silent_streams(&self) -> impl Iterator<Item = (&str, &StreamCoverage)> {
+        self.operations.iter().flat_map(|(label, coverage)| {
+            coverage
+                .streams
+                .iter()
+                .filter(|stream| stream.reached == 0)
+                .map(|stream| (label.as_str(), stream))
+        })
+    }
+
+    /// Check whether every operation, branch, and stream was reached.
+    pub fn is_complete(&self) -> bool {
+        self.unreached().next().is_none()
+            && self.untaken_branches().next().is_none()
+            && self.silent_streams().next().is_none()
+    }
+}
+
+impl Display for CoverageReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (label, coverage) in &self.operations {
+            writeln!(f, "{label}: {}", coverage.reached)?;
+            for branch in &coverage.branches {
+                write!(f, "  branch {}", branch.index)?;
+                if let Some(target) = &branch.target {
+                    write!(f, " -> {target}")?;
+                }
+                writeln!(f, ": {}", branch.reached)?;
+            }
+            for stream in &coverage.streams {
+                let name = stream.name.as_deref().unwrap_or("<anonymous>");
+                writeln!(f, "  stream {name}: {}", stream.reached)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Coverage of one labeled operation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperationCoverage {
+    /// How many inputs the operation received.
+    pub reached: usize,
+    /// The branches coming out of the operation, if it forks.
+    pub branches: Vec<BranchCoverage>,
+    /// The streams coming out of the operation.
+    pub streams: Vec<StreamCoverage>,
+}
+
+/// Coverage of one branch coming out of a fork, e.g. the `ok` and `err`
+/// branches of a fork result. A branch is reached when its target receives an
+/// input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BranchCoverage {
+    /// The position of the branch in the fork, e.g. 0 for `ok` and 1 for `err`.
+    pub index: usize,
+    /// The label of the operation that the branch leads to, if it has one.
+    pub target: Option<String>,
+    pub reached: usize,
+}
+
+/// Coverage of one stream coming out of an operation. Anonymous streams of
+/// the same operation are counted together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamCoverage {
+    /// The name of the stream, or [`None`] for anonymous streams.
+    pub name: Option<String>,
+    /// How many messages the stream delivered.
+    pub reached: usize,
+}
+
+/// Record that an operation has received an input.
+pub(crate) fn record_coverage(operation: &mut EntityWorldMut) {
+    if !operation.world().contains_resource::<WorkflowCoverage>() {
+        return;
+    }
+
+    let id = operation.id();
+    operation.world_scope(|world| {
+        *world
+            .resource_mut::<WorkflowCoverage>()
+            .inputs
+            .entry(id)
+            .or_default() += 1;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, OperationLabel, WorkflowCoverage};
+    use bevy_hierarchy::DespawnRecursiveExt;
+
+    #[test]
+    fn test_coverage_of_branches() {
+        let mut context = TestingContext::minimal_plugins();
+        context.enable_coverage();
+
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .label("check")
+                .map_block(|value: u32| if value < 10 { Ok(value) } else { Err(value) })
+                .label("fork")
+                .fork_result(
+                    |ok| {
+                        ok.label("small")
+                            .map_block(|v| v + 1)
+                            .connect(scope.terminate)
+                    },
+                    |err| {
+                        err.label("large")
+                            .map_block(|v| v - 1)
+                            .connect(scope.terminate)
+                    },
+                );
+        });
+
+        let mut promise =
+            context.command(|commands| commands.request(1_u32, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some_and(|v| v == 2));
+
+        let report = context.coverage_report().unwrap();
+        assert_eq!(report.operations["check"].reached, 1);
+        assert_eq!(report.operations["small"].reached, 1);
+        assert_eq!(report.unreached().collect::<Vec<_>>(), ["large"]);
+
+        let fork = &report.operations["fork"];
+        assert_eq!(fork.branches.len(), 2);
+        assert_eq!(fork.branches[0].reached, 1);
+        assert_eq!(fork.branches[1].target.as_deref(), Some("large"));
+        assert_eq!(report.untaken_branches().count(), 1);
+        assert!(!report.is_complete());
+
+        let mut promise =
+            context.command(|commands| commands.request(20_u32, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some_and(|v| v == 19));
+
+        let report = context.coverage_report().unwrap();
+        assert!(report.is_complete());
+    }
+
+    #[test]
+    fn test_coverage_forgets_despawned_workflows() {
+        let mut context = TestingContext::minimal_plugins();
+        context.enable_coverage();
+
+        let make_workflow = |context: &mut TestingContext, label: &'static str| {
+            context.spawn_io_workflow(move |scope: Scope<u32, u32>, builder| {
+                scope
+                    .input
+                    .chain(builder)
+                    .label(label)
+                    .map_block(|v: u32| v)
+                    .connect(scope.terminate);
+            })
+        };
+        let kept = make_workflow(&mut context, "kept");
+        let dropped = make_workflow(&mut context, "dropped");
+
+        for workflow in [kept, dropped, kept] {
+            let mut promise =
+                context.command(|commands| commands.request(0_u32, workflow).take_response());
+            context.run_with_conditions(&mut promise, Duration::from_secs(2));
+            assert!(promise.take().available().is_some());
+        }
+
+        let report = context.coverage_report().unwrap();
+        assert_eq!(report.operations["kept"].reached, 2);
+        assert_eq!(report.operations["dropped"].reached, 1);
+        let dropped_operation = context
+            .app
+            .world
+            .query::<(Entity, &OperationLabel)>()
+            .iter(&context.app.world)
+            .find(|(_, label)| label.to_string() == "dropped")
+            .map(|(e, _)| e)
+            .unwrap();
+
+        context.command(|commands| {
+            commands.entity(dropped.provider()).despawn_recursive();
+        });
+        context.run(1);
+
+        let report = context.coverage_report().unwrap();
+        assert_eq!(report.operations.keys().collect::<Vec<_>>(), ["kept"]);
+        assert_eq!(report.operations["kept"].reached, 2);
+        let coverage = context.app.world.resource::<WorkflowCoverage>();
+        assert_eq!(coverage.inputs_received(dropped_operation), 0);
+    }
+}
@@ -0,0 +1,562 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+//! Step through workflows one operation at a time.
+//!
+//! Insert a [`WorkflowDebugger`] resource to enable debugging. Whenever a
+//! session reaches a breakpoint, the operation is held by the debugger instead
+//! of being executed, and the session stays paused until it is resumed,
+//! stepped, or aborted. While a session is paused, every other operation that
+//! becomes ready for that session is held as well.
+
+use bevy_ecs::prelude::{Entity, Resource, World};
+
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+use crate::{
+    queue_priority, Cancellation, InputTypeIndicator, ManageCancellation, OperationLabel,
+    OperationRoster,
+};
+
+/// Decide when the [`WorkflowDebugger`] should pause a session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DebugMode {
+    /// Pause a session when it is about to execute an operation that has a
+    /// breakpoint.
+    #[default]
+    Breakpoints,
+    /// Pause a session before every operation that it executes.
+    EveryOperation,
+}
+
+/// An operation that is being held by the [`WorkflowDebugger`].
+#[derive(Clone, Debug)]
+pub struct PausedOperation {
+    /// The operation that is waiting to be executed.
+    pub operation: Entity,
+    /// The session whose input the operation will take when it executes.
+    pub session: Entity,
+    /// The label of the operation, if it has one.
+    pub label: Option<OperationLabel>,
+    /// The name of the input type that the operation is waiting to take.
+    pub input_type: &'static str,
+}
+
+impl PausedOperation {
+    /// Look at the input that the operation will take for this session when it
+    /// executes. Use [`Any::downcast_ref`] to access the value.
+    pub fn pending_input<'w>(&self, world: &'w World) -> Option<&'w dyn Any> {
+        world.get::<InputTypeIndicator>(self.operation)?.peek_input(
+            self.operation,
+            self.session,
+            world,
+        )
+    }
+
+    /// Serialize the input that the operation will take when it executes, if
+    /// its type was registered as serializable.
+    #[cfg(feature = "diagram")]
+    pub fn pending_input_json(
+        &self,
+        world: &World,
+        registry: &crate::DiagramElementRegistry,
+    ) -> Option<crate::JsonMessage> {
+        registry.serialize_any(self.pending_input(world)?)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum DebugAction {
+    Resume(Entity),
+    Step(Entity),
+    Abort(Entity),
+}
+
+/// Insert this resource to pause sessions at breakpoints. Operations of a
+/// paused session are held here instead of in the [`OperationRoster`] until the
+/// session is resumed, stepped, or aborted. Those actions take effect during
+/// the next flush.
+#[derive(Resource, Default, Debug)]
+pub struct WorkflowDebugger {
+    pub mode: DebugMode,
+    breakpoints: HashSet<Entity>,
+    labeled_breakpoints: HashSet<Arc<str>>,
+    paused: VecDeque<PausedOperation>,
+    paused_sessions: HashSet<Entity>,
+    /// Operations that have been released and may execute once for a session
+    /// without being held again.
+    released: HashMap<(Entity, Entity), usize>,
+    actions: Vec<DebugAction>,
+}
+
+impl WorkflowDebugger {
+    pub fn new(mode: DebugMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Pause any session that is about to execute this operation.
+    pub fn set_breakpoint(&mut self, operation: Entity) {
+        self.breakpoints.insert(operation);
+    }
+
+    /// Pause any session that is about to execute an operation with this
+    /// [label](OperationLabel). Operations that were created from a diagram are
+    /// labeled with their op id, prefixed by the namespaces of any sections or
+    /// scopes that contain them, e.g. `my_section:my_op`.
+    pub fn set_breakpoint_by_label(&mut self, label: impl Into<Arc<str>>) {
+        self.labeled_breakpoints.insert(label.into());
+    }
+
+    pub fn clear_breakpoint(&mut self, operation: Entity) {
+        self.breakpoints.remove(&operation);
+    }
+
+    pub fn clear_breakpoint_by_label(&mut self, label: &str) {
+        self.labeled_breakpoints.remove(label);
+    }
+
+    pub fn clear_all_breakpoints(&mut self) {
+        self.breakpoints.clear();
+        self.labeled_breakpoints.clear();
+    }
+
+    /// Iterate over the operations that are currently being held.
+    pub fn paused(&self) -> impl Iterator<Item = &PausedOperation> {
+        self.paused.iter()
+    }
+
+    /// Iterate over the sessions that are currently paused.
+    pub fn paused_sessions(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.paused_sessions.iter().copied()
+    }
+
+    pub fn is_paused(&self, session: Entity) -> bool {
+        self.paused_sessions.contains(&session)
+    }
+
+    /// Let a paused session continue until it reaches another breakpoint.
+    pub fn resume(&mut self, session: Entity) {
+        self.actions.push(DebugAction::Resume(session));
+    }
+
+    /// Let a paused session execute the next operation that it is holding, then
+    /// pause again.
+    pub fn step(&mut self, session: Entity) {
+        self.actions.push(DebugAction::Step(session));
+    }
+
+    /// Cancel a paused session. The operations that were held for the session
+    /// will be cleaned up by the cancellation.
+    pub fn abort(&mut self, session: Entity) {
+        self.actions.push(DebugAction::Abort(session));
+    }
+
+    fn is_breakpoint(&self, operation: Entity, label: Option<&OperationLabel>) -> bool {
+        self.breakpoints.contains(&operation)
+            || label.is_some_and(|label| self.labeled_breakpoints.contains(&**label))
+    }
+
+    /// Let a held operation execute once for its session. Returns the
+    /// operation so it can be queued again.
+    fn release(&mut self, paused: PausedOperation) -> PausedOperation {
+        *self
+            .released
+            .entry((paused.operation, paused.session))
+            .or_default() += 1;
+        paused
+    }
+
+    fn take_paused(&mut self, session: Entity) -> Vec<PausedOperation> {
+        let mut taken = Vec::new();
+        self.paused.retain(|p| {
+            if p.session == session {
+                taken.push(p.clone());
+                return false;
+            }
+            true
+        });
+        taken
+    }
+}
+
+/// Check whether the debugger wants to hold onto an operation instead of
+/// letting it execute. If this returns true, the operation has been moved into
+/// the debugger.
+pub(crate) fn hold_for_debugger(operation: Entity, world: &mut World) -> bool {
+    let Some(debugger) = world.get_resource::<WorkflowDebugger>() else {
+        return false;
+    };
+    let Some(operation_ref) = world.get_entity(operation) else {
+        return false;
+    };
+    let Some(indicator) = operation_ref.get::<InputTypeIndicator>() else {
+        return false;
+    };
+    let sessions = indicator.sessions(&operation_ref);
+
+    // An input that was released by the debugger gets to execute before any
+    // other input of the operation.
+    let released = sessions
+        .iter()
+        .position(|session| debugger.released.contains_key(&(operation, *session)));
+    if let Some(index) = released {
+        let session = sessions[index];
+        let mut debugger = world.resource_mut::<WorkflowDebugger>();
+        if let Some(count) = debugger.released.get_mut(&(operation, session)) {
+            *count -= 1;
+            if *count == 0 {
+                debugger.released.remove(&(operation, session));
+            }
+        }
+        prioritize_input(operation, index, world);
+        return false;
+    }
+
+    // Skip over the inputs that are already being held for their sessions.
+    let mut held: HashMap<Entity, usize> = HashMap::new();
+    for paused in debugger.paused.iter().filter(|p| p.operation == operation) {
+        *held.entry(paused.session).or_default() += 1;
+    }
+    let next = sessions
+        .iter()
+        .position(|session| match held.get_mut(session) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        });
+    let Some(index) = next else {
+        return false;
+    };
+    let session = sessions[index];
+    let label = operation_ref.get::<OperationLabel>();
+
+    let hold = debugger.mode == DebugMode::EveryOperation
+        || debugger.paused_sessions.contains(&session)
+        || debugger.is_breakpoint(operation, label);
+    if !hold {
+        prioritize_input(operation, index, world);
+        return false;
+    }
+
+    let paused = PausedOperation {
+        operation,
+        session,
+        label: label.cloned(),
+        input_type: indicator.name,
+    };
+    let mut debugger = world.resource_mut::<WorkflowDebugger>();
+    debugger.paused.push_back(paused);
+    debugger.paused_sessions.insert(session);
+    true
+}
+
+fn prioritize_input(operation: Entity, index: usize, world: &mut World) {
+    if index == 0 {
+        return;
+    }
+
+    let Some(mut operation_mut) = world.get_entity_mut(operation) else {
+        return;
+    };
+    let Some(indicator) = operation_mut.get::<InputTypeIndicator>().copied() else {
+        return;
+    };
+    indicator.prioritize(&mut operation_mut, index);
+}
+
+/// Forget anything that the debugger is holding for an operation in a session
+/// whose inputs are being cleaned up, e.g. because the session was cancelled
+/// while it was paused. Once nothing is held for the session anymore, the
+/// session is no longer considered paused.
+pub(crate) fn release_cleaned_session(operation: Entity, session: Entity, world: &mut World) {
+    let Some(mut debugger) = world.get_resource_mut::<WorkflowDebugger>() else {
+        return;
+    };
+
+    debugger
+        .paused
+        .retain(|p| p.operation != operation || p.session != session);
+    debugger.released.remove(&(operation, session));
+    if !debugger.paused.iter().any(|p| p.session == session) {
+        debugger.paused_sessions.remove(&session);
+    }
+}
+
+/// Carry out any actions that were requested of the debugger since the last
+/// flush.
+pub(crate) fn apply_debugger_actions(world: &mut World, roster: &mut OperationRoster) {
+    let Some(debugger) = world.get_resource::<WorkflowDebugger>() else {
+        return;
+    };
+
+    // Operations of impulses are despawned without their inputs being cleaned
+    // up, so anything held for them is dropped here.
+    let despawned: Vec<_> = debugger
+        .paused
+        .iter()
+        .filter(|p| world.get_entity(p.operation).is_none())
+        .map(|p| (p.operation, p.session))
+        .collect();
+    for (operation, session) in despawned {
+        release_cleaned_session(operation, session, world);
+    }
+
+    let mut debugger = world.resource_mut::<WorkflowDebugger>();
+    if debugger.actions.is_empty() {
+        return;
+    }
+
+    let mut released = Vec::new();
+    let mut aborted = Vec::new();
+    for action in std::mem::take(&mut debugger.actions) {
+        match action {
+            DebugAction::Resume(session) => {
+                debugger.paused_sessions.remove(&session);
+                for paused in debugger.take_paused(session) {
+                    released.push(debugger.release(paused));
+                }
+            }
+            DebugAction::Step(session) => {
+                let next = debugger.paused.iter().position(|p| p.session == session);
+                if let Some(paused) = next.and_then(|i| debugger.paused.remove(i)) {
+                    released.push(debugger.release(paused));
+                }
+            }
+            DebugAction::Abort(session) => {
+                debugger.paused_sessions.remove(&session);
+                if let Some(paused) = debugger.take_paused(session).into_iter().next() {
+                    aborted.push(paused);
+                }
+            }
+        }
+    }
+
+    for paused in released {
+        let priority = queue_priority(paused.session, world);
+        roster.queue_with_priority(paused.operation, priority);
+    }
+
+    for paused in aborted {
+        let Some(mut operation_mut) = world.get_entity_mut(paused.operation) else {
+            continue;
+        };
+        let cancellation = Cancellation::triggered(
+            paused.operation,
+            Some("aborted by the workflow debugger".to_owned()),
+        );
+        operation_mut.emit_cancel(paused.session, cancellation, roster);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, WorkflowDebugger};
+
+    #[test]
+    fn test_step_through_breakpoint() {
+        let mut context = TestingContext::minimal_plugins();
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .map_block(|value: u32| value + 1)
+                .label("double")
+                .map_block(|value: u32| 2 * value)
+                .connect(scope.terminate);
+        });
+
+        let mut debugger = WorkflowDebugger::default();
+        debugger.set_breakpoint_by_label("double");
+        context.app.insert_resource(debugger);
+
+        let mut promise =
+            context.command(|commands| commands.request(1_u32, workflow).take_response());
+        context.run(2);
+        assert!(promise.peek().is_pending());
+
+        let debugger = context.app.world.resource::<WorkflowDebugger>();
+        let paused: Vec<_> = debugger.paused().cloned().collect();
+        assert_eq!(paused.len(), 1);
+        assert_eq!(paused[0].label.as_ref().unwrap().as_str(), "double");
+        let input = paused[0].pending_input(&context.app.world).unwrap();
+        assert_eq!(input.downcast_ref::<u32>(), Some(&2));
+
+        context
+            .app
+            .world
+            .resource_mut::<WorkflowDebugger>()
+            .step(paused[0].session);
+        context.run(2);
+        assert!(promise.peek().is_pending());
+
+        // The session should pause again at the next operation after stepping.
+        let session = paused[0].session;
+        let debugger = context.app.world.resource::<WorkflowDebugger>();
+        assert!(debugger.is_paused(session));
+        let paused: Vec<_> = debugger.paused().cloned().collect();
+        assert_eq!(paused.len(), 1);
+        let input = paused[0].pending_input(&context.app.world).unwrap();
+        assert_eq!(input.downcast_ref::<u32>(), Some(&4));
+
+        context
+            .app
+            .world
+            .resource_mut::<WorkflowDebugger>()
+            .resume(session);
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some_and(|v| v == 4));
+        assert!(context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_paused_session_does_not_hold_other_sessions() {
+        let mut context = TestingContext::minimal_plugins();
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .label("first")
+                .map_block(|value: u32| value + 1)
+                .label("second")
+                .map_block(|value: u32| 2 * value)
+                .connect(scope.terminate);
+        });
+
+        let mut debugger = WorkflowDebugger::default();
+        debugger.set_breakpoint_by_label("first");
+        context.app.insert_resource(debugger);
+
+        let mut paused_promise =
+            context.command(|commands| commands.request(1_u32, workflow).take_response());
+        context.run(2);
+        let paused_session = {
+            let debugger = context.app.world.resource::<WorkflowDebugger>();
+            debugger.paused().next().unwrap().session
+        };
+
+        // Step the paused session so that its input waits in the second
+        // operation, then let other sessions pass through the first one.
+        {
+            let mut debugger = context.app.world.resource_mut::<WorkflowDebugger>();
+            debugger.step(paused_session);
+            debugger.clear_all_breakpoints();
+        }
+        context.run(2);
+
+        let mut promise =
+            context.command(|commands| commands.request(10_u32, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some_and(|v| v == 22));
+        assert!(paused_promise.peek().is_pending());
+
+        let debugger = context.app.world.resource::<WorkflowDebugger>();
+        let paused: Vec<_> = debugger.paused().cloned().collect();
+        assert_eq!(paused.len(), 1);
+        assert_eq!(paused[0].session, paused_session);
+        assert_eq!(paused[0].label.as_ref().unwrap().as_str(), "second");
+        let input = paused[0].pending_input(&context.app.world).unwrap();
+        assert_eq!(input.downcast_ref::<u32>(), Some(&2));
+
+        context
+            .app
+            .world
+            .resource_mut::<WorkflowDebugger>()
+            .resume(paused_session);
+        context.run_with_conditions(&mut paused_promise, Duration::from_secs(2));
+        assert!(paused_promise.take().available().is_some_and(|v| v == 4));
+        assert!(context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_abort_paused_session() {
+        let mut context = TestingContext::minimal_plugins();
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .map_block(|value: u32| value + 1)
+                .connect(scope.terminate);
+        });
+
+        context
+            .app
+            .insert_resource(WorkflowDebugger::new(crate::DebugMode::EveryOperation));
+
+        let mut promise =
+            context.command(|commands| commands.request(1_u32, workflow).take_response());
+        context.run(2);
+        let session = {
+            let debugger = context.app.world.resource::<WorkflowDebugger>();
+            debugger.paused().next().unwrap().session
+        };
+        context
+            .app
+            .world
+            .resource_mut::<WorkflowDebugger>()
+            .abort(session);
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.peek().is_cancelled());
+    }
+
+    #[test]
+    fn test_cleaned_up_session_is_no_longer_paused() {
+        let mut context = TestingContext::minimal_plugins();
+        let delay = context.spawn_async_delayed_map(Duration::from_millis(10), |v: u32| v);
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            let inner = builder.create_io_scope(|scope: Scope<u32, u32>, builder| {
+                scope
+                    .input
+                    .chain(builder)
+                    .map_block(|value: u32| value + 1)
+                    .label("held")
+                    .connect(scope.terminate);
+            });
+
+            // The outer session finishes while the inner session is paused,
+            // which cleans up the inner session.
+            scope.input.chain(builder).fork_clone((
+                |chain: Chain<u32>| chain.connect(inner.input),
+                |chain: Chain<u32>| chain.then(delay).connect(scope.terminate),
+            ));
+            builder.connect(inner.output, scope.terminate);
+        });
+
+        let mut debugger = WorkflowDebugger::default();
+        debugger.set_breakpoint_by_label("held");
+        context.app.insert_resource(debugger);
+
+        let mut promise =
+            context.command(|commands| commands.request(1_u32, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some_and(|v| v == 1));
+
+        let debugger = context.app.world.resource::<WorkflowDebugger>();
+        assert_eq!(debugger.paused().count(), 0);
+        assert_eq!(debugger.paused_sessions().count(), 0);
+        assert!(debugger.released.is_empty());
+        assert!(context.no_unhandled_errors());
+    }
+}
@@ -0,0 +1,43 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+//! Pruning for resources that keep records keyed by entity, such as
+//! [`WorkflowMetrics`](crate::WorkflowMetrics) and
+//! [`WorkflowCoverage`](crate::WorkflowCoverage).
+
+use bevy_ecs::prelude::{Entity, Resource, World};
+
+use std::collections::HashMap;
+
+/// Drop every record whose entity no longer exists in the world.
+pub(crate) fn retain_spawned<V>(records: &mut HashMap<Entity, V>, world: &World) {
+    records.retain(|e, _| world.get_entity(*e).is_some());
+}
+
+/// Make a system that calls `prune` on the resource `R` whenever the resource
+/// is present.
+pub(crate) fn remove_despawned_system<R: Resource>(
+    prune: fn(&mut R, &World),
+) -> impl FnMut(&mut World) {
+    move |world: &mut World| {
+        if !world.contains_resource::<R>() {
+            return;
+        }
+
+        world.resource_scope::<R, _>(|world, mut resource| prune(&mut resource, world));
+    }
+}
@@ -16,17 +16,24 @@
 */
 
 mod buffer_schema;
+mod call_schema;
+mod cancellation;
+mod cleanup_schema;
+mod contract;
 mod fork_clone_schema;
 mod fork_result_schema;
 mod join_schema;
 mod node_schema;
 mod registration;
+mod report;
 mod scope_schema;
 mod section_schema;
 mod serialization;
+mod snapshot;
 mod split_schema;
 mod stream_out_schema;
 mod supported;
+mod trace;
 mod transform_schema;
 mod unzip_schema;
 mod workflow_builder;
@@ -34,18 +41,28 @@ mod workflow_builder;
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::system::Commands;
 use buffer_schema::{BufferAccessSchema, BufferSchema, ListenSchema};
+use call_schema::CallSchema;
+pub use call_schema::{ResolveService, ServiceCatalog, ServiceCatalogError};
+pub use cancellation::SerializedCancellationCause;
+pub use cleanup_schema::{CleanupSchema, CleanupTrigger, CleanupWorkflows};
+pub use contract::{ContractSide, ContractViolation};
 use fork_clone_schema::{DynForkClone, ForkCloneSchema, PerformForkClone};
 use fork_result_schema::{DynForkResult, ForkResultSchema};
 pub use join_schema::JoinOutput;
 use join_schema::{JoinSchema, SerializedJoinSchema};
 pub use node_schema::NodeSchema;
 pub use registration::*;
+pub use report::*;
 pub use scope_schema::*;
 pub use section_schema::*;
 pub use serialization::*;
+pub use snapshot::*;
 pub use split_schema::*;
 pub use stream_out_schema::*;
+pub use trace::*;
+pub(crate) use trace::{record_handoff, set_trace_source};
 use tracing::debug;
+pub use transform_schema::{CelLimits, CelProgram};
 use transform_schema::{TransformError, TransformSchema};
 use unzip_schema::UnzipSchema;
 pub use workflow_builder::*;
@@ -321,6 +338,45 @@ pub enum DiagramOperation {
     /// # Ok::<_, serde_json::Error>(())
     Node(NodeSchema),
 
+    /// Call a service that was added to the [`ServiceCatalog`] of the registry.
+    /// Any [`Service`] can be added to the catalog, including workflows that
+    /// were spawned from other diagrams, so this can be used to compose
+    /// diagrams without registering a node builder for each one.
+    ///
+    /// By default the service is looked up once while the workflow is being
+    /// built. Set `"resolve": "runtime"` to look up the service each time a
+    /// request arrives instead. If the service cannot be found at runtime, an
+    /// error message will be sent to `on_error`.
+    ///
+    /// Streams of the service can be connected with `stream_out`, just like
+    /// for `node` operations.
+    ///
+    /// # Examples
+    /// ```
+    /// # bevy_impulse::Diagram::from_json_str(r#"
+    /// {
+    ///     "version": "0.1.0",
+    ///     "start": "plan",
+    ///     "ops": {
+    ///         "plan": {
+    ///             "type": "call",
+    ///             "service": "path_planner",
+    ///             "next": "drive"
+    ///         },
+    ///         "drive": {
+    ///             "type": "call",
+    ///             "service": "navigation",
+    ///             "resolve": "runtime",
+    ///             "next": { "builtin": "terminate" },
+    ///             "on_error": { "builtin": "cancel" }
+    ///         }
+    ///     }
+    /// }
+    /// # "#)?;
+    /// # Ok::<_, serde_json::Error>(())
+    /// ```
+    Call(CallSchema),
+
     /// Connect the request to a registered section.
     ///
     /// ```
@@ -955,6 +1011,7 @@ impl BuildDiagramOperation for DiagramOperation {
         match self {
             Self::Buffer(op) => op.build_diagram_operation(id, builder, ctx),
             Self::BufferAccess(op) => op.build_diagram_operation(id, builder, ctx),
+            Self::Call(op) => op.build_diagram_operation(id, builder, ctx),
             Self::ForkClone(op) => op.build_diagram_operation(id, builder, ctx),
             Self::ForkResult(op) => op.build_diagram_operation(id, builder, ctx),
             Self::Join(op) => op.build_diagram_operation(id, builder, ctx),
@@ -1031,8 +1088,30 @@ pub struct Diagram {
     #[serde(default)]
     pub on_implicit_error: Option<NextOperation>,
 
+    /// The request message type of this diagram, given as the type name that
+    /// the message is registered under in the [`MessageRegistry`].
+    ///
+    /// When this is set, the request type used to spawn the workflow must
+    /// match it. If the workflow is spawned with [`JsonMessage`] requests then
+    /// each request will be validated against this type before it enters the
+    /// workflow, and invalid requests will cancel the workflow with a
+    /// [`ContractViolation`].
+    #[serde(default)]
+    pub input_schema: Option<String>,
+
+    /// The response message type of this diagram, given as the type name that
+    /// the message is registered under in the [`MessageRegistry`]. This works
+    /// the same way as `input_schema`, but for the final response.
+    #[serde(default)]
+    pub output_schema: Option<String>,
+
     /// Operations that define the workflow
     pub ops: Operations,
+
+    /// Cleanup workflows that run when a session of the workflow is finished.
+    /// Their names share a namespace with the operations in `ops`.
+    #[serde(default)]
+    pub cleanup: CleanupWorkflows,
 }
 
 impl Diagram {
@@ -1043,7 +1122,10 @@ impl Diagram {
             start,
             templates: Default::default(),
             on_implicit_error: Default::default(),
+            input_schema: Default::default(),
+            output_schema: Default::default(),
             ops: Default::default(),
+            cleanup: Default::default(),
         }
     }
 
@@ -1452,12 +1534,24 @@ pub enum DiagramErrorCode {
     #[error("cannot listen on these buffers to produce a request of [{0}]")]
     CannotListen(TypeInfo),
 
+    #[error("cannot run a cleanup workflow on these buffers with a request of [{0}]")]
+    CannotCleanup(TypeInfo),
+
+    #[error("cleanup workflow [{0}] has the same name as an operation in its scope")]
+    CleanupNameCollision(OperationName),
+
     #[error(transparent)]
     IncompatibleBuffers(#[from] IncompatibleLayout),
 
     #[error(transparent)]
     SectionError(#[from] SectionError),
 
+    #[error(transparent)]
+    ServiceCatalogError(#[from] ServiceCatalogError),
+
+    #[error("call operation [{0}] has an on_error target, but on_error is only used when resolve is runtime")]
+    CallOnErrorUnused(OperationName),
+
     #[error("one or more operation is missing inputs")]
     IncompleteDiagram,
 
@@ -1470,6 +1564,18 @@ pub enum DiagramErrorCode {
     #[error("a type being used in the diagram was not registered {0}")]
     UnregisteredType(TypeInfo),
 
+    #[error("no message is registered with the type name [{0}]")]
+    UnknownMessageTypeName(String),
+
+    #[error(
+        "the {side} type of the diagram is declared as {contract} but the workflow uses {actual}"
+    )]
+    ContractMismatch {
+        side: ContractSide,
+        contract: TypeInfo,
+        actual: TypeInfo,
+    },
+
     #[error("The build of the workflow came to a halt, reasons:\n{reasons:?}")]
     BuildHalted {
         /// Reasons that operations were unable to make progress building
@@ -0,0 +1,498 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use bevy_ecs::prelude::Resource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error as ThisError;
+
+use crate::{Builder, Service, StreamPack};
+
+use super::{
+    is_default, BuildDiagramOperation, BuildStatus, DiagramContext, DiagramErrorCode, DynNode,
+    DynOutput, MissingStream, NextOperation, OperationName, TypeInfo,
+};
+
+/// A catalog of services that diagrams can call by name using the `call`
+/// operation.
+///
+/// Clones of a catalog share the same entries, so the same catalog can be
+/// given to a [`DiagramElementRegistry`](super::DiagramElementRegistry) for
+/// building diagrams and inserted into the [`World`](bevy_ecs::prelude::World)
+/// as a resource so that systems can add or replace services later.
+#[derive(Resource, Clone, Default)]
+pub struct ServiceCatalog {
+    entries: Arc<RwLock<HashMap<Arc<str>, CatalogEntry>>>,
+}
+
+impl ServiceCatalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a service to the catalog. If another service was already using this
+    /// name, it will be replaced.
+    ///
+    /// Any [`Service`] can be added, including workflows that were spawned from
+    /// other diagrams.
+    pub fn insert<Request, Response, Streams>(
+        &self,
+        name: impl Into<Arc<str>>,
+        service: Service<Request, Response, Streams>,
+    ) where
+        Request: 'static + Send + Sync,
+        Response: 'static + Send + Sync + Unpin,
+        Streams: StreamPack,
+    {
+        let entry = CatalogEntry {
+            service: Arc::new(service),
+            request: TypeInfo::of::<Request>(),
+            response: TypeInfo::of::<Response>(),
+            create_node: create_node::<Request, Response, Streams>,
+            create_runtime_node: create_runtime_node::<Request, Response, Streams>,
+        };
+
+        self.entries.write().unwrap().insert(name.into(), entry);
+    }
+
+    /// Remove a service from the catalog. Returns true if a service was
+    /// registered under this name.
+    pub fn remove(&self, name: &str) -> bool {
+        self.entries.write().unwrap().remove(name).is_some()
+    }
+
+    /// Get the service registered under `name` if its message types match.
+    pub fn get<Request, Response, Streams>(
+        &self,
+        name: &str,
+    ) -> Result<Service<Request, Response, Streams>, ServiceCatalogError>
+    where
+        Request: 'static + Send + Sync,
+        Response: 'static + Send + Sync,
+        Streams: StreamPack,
+    {
+        let entry = self.get_entry(name)?;
+        entry
+            .service
+            .downcast_ref::<Service<Request, Response, Streams>>()
+            .copied()
+            .ok_or_else(|| ServiceCatalogError::TypeMismatch {
+                name: name.into(),
+                request: entry.request,
+                response: entry.response,
+            })
+    }
+
+    /// Get the names of all services currently in the catalog.
+    pub fn names(&self) -> Vec<Arc<str>> {
+        self.entries.read().unwrap().keys().cloned().collect()
+    }
+
+    fn get_entry(&self, name: &str) -> Result<CatalogEntry, ServiceCatalogError> {
+        self.entries
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ServiceCatalogError::NotFound(name.into()))
+    }
+}
+
+#[derive(Clone)]
+struct CatalogEntry {
+    service: Arc<dyn Any + Send + Sync>,
+    request: TypeInfo,
+    response: TypeInfo,
+    create_node: fn(&(dyn Any + Send + Sync), &mut Builder) -> DynNode,
+    create_runtime_node: fn(Arc<str>, ServiceCatalog, &mut Builder) -> (DynNode, DynOutput),
+}
+
+fn create_node<Request, Response, Streams>(
+    service: &(dyn Any + Send + Sync),
+    builder: &mut Builder,
+) -> DynNode
+where
+    Request: 'static + Send + Sync,
+    Response: 'static + Send + Sync,
+    Streams: StreamPack,
+{
+    // This function is only ever paired with a service of this type inside of
+    // CatalogEntry, so the downcast cannot fail.
+    let service = *service
+        .downcast_ref::<Service<Request, Response, Streams>>()
+        .unwrap();
+    builder.create_node(service).into()
+}
+
+fn create_runtime_node<Request, Response, Streams>(
+    name: Arc<str>,
+    catalog: ServiceCatalog,
+    builder: &mut Builder,
+) -> (DynNode, DynOutput)
+where
+    Request: 'static + Send + Sync,
+    Response: 'static + Send + Sync + Unpin,
+    Streams: StreamPack,
+{
+    let lookup = builder.create_map_block(move |request: Request| {
+        catalog
+            .get::<Request, Response, Streams>(&name)
+            .map(|service| (request, service))
+    });
+
+    let (injection, err) = lookup
+        .output
+        .chain(builder)
+        .fork_result(|ok| ok.then_injection_node(), |err| err.output());
+
+    let mut node: DynNode = injection.into();
+    node.input = lookup.input.into();
+    (node, err.into())
+}
+
+/// Errors that can happen while looking up a service in a [`ServiceCatalog`].
+#[derive(ThisError, Debug, Clone)]
+pub enum ServiceCatalogError {
+    #[error("no service named [{0}] exists in the service catalog")]
+    NotFound(Arc<str>),
+
+    #[error("service [{name}] in the catalog has request type {request} and response type {response} which do not match what was expected")]
+    TypeMismatch {
+        name: Arc<str>,
+        request: TypeInfo,
+        response: TypeInfo,
+    },
+}
+
+/// When should a `call` operation look up its service in the catalog.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolveService {
+    /// Look up the service once while the workflow is being built.
+    #[default]
+    Build,
+    /// Look up the service each time a request arrives, and pass the request
+    /// into whichever service is in the catalog at that moment. The service
+    /// must still be in the catalog while the workflow is being built so that
+    /// its message types are known.
+    Runtime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CallSchema {
+    /// Name of the service in the [`ServiceCatalog`].
+    pub(super) service: Arc<str>,
+    pub(super) next: NextOperation,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub(super) stream_out: HashMap<OperationName, NextOperation>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub(super) resolve: ResolveService,
+    /// Specify where an error should go if the service cannot be found in the
+    /// catalog at runtime. This can only be set when `resolve` is `runtime`.
+    ///
+    /// If left unspecified, a failure will be treated like an implicit operation
+    /// failure and behave according to `on_implicit_error`.
+    #[serde(default)]
+    pub(super) on_error: Option<NextOperation>,
+}
+
+impl BuildDiagramOperation for CallSchema {
+    fn build_diagram_operation(
+        &self,
+        id: &OperationName,
+        builder: &mut Builder,
+        ctx: &mut DiagramContext,
+    ) -> Result<BuildStatus, DiagramErrorCode> {
+        let catalog = ctx.registry.service_catalog();
+        let entry = catalog.get_entry(&self.service)?;
+        let mut node = match self.resolve {
+            ResolveService::Build => {
+                if self.on_error.is_some() {
+                    // The service is looked up right here, so there is no
+                    // runtime error that could ever reach on_error.
+                    return Err(DiagramErrorCode::CallOnErrorUnused(id.clone()));
+                }
+                (entry.create_node)(entry.service.as_ref(), builder)
+            }
+            ResolveService::Runtime => {
+                let (node, err) = (entry.create_runtime_node)(
+                    Arc::clone(&self.service),
+                    catalog.clone(),
+                    builder,
+                );
+
+                let error_target = self
+                    .on_error
+                    .as_ref()
+                    .map(|on_error| ctx.into_operation_ref(on_error))
+                    .unwrap_or(ctx.get_implicit_error_target());
+                ctx.add_output_into_resolved_target(error_target, err);
+                node
+            }
+        };
+
+        ctx.set_input_for_target(id, node.input)?;
+        ctx.add_output_into_target(&self.next, node.output);
+
+        let available_names = node
+            .streams
+            .available_names()
+            .map(|n| n.clone().into())
+            .collect();
+
+        for (name, target) in &self.stream_out {
+            let Some(output) = node.streams.take_named(name) else {
+                return Err(DiagramErrorCode::MissingStream(MissingStream {
+                    missing_name: Arc::clone(name),
+                    available_names,
+                }));
+            };
+
+            ctx.add_output_into_target(target, output);
+        }
+
+        Ok(BuildStatus::Finished)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use test_log::test;
+
+    use crate::{
+        diagram::{testing::DiagramTestFixture, *},
+        prelude::*,
+        stream::tests::*,
+    };
+
+    fn spawn_multiply3(fixture: &mut DiagramTestFixture) -> Service<JsonMessage, JsonMessage> {
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "multiply3",
+            "ops": {
+                "multiply3": {
+                    "type": "node",
+                    "builder": "multiply3",
+                    "next": { "builtin": "terminate" },
+                },
+            },
+        }))
+        .unwrap();
+
+        fixture.spawn_json_io_workflow(&diagram).unwrap()
+    }
+
+    #[test]
+    fn test_call_diagram_by_name() {
+        let mut fixture = DiagramTestFixture::new();
+        let multiply3 = spawn_multiply3(&mut fixture);
+        fixture
+            .registry
+            .service_catalog()
+            .insert("times3", multiply3);
+
+        for resolve in ["build", "runtime"] {
+            let diagram = Diagram::from_json(json!({
+                "version": "0.1.0",
+                "start": "call",
+                "ops": {
+                    "call": {
+                        "type": "call",
+                        "service": "times3",
+                        "resolve": resolve,
+                        "next": "call_again",
+                    },
+                    "call_again": {
+                        "type": "call",
+                        "service": "times3",
+                        "resolve": resolve,
+                        "next": { "builtin": "terminate" },
+                    },
+                },
+            }))
+            .unwrap();
+
+            let result: JsonMessage = fixture
+                .spawn_and_run(&diagram, JsonMessage::from(2))
+                .unwrap();
+            assert!(fixture.context.no_unhandled_errors());
+            assert_eq!(result, 18);
+        }
+    }
+
+    #[test]
+    fn test_call_unknown_service() {
+        let mut fixture = DiagramTestFixture::new();
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "call",
+            "ops": {
+                "call": {
+                    "type": "call",
+                    "service": "does_not_exist",
+                    "next": { "builtin": "terminate" },
+                },
+            },
+        }))
+        .unwrap();
+
+        let err = fixture.spawn_json_io_workflow(&diagram).unwrap_err();
+        assert!(
+            matches!(
+                err.code,
+                DiagramErrorCode::ServiceCatalogError(ServiceCatalogError::NotFound(_))
+            ),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_call_on_error_requires_runtime_resolve() {
+        let mut fixture = DiagramTestFixture::new();
+        let multiply3 = spawn_multiply3(&mut fixture);
+        fixture
+            .registry
+            .service_catalog()
+            .insert("times3", multiply3);
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "call",
+            "ops": {
+                "call": {
+                    "type": "call",
+                    "service": "times3",
+                    "resolve": "build",
+                    "next": { "builtin": "terminate" },
+                    "on_error": { "builtin": "cancel" },
+                },
+            },
+        }))
+        .unwrap();
+
+        let err = fixture.spawn_json_io_workflow(&diagram).unwrap_err();
+        assert!(
+            matches!(&err.code, DiagramErrorCode::CallOnErrorUnused(name) if name.as_ref() == "call"),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_call_removed_service_at_runtime() {
+        let mut fixture = DiagramTestFixture::new();
+        let multiply3 = spawn_multiply3(&mut fixture);
+        let catalog = fixture.registry.service_catalog().clone();
+        catalog.insert("times3", multiply3);
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "call",
+            "ops": {
+                "call": {
+                    "type": "call",
+                    "service": "times3",
+                    "resolve": "runtime",
+                    "next": { "builtin": "terminate" },
+                    "on_error": { "builtin": "cancel" },
+                },
+            },
+        }))
+        .unwrap();
+
+        let workflow = fixture.spawn_json_io_workflow(&diagram).unwrap();
+        assert!(catalog.remove("times3"));
+
+        let mut promise = fixture
+            .context
+            .command(|cmds| cmds.request(JsonMessage::from(2), workflow).take_response());
+        fixture.context.run_while_pending(&mut promise);
+        assert!(promise.take().is_cancelled());
+        assert!(fixture.context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_call_with_streams() {
+        let mut fixture = DiagramTestFixture::new();
+        let streaming = fixture.context.command(|cmds| {
+            cmds.spawn_service(
+                |In(input): BlockingServiceInput<Vec<String>, TestStreamPack>| {
+                    for r in input.request {
+                        if let Ok(value) = r.parse::<u32>() {
+                            input.streams.stream_u32.send(value);
+                        }
+
+                        input.streams.stream_string.send(r);
+                    }
+                },
+            )
+        });
+        fixture
+            .registry
+            .service_catalog()
+            .insert("streaming", streaming);
+
+        for resolve in ["build", "runtime"] {
+            let diagram = Diagram::from_json(json!({
+                "version": "0.1.0",
+                "start": "call",
+                "ops": {
+                    "call": {
+                        "type": "call",
+                        "service": "streaming",
+                        "resolve": resolve,
+                        "next": { "builtin": "terminate" },
+                        "stream_out": {
+                            "stream_u32": "stream_u32_out",
+                            "stream_string": "stream_string_out",
+                        },
+                    },
+                    "stream_u32_out": {
+                        "type": "stream_out",
+                        "name": "stream_u32",
+                    },
+                    "stream_string_out": {
+                        "type": "stream_out",
+                        "name": "stream_string",
+                    },
+                },
+            }))
+            .unwrap();
+
+            let request = vec!["5".to_owned(), "hello".to_owned()];
+            let (_, receivers) = fixture
+                .spawn_and_run_with_streams::<_, (), TestStreamPack>(&diagram, request)
+                .unwrap();
+
+            assert_eq!(collect_received_values(receivers.stream_u32), [5]);
+            assert_eq!(
+                collect_received_values(receivers.stream_string),
+                ["5", "hello"]
+            );
+        }
+    }
+}
@@ -0,0 +1,102 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{Builder, Cancellation, CancellationCause, JsonMessage, Node};
+
+use super::report::triggered_value;
+
+/// A serializable description of a [`CancellationCause`]. This is the message
+/// that gets sent to the `on_cancel` target of a scope or section.
+///
+/// Entity IDs are not included since they have no meaning within a diagram.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SerializedCancellationCause {
+    /// The promise taken by the requester was dropped without being detached.
+    TargetDropped,
+    /// There are no terminating operations that can be reached anymore.
+    Unreachable {
+        /// How many disposals led to the termination being unreachable.
+        disposals: usize,
+    },
+    /// A filtering operation has triggered a cancellation.
+    Filtered { reason: Option<String> },
+    /// The workflow triggered its own cancellation, e.g. by sending a message
+    /// to `{ "builtin": "cancel" }`.
+    Triggered {
+        /// The message that triggered the cancellation, if it could be
+        /// serialized or converted into a string. Messages that could only be
+        /// converted into a string are sent as a string.
+        value: Option<JsonMessage>,
+    },
+    /// A request was supplanted by a newer one.
+    Supplanted,
+    /// An operation was given an invalid span to operate on.
+    InvalidSpan,
+    /// There is a circular dependency between two or more collect operations.
+    CircularCollect,
+    /// Operations kept executing until the flush reached its loop limit.
+    Livelock,
+    /// A request became undeliverable because the sender was dropped.
+    Undeliverable,
+    /// A promise can never be delivered because its mutex was poisoned.
+    PoisonedMutexInPromise,
+    /// An operation in the workflow was broken.
+    Broken,
+}
+
+impl SerializedCancellationCause {
+    /// Create a node that serializes [`Cancellation`] messages.
+    pub(super) fn create_serializer(builder: &mut Builder) -> Node<Cancellation, JsonMessage> {
+        builder.create_map_block(|cancellation: Cancellation| {
+            Self::from(cancellation.cause.as_ref()).to_json()
+        })
+    }
+
+    pub(super) fn to_json(&self) -> JsonMessage {
+        // SAFETY: This enum only contains types that always serialize
+        // successfully.
+        serde_json::to_value(self).unwrap()
+    }
+}
+
+impl From<&CancellationCause> for SerializedCancellationCause {
+    fn from(cause: &CancellationCause) -> Self {
+        match cause {
+            CancellationCause::TargetDropped(_) => Self::TargetDropped,
+            CancellationCause::Unreachable(unreachable) => Self::Unreachable {
+                disposals: unreachable.disposals.len(),
+            },
+            CancellationCause::Filtered(filtered) => Self::Filtered {
+                reason: filtered.reason.as_ref().map(|r| r.to_string()),
+            },
+            CancellationCause::Triggered(triggered) => Self::Triggered {
+                value: triggered_value(triggered),
+            },
+            CancellationCause::Supplanted(_) => Self::Supplanted,
+            CancellationCause::InvalidSpan(_) => Self::InvalidSpan,
+            CancellationCause::CircularCollect(_) => Self::CircularCollect,
+            CancellationCause::Livelock(_) => Self::Livelock,
+            CancellationCause::Undeliverable => Self::Undeliverable,
+            CancellationCause::PoisonedMutexInPromise => Self::PoisonedMutexInPromise,
+            CancellationCause::Broken(_) => Self::Broken,
+        }
+    }
+}
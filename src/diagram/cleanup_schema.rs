@@ -0,0 +1,503 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    sync::Arc,
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use smallvec::smallvec;
+
+use crate::{
+    BufferMap, BufferSelection, BuildDiagramOperation, BuildStatus, Builder, BuiltinTarget,
+    CleanupWorkflowConditions, ConnectIntoTarget, DiagramContext, DiagramErrorCode, DynInputSlot,
+    DynOutput, IncrementalScopeBuilder, InferMessageType, NextOperation, OperationName,
+    OperationRef, Operations, ScopeSettings, TypeInfo,
+};
+
+use super::workflow_builder::ConnectToCancel;
+
+/// Cleanup workflows of a diagram or scope, identified by name. The names share
+/// a namespace with the operations of the diagram or scope.
+pub type CleanupWorkflows = HashMap<OperationName, Arc<CleanupSchema>>;
+
+/// The schema to define a cleanup workflow within a diagram or scope. This is
+/// the diagram equivalent of [`Builder::on_cleanup_if`].
+///
+/// When a session of the diagram or scope is finished, the cleanup workflow
+/// will be given the keys of its buffers so it can perform any final actions
+/// with their contents, such as releasing resources that were acquired during
+/// the session. The buffers will not be cleared until all cleanup workflows
+/// have terminated.
+///
+/// The operations of a cleanup workflow run in their own scope. They should
+/// eventually connect to `{ "builtin": "terminate" }`, which accepts a message
+/// of any type.
+///
+/// # Examples
+/// ```
+/// # bevy_impulse::Diagram::from_json_str(r#"
+/// {
+///     "version": "0.1.0",
+///     "start": "acquire_door",
+///     "ops": {
+///         "acquire_door": {
+///             "type": "node",
+///             "builder": "acquire_door",
+///             "next": "door"
+///         },
+///         "door": {
+///             "type": "buffer"
+///         }
+///     },
+///     "cleanup": {
+///         "release": {
+///             "buffers": ["door"],
+///             "trigger": "on_cleanup",
+///             "start": "release_door",
+///             "ops": {
+///                 "release_door": {
+///                     "type": "node",
+///                     "builder": "release_door",
+///                     "next": { "builtin": "terminate" }
+///                 }
+///             }
+///         }
+///     }
+/// }
+/// # "#)?;
+/// # Ok::<_, serde_json::Error>(())
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CleanupSchema {
+    /// The buffers whose keys will be passed into the cleanup workflow. The
+    /// keys are combined into a single message the same way as a `listen`
+    /// operation.
+    pub buffers: BufferSelection,
+
+    /// The condition that triggers the cleanup workflow.
+    #[serde(default)]
+    pub trigger: CleanupTrigger,
+
+    /// Indicates which operation inside the cleanup workflow should receive
+    /// the buffer keys.
+    pub start: NextOperation,
+
+    /// Operations that exist inside the cleanup workflow.
+    pub ops: Operations,
+
+    /// Settings specific to the scope of the cleanup workflow.
+    #[serde(default)]
+    pub settings: ScopeSettings,
+}
+
+/// The conditions under which a cleanup workflow will run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupTrigger {
+    /// Run whenever a session is finished, whether it terminated or was
+    /// cancelled. This is the equivalent of [`Builder::on_cleanup`].
+    #[default]
+    OnCleanup,
+    /// Only run when a session is cancelled. This is the equivalent of
+    /// [`Builder::on_cancel`].
+    OnCancel,
+    /// Only run when a session successfully terminates. This is the equivalent
+    /// of [`Builder::on_terminate`].
+    OnTerminate,
+}
+
+impl From<CleanupTrigger> for CleanupWorkflowConditions {
+    fn from(trigger: CleanupTrigger) -> Self {
+        match trigger {
+            CleanupTrigger::OnCleanup => CleanupWorkflowConditions::always_if(true, true),
+            CleanupTrigger::OnCancel => CleanupWorkflowConditions::always_if(false, true),
+            CleanupTrigger::OnTerminate => CleanupWorkflowConditions::always_if(true, false),
+        }
+    }
+}
+
+/// Make sure that none of the cleanup workflows share a name with the
+/// operations that they are next to.
+pub(super) fn validate_cleanup_names(
+    cleanup: &CleanupWorkflows,
+    ops: &Operations,
+) -> Result<(), DiagramErrorCode> {
+    for name in cleanup.keys() {
+        if ops.contains_key(name) {
+            return Err(DiagramErrorCode::CleanupNameCollision(Arc::clone(name)));
+        }
+    }
+
+    Ok(())
+}
+
+impl BuildDiagramOperation for CleanupSchema {
+    fn build_diagram_operation(
+        &self,
+        id: &OperationName,
+        builder: &mut Builder,
+        ctx: &mut DiagramContext,
+    ) -> Result<BuildStatus, DiagramErrorCode> {
+        let buffers = match ctx.create_buffer_map(&self.buffers) {
+            Ok(buffers) => buffers,
+            Err(reason) => return Ok(BuildStatus::defer(reason)),
+        };
+
+        let scope = IncrementalScopeBuilder::begin_cleanup(self.settings.clone(), builder);
+        let scope_context = scope.builder_scope_context();
+
+        // Cancelling from inside of the cleanup workflow only cancels the
+        // cleanup workflow, and so does any implicit error.
+        let cleanup_cancel = ctx.into_child_operation_ref(
+            id,
+            &NextOperation::Builtin {
+                builtin: BuiltinTarget::Cancel,
+            },
+        );
+        let mut cleanup_builder = Builder {
+            context: scope_context,
+            commands: builder.commands(),
+        };
+        ctx.set_connect_into_target_in_scope(
+            cleanup_cancel.clone(),
+            ConnectToCancel::new(&mut cleanup_builder)?,
+            scope_context,
+            cleanup_cancel.clone(),
+        )?;
+
+        for (child_id, op) in self.ops.iter() {
+            ctx.add_child_operation(
+                id,
+                child_id,
+                op,
+                self.ops.clone(),
+                Some(scope_context),
+                Some(cleanup_cancel.clone()),
+            );
+        }
+
+        ctx.set_connect_into_target(
+            OperationRef::Terminate(smallvec![Arc::clone(id)]),
+            ConnectCleanupTerminate {
+                scope: scope.clone(),
+                terminate: None,
+                triggers: Default::default(),
+            },
+        )?;
+
+        // The request type of the cleanup workflow is decided by its start
+        // operation, which will not be known until the child operations have
+        // been built, so we hand off the rest of the work to another operation.
+        ctx.add_child_operation(
+            id,
+            id,
+            &Arc::new(BeginCleanup {
+                scope,
+                buffers,
+                conditions: self.trigger.into(),
+                start: self.start.clone(),
+            }),
+            self.ops.clone(),
+            None,
+            None,
+        );
+
+        Ok(BuildStatus::Finished)
+    }
+}
+
+struct BeginCleanup {
+    scope: IncrementalScopeBuilder,
+    buffers: BufferMap,
+    conditions: CleanupWorkflowConditions,
+    start: NextOperation,
+}
+
+impl BuildDiagramOperation for BeginCleanup {
+    fn build_diagram_operation(
+        &self,
+        _: &OperationName,
+        builder: &mut Builder,
+        ctx: &mut DiagramContext,
+    ) -> Result<BuildStatus, DiagramErrorCode> {
+        let Some(key_type) = ctx.infer_input_type_into_target(&self.start)? else {
+            return Ok(BuildStatus::defer(
+                "waiting to find out the request type of the cleanup workflow",
+            ));
+        };
+
+        let begin_scope = ctx.registry.messages.cleanup(
+            &key_type,
+            &self.buffers,
+            self.conditions.clone(),
+            &mut self.scope.clone(),
+            builder,
+        )?;
+
+        if let Some(begin_scope) = begin_scope {
+            ctx.add_output_into_target(&self.start, begin_scope);
+        }
+
+        Ok(BuildStatus::Finished)
+    }
+}
+
+/// The terminate operation of a cleanup workflow accepts messages of any type
+/// by converting them into a trigger.
+struct ConnectCleanupTerminate {
+    scope: IncrementalScopeBuilder,
+    terminate: Option<DynInputSlot>,
+    triggers: HashMap<TypeInfo, DynInputSlot>,
+}
+
+impl ConnectIntoTarget for ConnectCleanupTerminate {
+    fn connect_into_target(
+        &mut self,
+        output: DynOutput,
+        builder: &mut Builder,
+        ctx: &mut DiagramContext,
+    ) -> Result<(), DiagramErrorCode> {
+        let terminate = match self.terminate {
+            Some(terminate) => terminate,
+            None => {
+                let terminate = self.scope.set_response::<()>(builder.commands())?.terminate;
+                self.terminate = Some(terminate);
+                terminate
+            }
+        };
+
+        if output.message_info() == terminate.message_info() {
+            return output.connect_to(&terminate, builder).map_err(Into::into);
+        }
+
+        let input_slot = match self.triggers.entry(*output.message_info()) {
+            Entry::Occupied(occupied) => *occupied.get(),
+            Entry::Vacant(vacant) => {
+                let trigger = ctx
+                    .registry
+                    .messages
+                    .trigger(output.message_info(), builder)?;
+                trigger.output.connect_to(&terminate, builder)?;
+                *vacant.insert(trigger.input)
+            }
+        };
+
+        output.connect_to(&input_slot, builder)?;
+        Ok(())
+    }
+
+    fn infer_input_type(
+        &self,
+        _: &DiagramContext,
+        _: &mut HashSet<OperationRef>,
+    ) -> Result<Option<Arc<dyn InferMessageType>>, DiagramErrorCode> {
+        // Any message type is accepted.
+        Ok(None)
+    }
+
+    fn is_finished(&self) -> Result<(), DiagramErrorCode> {
+        self.scope.is_finished().map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::prelude::{In, World};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        diagram::testing::DiagramTestFixture, BufferKey, BufferWorldAccess, Diagram,
+        DiagramErrorCode, IntoBlockingCallback, JsonMessage, NodeBuilderOptions,
+    };
+
+    fn new_fixture() -> (DiagramTestFixture, Arc<Mutex<Vec<i64>>>) {
+        let mut fixture = DiagramTestFixture::new();
+
+        fixture.registry.register_node_builder(
+            NodeBuilderOptions::new("to_i64"),
+            |builder, _config: ()| {
+                builder.create_map_block(|msg: JsonMessage| msg.as_i64().unwrap())
+            },
+        );
+
+        let released = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&released);
+        fixture
+            .registry
+            .opt_out()
+            .no_serializing()
+            .no_deserializing()
+            .register_node_builder(
+                NodeBuilderOptions::new("release"),
+                move |builder, _config: ()| {
+                    let recorder = Arc::clone(&recorder);
+                    builder.create_node(
+                        (move |In(key): In<BufferKey<i64>>, world: &mut World| {
+                            let values: Vec<i64> = world
+                                .buffer_mut(&key, |mut buffer| buffer.drain(..).collect())
+                                .unwrap();
+                            recorder.lock().unwrap().extend(values);
+                        })
+                        .into_blocking_callback(),
+                    )
+                },
+            )
+            .with_listen();
+
+        (fixture, released)
+    }
+
+    fn cleanup_schema(trigger: &str) -> JsonMessage {
+        json!({
+            "release": {
+                "buffers": ["buffer"],
+                "trigger": trigger,
+                "start": "release",
+                "ops": {
+                    "release": {
+                        "type": "node",
+                        "builder": "release",
+                        "next": { "builtin": "terminate" },
+                    },
+                },
+            },
+        })
+    }
+
+    fn check_positive_ops() -> JsonMessage {
+        json!({
+            "to_i64": {
+                "type": "node",
+                "builder": "to_i64",
+                "next": "fork_clone",
+            },
+            "fork_clone": {
+                "type": "fork_clone",
+                "next": ["buffer", "check"],
+            },
+            "buffer": {
+                "type": "buffer",
+            },
+            "check": {
+                "type": "node",
+                "builder": "check_positive",
+                "next": "fork",
+            },
+            "fork": {
+                "type": "fork_result",
+                "ok": { "builtin": "terminate" },
+                "err": { "builtin": "cancel" },
+            },
+        })
+    }
+
+    fn check_positive_diagram(trigger: &str) -> Diagram {
+        Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "to_i64",
+            "ops": check_positive_ops(),
+            "cleanup": cleanup_schema(trigger),
+        }))
+        .unwrap()
+    }
+
+    fn run_check_positive(
+        diagram: &Diagram,
+        fixture: &mut DiagramTestFixture,
+        released: &Arc<Mutex<Vec<i64>>>,
+        value: i64,
+    ) -> (bool, Vec<i64>) {
+        let terminated = fixture
+            .spawn_and_run::<_, JsonMessage>(diagram, JsonMessage::from(value))
+            .is_ok();
+        let released = released.lock().unwrap().drain(..).collect();
+        (terminated, released)
+    }
+
+    #[test]
+    fn test_cleanup_triggers() {
+        for (trigger, expect_on_terminate, expect_on_cancel) in [
+            ("on_cleanup", vec![5], vec![-5]),
+            ("on_cancel", vec![], vec![-5]),
+            ("on_terminate", vec![5], vec![]),
+        ] {
+            let (mut fixture, released) = new_fixture();
+            let diagram = check_positive_diagram(trigger);
+
+            let outcome = run_check_positive(&diagram, &mut fixture, &released, 5);
+            assert_eq!(outcome, (true, expect_on_terminate), "trigger: {trigger}");
+
+            let outcome = run_check_positive(&diagram, &mut fixture, &released, -5);
+            assert_eq!(outcome, (false, expect_on_cancel), "trigger: {trigger}");
+            assert!(fixture.context.no_unhandled_errors());
+        }
+    }
+
+    #[test]
+    fn test_cleanup_in_scope() {
+        let (mut fixture, released) = new_fixture();
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "scope",
+            "ops": {
+                "scope": {
+                    "type": "scope",
+                    "start": "to_i64",
+                    "ops": check_positive_ops(),
+                    "cleanup": cleanup_schema("on_cancel"),
+                    "on_cancel": { "builtin": "terminate" },
+                    "next": { "builtin": "terminate" },
+                },
+            },
+        }))
+        .unwrap();
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(-3))
+            .unwrap();
+        assert_eq!(result["type"], "triggered");
+        assert_eq!(*released.lock().unwrap(), [-3]);
+        assert!(fixture.context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_cleanup_name_collision() {
+        let (mut fixture, _) = new_fixture();
+
+        let mut cleanup = cleanup_schema("on_cleanup");
+        cleanup["buffer"] = cleanup["release"].clone();
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "to_i64",
+            "ops": check_positive_ops(),
+            "cleanup": cleanup,
+        }))
+        .unwrap();
+
+        let err = fixture.spawn_json_io_workflow(&diagram).unwrap_err();
+        assert!(
+            matches!(err.code, DiagramErrorCode::CleanupNameCollision(ref name) if name.as_ref() == "buffer"),
+            "{err:?}",
+        );
+    }
+}
@@ -0,0 +1,252 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use std::any::Any;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error as ThisError;
+
+use crate::{Builder, Cancellation, CancellationCause, JsonMessage};
+
+use super::{DiagramErrorCode, DynInputSlot, DynOutput, MessageRegistry, TypeInfo};
+
+pub(super) type ValidateFn = fn(&JsonMessage) -> Result<(), String>;
+
+/// Which side of a diagram a contract applies to.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ContractSide {
+    Input,
+    Output,
+}
+
+/// A message did not satisfy the `input_schema` or `output_schema` of a
+/// diagram. This will be the value of the cancellation of the workflow,
+/// serialized as JSON. Use [`ContractViolation::from_cancellation`] to
+/// retrieve it.
+#[derive(ThisError, Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[error("the {side} message does not satisfy [{type_name}]: {reason}")]
+pub struct ContractViolation {
+    pub side: ContractSide,
+    pub type_name: String,
+    pub reason: String,
+}
+
+impl ContractViolation {
+    /// Check if a workflow was cancelled because of a contract violation.
+    pub fn from_cancellation(cancellation: &Cancellation) -> Option<Self> {
+        let CancellationCause::Triggered(triggered) = cancellation.cause.as_ref() else {
+            return None;
+        };
+
+        serde_json::from_str(triggered.value.as_ref()?).ok()
+    }
+}
+
+/// How a message type of a workflow needs to be checked against a contract
+/// that was declared by a diagram.
+pub(super) struct MessageContract {
+    side: ContractSide,
+    type_name: String,
+    validate: Option<ValidateFn>,
+}
+
+impl MessageContract {
+    /// Check that `T` is compatible with the message type named by `type_name`.
+    ///
+    /// If `T` is [`JsonMessage`] then every message will be validated at
+    /// runtime against the named type instead.
+    pub(super) fn new<T: Any>(
+        side: ContractSide,
+        type_name: &str,
+        messages: &MessageRegistry,
+    ) -> Result<Self, DiagramErrorCode> {
+        let Some((contract_type, registration)) = messages.get_by_type_name(type_name) else {
+            return Err(DiagramErrorCode::UnknownMessageTypeName(type_name.into()));
+        };
+
+        let actual = TypeInfo::of::<T>();
+        let json = TypeInfo::of::<JsonMessage>();
+        let validate = if actual == *contract_type || *contract_type == json {
+            None
+        } else if actual == json {
+            let validate = registration
+                .operations
+                .validate_impl
+                .ok_or(DiagramErrorCode::NotDeserializable(*contract_type))?;
+            Some(validate)
+        } else {
+            return Err(DiagramErrorCode::ContractMismatch {
+                side,
+                contract: *contract_type,
+                actual,
+            });
+        };
+
+        Ok(Self {
+            side,
+            type_name: type_name.to_owned(),
+            validate,
+        })
+    }
+
+    /// Create a runtime validation step if one is needed for this contract.
+    /// Messages that fail validation will cancel the workflow.
+    pub(super) fn create_validation(
+        &self,
+        builder: &mut Builder,
+    ) -> Option<(DynInputSlot, DynOutput)> {
+        let validate = self.validate?;
+        let side = self.side;
+        let type_name = self.type_name.clone();
+        let node = builder.create_map_block(move |message: JsonMessage| {
+            validate(&message)
+                .map(|_| message)
+                .map_err(|reason| ContractViolation {
+                    side,
+                    type_name: type_name.clone(),
+                    reason,
+                })
+        });
+
+        let cancel = builder.create_cancel::<JsonMessage>();
+        let (ok, _) = node.output.chain(builder).fork_result(
+            |ok| ok.output(),
+            |err| {
+                err.map_block(|violation| {
+                    // SAFETY: ContractViolation only contains types that always
+                    // serialize successfully.
+                    serde_json::to_value(violation).unwrap()
+                })
+                .connect(cancel)
+            },
+        );
+
+        Some((node.input.into(), ok.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use test_log::test;
+
+    use crate::{
+        diagram::{testing::DiagramTestFixture, *},
+        Cancellation,
+    };
+
+    fn multiply3_diagram(input_schema: &str, output_schema: &str) -> Diagram {
+        Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "op1",
+            "input_schema": input_schema,
+            "output_schema": output_schema,
+            "ops": {
+                "op1": {
+                    "type": "node",
+                    "builder": "multiply3",
+                    "next": { "builtin": "terminate" },
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_contract_checked_on_spawn() {
+        let mut fixture = DiagramTestFixture::new();
+        let diagram = multiply3_diagram("i64", "i64");
+
+        assert!(fixture.spawn_io_workflow::<i64, i64>(&diagram).is_ok());
+        assert!(fixture.spawn_json_io_workflow(&diagram).is_ok());
+
+        let err = fixture
+            .spawn_io_workflow::<String, i64>(&diagram)
+            .unwrap_err();
+        assert!(
+            matches!(
+                err.code,
+                DiagramErrorCode::ContractMismatch {
+                    side: ContractSide::Input,
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
+
+        let err = fixture
+            .spawn_io_workflow::<i64, String>(&diagram)
+            .unwrap_err();
+        assert!(
+            matches!(
+                err.code,
+                DiagramErrorCode::ContractMismatch {
+                    side: ContractSide::Output,
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
+
+        let diagram = multiply3_diagram("not_a_type", "i64");
+        let err = fixture.spawn_json_io_workflow(&diagram).unwrap_err();
+        assert!(
+            matches!(err.code, DiagramErrorCode::UnknownMessageTypeName(_)),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_json_request_rejected_early() {
+        let mut fixture = DiagramTestFixture::new();
+        let diagram = multiply3_diagram("i64", "i64");
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(4))
+            .unwrap();
+        assert_eq!(result, 12);
+
+        let err = fixture
+            .spawn_and_run::<_, JsonMessage>(&diagram, JsonMessage::from("four"))
+            .unwrap_err();
+        let violation =
+            ContractViolation::from_cancellation(err.downcast_ref::<Cancellation>().unwrap())
+                .unwrap();
+        assert_eq!(violation.side, ContractSide::Input);
+        assert_eq!(violation.type_name, "i64");
+    }
+
+    #[test]
+    fn test_json_response_validated() {
+        let mut fixture = DiagramTestFixture::new();
+        let diagram = multiply3_diagram("i64", "alloc::string::String");
+
+        let err = fixture
+            .spawn_and_run::<_, JsonMessage>(&diagram, JsonMessage::from(4))
+            .unwrap_err();
+        let violation =
+            ContractViolation::from_cancellation(err.downcast_ref::<Cancellation>().unwrap())
+                .unwrap();
+        assert_eq!(violation.side, ContractSide::Output);
+    }
+}
@@ -23,7 +23,7 @@ use crate::Builder;
 
 use super::{
     is_default, BuildDiagramOperation, BuildStatus, BuilderId, DiagramContext, DiagramErrorCode,
-    MissingStream, NextOperation, OperationName,
+    MissingStream, NextOperation, OperationName, OperationRef,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
@@ -45,7 +45,17 @@ impl BuildDiagramOperation for NodeSchema {
         ctx: &mut DiagramContext,
     ) -> Result<BuildStatus, DiagramErrorCode> {
         let node_registration = ctx.registry.get_node_registration(&self.builder)?;
-        let mut node = node_registration.create_node(builder, self.config.clone())?;
+        let replay = match (&ctx.registry.replay, ctx.into_operation_ref(id)) {
+            (Some(replay), OperationRef::Named(named)) => {
+                replay.create_node(&named.to_string(), &node_registration.response, builder)
+            }
+            _ => None,
+        };
+
+        let mut node = match replay {
+            Some(node) => node,
+            None => node_registration.create_node(builder, self.config.clone())?,
+        };
 
         ctx.set_input_for_target(id, node.input.into())?;
         ctx.add_output_into_target(&self.next, node.output);
@@ -28,7 +28,8 @@ use bevy_ecs::prelude::{Commands, Entity};
 
 pub use crate::dyn_node::*;
 use crate::{
-    Accessor, AnyBuffer, AsAnyBuffer, BufferMap, BufferSettings, Builder, IncrementalScopeBuilder,
+    begin_cleanup_workflow, Accessor, AnyBuffer, AsAnyBuffer, BufferMap, BufferMapLayout,
+    BufferSettings, Builder, CleanupWorkflowConditions, IncrementalScopeBuilder,
     IncrementalScopeRequest, IncrementalScopeRequestResult, IncrementalScopeResponse,
     IncrementalScopeResponseResult, Joined, JsonBuffer, JsonMessage, NamedStream, Node, StreamOf,
     StreamPack,
@@ -44,12 +45,13 @@ use serde_json::json;
 use tracing::debug;
 
 use super::{
-    buffer_schema::BufferAccessRequest, fork_clone_schema::PerformForkClone,
+    buffer_schema::BufferAccessRequest, contract::ValidateFn, fork_clone_schema::PerformForkClone,
     fork_result_schema::RegisterForkResult, register_json, supported::*,
-    unzip_schema::PerformUnzip, BuilderId, DeserializeMessage, DiagramErrorCode, DynForkClone,
-    DynForkResult, DynSplit, DynType, JsonRegistration, RegisterJson, RegisterSplit, Section,
-    SectionMetadata, SectionMetadataProvider, SerializeMessage, SplitSchema, TransformError,
-    TypeInfo,
+    unzip_schema::PerformUnzip, BuilderId, CancellationReport, CelLimits, DeserializeMessage,
+    DiagramErrorCode, DynForkClone, DynForkResult, DynSplit, DynType, JsonRegistration,
+    RegisterJson, RegisterSplit, ReplayHarness, Section, SectionMetadata, SectionMetadataProvider,
+    SerializeMessage, SerializeValueFn, SerializedCancellationCause, ServiceCatalog,
+    ServiceCatalogError, SplitSchema, TransformError, TypeInfo,
 };
 
 #[derive(Serialize, JsonSchema)]
@@ -91,6 +93,12 @@ type SplitFn = fn(&SplitSchema, &mut Builder) -> Result<DynSplit, DiagramErrorCo
 type JoinFn = fn(&BufferMap, &mut Builder) -> Result<DynOutput, DiagramErrorCode>;
 type BufferAccessFn = fn(&BufferMap, &mut Builder) -> Result<DynNode, DiagramErrorCode>;
 type ListenFn = fn(&BufferMap, &mut Builder) -> Result<DynOutput, DiagramErrorCode>;
+type CleanupFn = fn(
+    &BufferMap,
+    CleanupWorkflowConditions,
+    &mut IncrementalScopeBuilder,
+    &mut Builder,
+) -> Result<Option<DynOutput>, DiagramErrorCode>;
 type CreateBufferFn = fn(BufferSettings, &mut Builder) -> AnyBuffer;
 type CreateTriggerFn = fn(&mut Builder) -> DynNode;
 type ToStringFn = fn(&mut Builder) -> DynNode;
@@ -652,11 +660,25 @@ pub struct DiagramElementRegistry {
 
     #[serde(flatten)]
     pub(super) messages: MessageRegistry,
+
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub(super) cel_limits: CelLimits,
+
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub(super) service_catalog: ServiceCatalog,
+
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub(super) replay: Option<ReplayHarness>,
 }
 
 pub(super) struct MessageOperation {
     pub(super) deserialize_impl: Option<DeserializeFn>,
+    pub(super) validate_impl: Option<ValidateFn>,
     pub(super) serialize_impl: Option<SerializeFn>,
+    pub(super) serialize_value_impl: Option<SerializeValueFn>,
     pub(super) fork_clone_impl: Option<ForkCloneFn>,
     pub(super) unzip_impl: Option<Box<dyn PerformUnzip>>,
     pub(super) fork_result_impl: Option<ForkResultFn>,
@@ -664,6 +686,7 @@ pub(super) struct MessageOperation {
     pub(super) join_impl: Option<JoinFn>,
     pub(super) buffer_access_impl: Option<BufferAccessFn>,
     pub(super) listen_impl: Option<ListenFn>,
+    pub(super) cleanup_impl: Option<CleanupFn>,
     pub(super) to_string_impl: Option<ToStringFn>,
     pub(super) create_buffer_impl: CreateBufferFn,
     pub(super) create_trigger_impl: CreateTriggerFn,
@@ -677,7 +700,9 @@ impl MessageOperation {
     {
         Self {
             deserialize_impl: None,
+            validate_impl: None,
             serialize_impl: None,
+            serialize_value_impl: None,
             fork_clone_impl: None,
             unzip_impl: None,
             fork_result_impl: None,
@@ -685,6 +710,7 @@ impl MessageOperation {
             join_impl: None,
             buffer_access_impl: None,
             listen_impl: None,
+            cleanup_impl: None,
             to_string_impl: None,
             create_buffer_impl: |settings, builder| {
                 builder.create_buffer::<T>(settings).as_any_buffer()
@@ -851,6 +877,14 @@ impl MessageRegistry {
         self.messages.get(&TypeInfo::of::<T>())
     }
 
+    /// Find a message registration using the type name that it is published
+    /// under.
+    pub fn get_by_type_name(&self, type_name: &str) -> Option<(&TypeInfo, &MessageRegistration)> {
+        self.messages
+            .iter()
+            .find(|(_, reg)| reg.type_name == type_name)
+    }
+
     pub fn deserialize(
         &self,
         target_type: &TypeInfo,
@@ -1200,6 +1234,25 @@ impl MessageRegistry {
             .and_then(|f| f(buffers, builder))
     }
 
+    /// Begin a cleanup workflow whose scope receives the keys of `buffers` as
+    /// its request. The output that is returned will deliver the keys into the
+    /// cleanup workflow. It will only be returned the first time that this is
+    /// called for the scope.
+    pub(super) fn cleanup(
+        &self,
+        key_type: &TypeInfo,
+        buffers: &BufferMap,
+        conditions: CleanupWorkflowConditions,
+        scope: &mut IncrementalScopeBuilder,
+        builder: &mut Builder,
+    ) -> Result<Option<DynOutput>, DiagramErrorCode> {
+        self.messages
+            .get(key_type)
+            .and_then(|reg| reg.operations.cleanup_impl.as_ref())
+            .ok_or_else(|| DiagramErrorCode::CannotCleanup(*key_type))
+            .and_then(|f| f(buffers, conditions, scope, builder))
+    }
+
     pub(super) fn register_listen<T>(&mut self) -> bool
     where
         T: Send + Sync + 'static + Any + Accessor,
@@ -1216,6 +1269,19 @@ impl MessageRegistry {
         ops.listen_impl =
             Some(|buffers, builder| Ok(builder.try_listen::<T>(buffers)?.output().into()));
 
+        ops.cleanup_impl = Some(|buffers, conditions, scope, builder| {
+            let buffers = T::Buffers::try_from_buffer_map(buffers)?;
+            let request = scope.set_request::<T>(builder.commands())?;
+            begin_cleanup_workflow(
+                buffers,
+                scope.builder_scope_context().scope,
+                conditions,
+                builder,
+            );
+
+            Ok(request.begin_scope)
+        });
+
         true
     }
 
@@ -1289,6 +1355,9 @@ impl Default for DiagramElementRegistry {
             nodes: Default::default(),
             sections: Default::default(),
             messages: MessageRegistry::new(),
+            cel_limits: Default::default(),
+            service_catalog: Default::default(),
+            replay: None,
         };
 
         registry.register_builtin_messages();
@@ -1310,6 +1379,9 @@ impl DiagramElementRegistry {
             nodes: Default::default(),
             sections: Default::default(),
             messages: MessageRegistry::new(),
+            cel_limits: Default::default(),
+            service_catalog: Default::default(),
+            replay: None,
         }
     }
 
@@ -1474,6 +1546,58 @@ impl DiagramElementRegistry {
         self.messages.get::<T>()
     }
 
+    /// Serialize a message whose type is only known at runtime. This returns
+    /// [`None`] if the type was not registered or does not support
+    /// serialization.
+    pub fn serialize_any(&self, message: &dyn Any) -> Option<JsonMessage> {
+        let type_id = (*message).type_id();
+        let (_, registration) = self
+            .messages
+            .messages
+            .iter()
+            .find(|(info, _)| info.type_id == type_id)?;
+        (registration.operations.serialize_value_impl?)(message).ok()
+    }
+
+    /// Set the limits that will be applied to every CEL program used by
+    /// diagrams that are built with this registry.
+    pub fn set_cel_limits(&mut self, limits: CelLimits) -> &mut Self {
+        self.cel_limits = limits;
+        self
+    }
+
+    /// Get the limits that are applied to CEL programs.
+    pub fn cel_limits(&self) -> &CelLimits {
+        &self.cel_limits
+    }
+
+    /// Get the catalog of services that diagrams can use with the `call`
+    /// operation. Services can be added through this reference since clones
+    /// of a [`ServiceCatalog`] share their entries.
+    pub fn service_catalog(&self) -> &ServiceCatalog {
+        &self.service_catalog
+    }
+
+    /// Use a different [`ServiceCatalog`] for the `call` operation, e.g. one
+    /// that is also being kept as a resource in the [`World`](bevy_ecs::prelude::World).
+    pub fn set_service_catalog(&mut self, catalog: ServiceCatalog) -> &mut Self {
+        self.service_catalog = catalog;
+        self
+    }
+
+    /// Build diagram nodes as replays of their recorded outputs instead of
+    /// using their registered builders. Only nodes that have outputs in the
+    /// [`ReplayHarness`] are replaced. Pass [`None`] to build live nodes again.
+    pub fn set_replay(&mut self, replay: Option<ReplayHarness>) -> &mut Self {
+        self.replay = replay;
+        self
+    }
+
+    /// Get the [`ReplayHarness`] that diagram nodes are being replayed from.
+    pub fn replay(&self) -> Option<&ReplayHarness> {
+        self.replay.as_ref()
+    }
+
     /// Register useful messages that are known to the bevy impulse library.
     /// This will be run automatically when you create using [`Self::default()`]
     /// or [`Self::new()`].
@@ -1489,6 +1613,15 @@ impl DiagramElementRegistry {
             .register_message::<TransformError>()
             .with_to_string();
 
+        self.opt_out()
+            .no_serializing()
+            .no_deserializing()
+            .register_message::<ServiceCatalogError>()
+            .with_to_string();
+
+        self.register_message::<SerializedCancellationCause>();
+        self.register_message::<CancellationReport>();
+
         self.register_message::<String>();
         self.register_message::<u8>();
         self.register_message::<u16>();
@@ -0,0 +1,473 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use bevy_ecs::prelude::Entity;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Builder, Cancellation, CancellationCause, Disposal, DisposalCause, Node, OperationLabels,
+    TriggeredCancellation,
+};
+
+use super::JsonMessage;
+
+/// A serializable explanation of why a request was cancelled. Unlike
+/// [`SerializedCancellationCause`](super::SerializedCancellationCause), this
+/// includes every operation that was involved and, for unreachable terminals,
+/// the full chain of disposals, so it can be returned to remote callers.
+///
+/// Scopes and sections send this to their `on_cancel` target instead of a
+/// [`SerializedCancellationCause`](super::SerializedCancellationCause) when
+/// `report_cancellation` is set.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct CancellationReport {
+    /// A human-readable description of the cancellation.
+    pub message: String,
+    pub cause: CancellationReportCause,
+    /// Cancellations that happened inside of cancellation workflows that were
+    /// triggered by this cancellation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub while_cancelling: Vec<CancellationReport>,
+}
+
+/// A serializable explanation of why the output of an operation was disposed.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct DisposalReport {
+    /// A human-readable description of the disposal.
+    pub message: String,
+    pub cause: DisposalReportCause,
+}
+
+/// An operation that is referred to by a report.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct OperationReport {
+    /// The name of the operation in the diagram, prefixed by the namespaces of
+    /// any sections or scopes that it is nested inside of. This is [`None`] for
+    /// operations that were not named, e.g. internal operations of a builtin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The entity of the operation, e.g. `5v1`. This is only meaningful while
+    /// the workflow is still running, but it can help when comparing logs.
+    pub entity: String,
+}
+
+impl OperationReport {
+    fn new(operation: Entity, labels: &OperationLabels) -> Self {
+        Self {
+            name: labels.get(operation).map(|label| label.to_string()),
+            entity: format!("{operation:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CancellationReportCause {
+    /// The promise taken by the requester was dropped without being detached.
+    TargetDropped,
+    /// There are no terminating operations that can be reached anymore.
+    Unreachable {
+        /// The scope whose terminal became unreachable.
+        scope: OperationReport,
+        /// The disposals that led to the terminal being unreachable, in the
+        /// order that they happened.
+        disposals: Vec<DisposalReport>,
+    },
+    /// A filtering operation has triggered a cancellation.
+    Filtered {
+        operation: OperationReport,
+        reason: Option<String>,
+    },
+    /// The workflow triggered its own cancellation.
+    Triggered {
+        operation: OperationReport,
+        value: Option<JsonMessage>,
+    },
+    /// A request was supplanted by a newer one.
+    Supplanted {
+        operation: OperationReport,
+        supplanted_by: OperationReport,
+    },
+    /// An operation was given an invalid span to operate on.
+    InvalidSpan {
+        from: OperationReport,
+        to: Option<OperationReport>,
+    },
+    /// There is a circular dependency between two or more collect operations.
+    CircularCollect {
+        conflicts: Vec<[OperationReport; 2]>,
+    },
+    /// Operations kept executing until the flush reached its loop limit.
+    Livelock { operations: Vec<OperationReport> },
+    /// A request became undeliverable because the sender was dropped.
+    Undeliverable,
+    /// A promise can never be delivered because its mutex was poisoned.
+    PoisonedMutexInPromise,
+    /// An operation in the workflow was broken.
+    Broken { operation: OperationReport },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DisposalReportCause {
+    /// A request was supplanted by a newer one.
+    Supplanted {
+        operation: OperationReport,
+        supplanted_by: OperationReport,
+    },
+    /// An operation filtered out its output.
+    Filtered {
+        operation: OperationReport,
+        reason: Option<String>,
+    },
+    /// An operation disposed of one of its output branches.
+    Branching {
+        operation: OperationReport,
+        target: OperationReport,
+        reason: Option<String>,
+    },
+    /// A buffer key was disposed.
+    BufferKey {
+        operation: OperationReport,
+        buffer: OperationReport,
+    },
+    /// The service needed by an operation is no longer available.
+    ServiceUnavailable { operation: OperationReport },
+    /// The task of an operation was despawned.
+    TaskDespawned { operation: OperationReport },
+    /// A mutex was poisoned in an operation.
+    PoisonedMutex { operation: OperationReport },
+    /// A scope was cancelled so its output has been disposed.
+    Scope {
+        cancellation: Box<CancellationReport>,
+    },
+    /// Some streams of an operation never emitted anything.
+    UnusedStreams {
+        operation: OperationReport,
+        streams: Vec<String>,
+    },
+    /// Some operations were trimmed.
+    Trimming {
+        operation: OperationReport,
+        trimmed: Vec<OperationReport>,
+    },
+    /// A gate was closed.
+    ClosedGate {
+        operation: OperationReport,
+        buffers: Vec<OperationReport>,
+    },
+    /// A spread operation had nothing to spread.
+    EmptySpread { operation: OperationReport },
+    /// A collect operation cannot reach its minimum number of items.
+    DeficientCollection {
+        operation: OperationReport,
+        min: usize,
+        actual: usize,
+    },
+    /// A split operation did not send a value to some of its connections.
+    IncompleteSplit {
+        operation: OperationReport,
+        missing_keys: Vec<Option<String>>,
+    },
+}
+
+impl CancellationReport {
+    /// Create a node that turns [`Cancellation`] messages into reports.
+    pub(super) fn create_serializer(builder: &mut Builder) -> Node<Cancellation, JsonMessage> {
+        builder.create_map_block(|cancellation: Cancellation| cancellation.report().to_json())
+    }
+
+    pub(super) fn to_json(&self) -> JsonMessage {
+        // SAFETY: Reports only contain types that always serialize
+        // successfully.
+        serde_json::to_value(self).unwrap()
+    }
+}
+
+/// Get the message that triggered a cancellation. Messages that were sent to
+/// the cancel operation as a [`JsonMessage`] or a [`String`] keep their type.
+/// Anything else is reported in its string form.
+pub(super) fn triggered_value(triggered: &TriggeredCancellation) -> Option<JsonMessage> {
+    if let Some(message) = &triggered.message {
+        if let Some(json) = message.downcast_ref::<JsonMessage>() {
+            return Some(json.clone());
+        }
+    }
+
+    triggered.value.clone().map(JsonMessage::String)
+}
+
+impl From<&Cancellation> for CancellationReport {
+    fn from(cancellation: &Cancellation) -> Self {
+        let labels = &cancellation.labels;
+        let op = |e: Entity| OperationReport::new(e, labels);
+        let cause = match cancellation.cause.as_ref() {
+            CancellationCause::TargetDropped(_) => CancellationReportCause::TargetDropped,
+            CancellationCause::Unreachable(unreachable) => CancellationReportCause::Unreachable {
+                scope: op(unreachable.scope),
+                disposals: unreachable.disposals.iter().map(Into::into).collect(),
+            },
+            CancellationCause::Filtered(filtered) => CancellationReportCause::Filtered {
+                operation: op(filtered.filtered_at_node),
+                reason: filtered.reason.as_ref().map(|r| r.to_string()),
+            },
+            CancellationCause::Triggered(triggered) => CancellationReportCause::Triggered {
+                operation: op(triggered.cancelled_at_node),
+                value: triggered_value(triggered),
+            },
+            CancellationCause::Supplanted(supplanted) => CancellationReportCause::Supplanted {
+                operation: op(supplanted.supplanted_at_node),
+                supplanted_by: op(supplanted.supplanted_by_node),
+            },
+            CancellationCause::InvalidSpan(span) => CancellationReportCause::InvalidSpan {
+                from: op(span.from_point),
+                to: span.to_point.map(op),
+            },
+            CancellationCause::CircularCollect(circular) => {
+                CancellationReportCause::CircularCollect {
+                    conflicts: circular
+                        .conflicts
+                        .iter()
+                        .map(|[a, b]| [op(*a), op(*b)])
+                        .collect(),
+                }
+            }
+            CancellationCause::Livelock(livelock) => CancellationReportCause::Livelock {
+                operations: livelock
+                    .operations
+                    .iter()
+                    .map(|o| op(o.operation))
+                    .collect(),
+            },
+            CancellationCause::Undeliverable => CancellationReportCause::Undeliverable,
+            CancellationCause::PoisonedMutexInPromise => {
+                CancellationReportCause::PoisonedMutexInPromise
+            }
+            CancellationCause::Broken(broken) => CancellationReportCause::Broken {
+                operation: op(broken.node),
+            },
+        };
+
+        Self {
+            message: cancellation.cause.display_with(labels).to_string(),
+            cause,
+            while_cancelling: cancellation
+                .while_cancelling
+                .iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl From<&Disposal> for DisposalReport {
+    fn from(disposal: &Disposal) -> Self {
+        let labels = &disposal.labels;
+        let op = |e: Entity| OperationReport::new(e, labels);
+        let ops = |es: &[Entity]| es.iter().copied().map(op).collect();
+        let cause = match disposal.cause.as_ref() {
+            DisposalCause::Supplanted(supplanted) => DisposalReportCause::Supplanted {
+                operation: op(supplanted.supplanted_at_node),
+                supplanted_by: op(supplanted.supplanted_by_node),
+            },
+            DisposalCause::Filtered(filtered) => DisposalReportCause::Filtered {
+                operation: op(filtered.filtered_at_node),
+                reason: filtered.reason.as_ref().map(|r| r.to_string()),
+            },
+            DisposalCause::Branching(branch) => DisposalReportCause::Branching {
+                operation: op(branch.branched_at_node),
+                target: op(branch.disposed_for_target),
+                reason: branch.reason.as_ref().map(|r| r.to_string()),
+            },
+            DisposalCause::BufferKey(key) => DisposalReportCause::BufferKey {
+                operation: op(key.accessor_node),
+                buffer: op(key.key_for_buffer),
+            },
+            DisposalCause::ServiceUnavailable(unavailable) => {
+                DisposalReportCause::ServiceUnavailable {
+                    operation: op(unavailable.for_node),
+                }
+            }
+            DisposalCause::TaskDespawned(despawned) => DisposalReportCause::TaskDespawned {
+                operation: op(despawned.node),
+            },
+            DisposalCause::PoisonedMutex(poisoned) => DisposalReportCause::PoisonedMutex {
+                operation: op(poisoned.for_node),
+            },
+            DisposalCause::Scope(cancellation) => DisposalReportCause::Scope {
+                cancellation: Box::new(cancellation.into()),
+            },
+            DisposalCause::UnusedStreams(unused) => DisposalReportCause::UnusedStreams {
+                operation: op(unused.node),
+                streams: unused.streams.iter().map(|s| s.to_string()).collect(),
+            },
+            DisposalCause::Trimming(trimming) => DisposalReportCause::Trimming {
+                operation: op(trimming.trimmer),
+                trimmed: ops(&trimming.nodes),
+            },
+            DisposalCause::ClosedGate(gate) => DisposalReportCause::ClosedGate {
+                operation: op(gate.gate_node),
+                buffers: ops(&gate.closed_buffers),
+            },
+            DisposalCause::EmptySpread(spread) => DisposalReportCause::EmptySpread {
+                operation: op(spread.spread_node),
+            },
+            DisposalCause::DeficientCollection(collection) => {
+                DisposalReportCause::DeficientCollection {
+                    operation: op(collection.collect_node),
+                    min: collection.min,
+                    actual: collection.actual,
+                }
+            }
+            DisposalCause::IncompleteSplit(split) => DisposalReportCause::IncompleteSplit {
+                operation: op(split.split_node),
+                missing_keys: split
+                    .missing_keys
+                    .iter()
+                    .map(|key| key.as_ref().map(|k| k.to_string()))
+                    .collect(),
+            },
+        };
+
+        Self {
+            message: disposal.cause.display_with(labels).to_string(),
+            cause,
+        }
+    }
+}
+
+impl Cancellation {
+    /// Create a serializable report that explains this cancellation.
+    pub fn report(&self) -> CancellationReport {
+        self.into()
+    }
+}
+
+impl Disposal {
+    /// Create a serializable report that explains this disposal.
+    pub fn report(&self) -> DisposalReport {
+        self.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{diagram::testing::*, testing::*, *};
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn test_unreachable_report_names_operations() {
+        let mut fixture = DiagramTestFixture::new();
+
+        fixture
+            .registry
+            .register_node_builder(
+                NodeBuilderOptions::new("check_even".to_string()),
+                |builder: &mut Builder, _config: ()| {
+                    builder.create_map_block(|v: i64| if v % 2 == 0 { Ok(v) } else { Err(v) })
+                },
+            )
+            .with_fork_result();
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "check_even",
+            "ops": {
+                "check_even": {
+                    "type": "node",
+                    "builder": "check_even",
+                    "next": "fork_result",
+                },
+                "fork_result": {
+                    "type": "fork_result",
+                    "ok": { "builtin": "terminate" },
+                    "err": { "builtin": "dispose" },
+                },
+            },
+        }))
+        .unwrap();
+
+        let result = fixture
+            .spawn_and_run::<JsonMessage, JsonMessage>(&diagram, json!(5))
+            .unwrap_err();
+        let report = result.downcast_ref::<Cancellation>().unwrap().report();
+        let CancellationReportCause::Unreachable { disposals, .. } = &report.cause else {
+            panic!("unexpected cause: {report:?}");
+        };
+        assert!(disposals.iter().any(|disposal| matches!(
+            &disposal.cause,
+            DisposalReportCause::Branching { operation, .. }
+                if operation.name.as_deref() == Some("fork_result")
+        )));
+
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["cause"]["type"], "unreachable");
+        let round_trip: CancellationReport = serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip, report);
+    }
+
+    #[test]
+    fn test_triggered_report_keeps_message_type() {
+        let mut context = TestingContext::minimal_plugins();
+
+        for request in [
+            json!("123"),
+            json!("true"),
+            json!(123),
+            json!({ "a": [1, "b"] }),
+        ] {
+            let value = triggered_value(&mut context, request.clone());
+            assert_eq!(value, Some(request));
+        }
+
+        // Strings must not be mistaken for the JSON that they look like.
+        for request in ["123", "true", "abc"] {
+            let value = triggered_value(&mut context, request.to_owned());
+            assert_eq!(value, Some(JsonMessage::from(request)));
+        }
+    }
+
+    fn triggered_value<T>(context: &mut TestingContext, request: T) -> Option<JsonMessage>
+    where
+        T: 'static + Send + Sync + ToString,
+    {
+        let workflow = context.spawn_io_workflow(|scope: Scope<T, T>, builder| {
+            let cancel = builder.create_cancel::<T>();
+            scope
+                .input
+                .chain(builder)
+                .map_block(|value: T| Err::<T, _>(value))
+                .fork_result(|ok| ok.connect(scope.terminate), |err| err.connect(cancel));
+        });
+
+        let mut promise =
+            context.command(|commands| commands.request(request, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        let PromiseState::Cancelled(cancellation) = promise.take() else {
+            panic!("workflow should have been cancelled");
+        };
+
+        let report = cancellation.report();
+        let CancellationReportCause::Triggered { value, .. } = report.cause else {
+            panic!("unexpected cause: {report:?}");
+        };
+        value
+    }
+}
@@ -27,12 +27,15 @@ use serde::{Deserialize, Serialize};
 use smallvec::smallvec;
 
 use crate::{
-    standard_input_connection, BuildDiagramOperation, BuildStatus, Builder, ConnectIntoTarget,
-    DiagramContext, DiagramErrorCode, DynOutput, IncrementalScopeBuilder, IncrementalScopeRequest,
-    IncrementalScopeResponse, InferMessageType, NextOperation, OperationName, OperationRef,
-    Operations, ScopeSettings, StreamOutRef,
+    standard_input_connection, BuildDiagramOperation, BuildStatus, Builder, BuiltinTarget,
+    CancellationReport, CleanupWorkflows, ConnectIntoTarget, DiagramContext, DiagramErrorCode,
+    DynOutput, IncrementalScopeBuilder, IncrementalScopeRequest, IncrementalScopeResponse,
+    InferMessageType, NextOperation, OperationName, OperationRef, Operations, ScopeSettings,
+    SerializedCancellationCause, StreamOutRef,
 };
 
+use super::{cleanup_schema::validate_cleanup_names, workflow_builder::ConnectToCancel};
+
 /// The schema to define a scope within a diagram.
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -45,15 +48,37 @@ pub struct ScopeSchema {
     /// sometimes insert implicit operations into the workflow, such as implicit
     /// serializing and deserializing. These implicit operations may be fallible.
     ///
-    /// This field indicates how a failed implicit operation should be handled.
-    /// If left unspecified, an implicit error will cause the entire workflow to
-    /// be cancelled.
+    /// This field indicates how a failed implicit operation inside of this
+    /// scope should be handled. If left unspecified, an implicit error will
+    /// cause the entire workflow to be cancelled, unless `on_cancel` is given,
+    /// in which case only the scope will be cancelled.
     #[serde(default)]
     pub on_implicit_error: Option<NextOperation>,
 
+    /// Where to send a [`SerializedCancellationCause`] if this scope gets
+    /// cancelled, e.g. because an operation inside of it sent a message to
+    /// `{ "builtin": "cancel" }` or because its terminate operation can no
+    /// longer be reached.
+    ///
+    /// If left unspecified, a cancellation of this scope will cancel the
+    /// scope that contains it.
+    #[serde(default)]
+    pub on_cancel: Option<NextOperation>,
+
+    /// Send a [`CancellationReport`] to `on_cancel` instead of a
+    /// [`SerializedCancellationCause`]. The report names every operation that
+    /// was involved in the cancellation.
+    #[serde(default)]
+    pub report_cancellation: bool,
+
     /// Operations that exist inside this scope.
     pub ops: Operations,
 
+    /// Cleanup workflows that run when a session of this scope is finished.
+    /// Their names share a namespace with the operations in `ops`.
+    #[serde(default)]
+    pub cleanup: CleanupWorkflows,
+
     /// Where to connect streams that are coming out of this scope.
     #[serde(default)]
     pub stream_out: HashMap<OperationName, NextOperation>,
@@ -73,13 +98,52 @@ impl BuildDiagramOperation for ScopeSchema {
         builder: &mut Builder,
         ctx: &mut DiagramContext,
     ) -> Result<BuildStatus, DiagramErrorCode> {
-        let scope = IncrementalScopeBuilder::begin(self.settings.clone(), builder);
+        validate_cleanup_names(&self.cleanup, &self.ops)?;
+
+        let mut scope = IncrementalScopeBuilder::begin(self.settings.clone(), builder);
+        let scope_context = scope.builder_scope_context();
+        ctx.label_operation(scope_context.scope, &ctx.into_operation_ref(id));
+
+        // Cancelling from inside of this scope only cancels this scope.
+        let scope_cancel = ctx.into_child_operation_ref(
+            id,
+            &NextOperation::Builtin {
+                builtin: BuiltinTarget::Cancel,
+            },
+        );
+        let on_implicit_error = match (&self.on_implicit_error, &self.on_cancel) {
+            (Some(target), _) => ctx.into_child_operation_ref(id, target),
+            (None, Some(_)) => scope_cancel.clone(),
+            (None, None) => ctx.get_implicit_error_target(),
+        };
+
+        let mut scope_builder = Builder {
+            context: scope_context,
+            commands: builder.commands(),
+        };
+        ctx.set_connect_into_target_in_scope(
+            scope_cancel,
+            ConnectToCancel::new(&mut scope_builder)?,
+            scope_context,
+            on_implicit_error.clone(),
+        )?;
+
+        if let Some(on_cancel) = &self.on_cancel {
+            let cancellation = scope.cancellation_output(builder.commands());
+            let serialize = if self.report_cancellation {
+                CancellationReport::create_serializer(builder)
+            } else {
+                SerializedCancellationCause::create_serializer(builder)
+            };
+            cancellation.connect_to(&serialize.input.into(), builder)?;
+            ctx.add_output_into_target(on_cancel, serialize.output.into());
+        }
 
         for (stream_in_id, stream_out_target) in &self.stream_out {
             ctx.set_connect_into_target(
                 StreamOutRef::new_for_scope(id.clone(), stream_in_id.clone()),
                 ConnectScopeStream {
-                    scope_id: scope.builder_scope_context().scope,
+                    scope_id: scope_context.scope,
                     parent_scope_id: builder.scope(),
                     stream_out_target: ctx.into_operation_ref(stream_out_target),
                     connection: None,
@@ -93,7 +157,19 @@ impl BuildDiagramOperation for ScopeSchema {
                 child_id,
                 op,
                 self.ops.clone(),
-                Some(scope.builder_scope_context()),
+                Some(scope_context),
+                Some(on_implicit_error.clone()),
+            );
+        }
+
+        for (cleanup_id, cleanup) in &self.cleanup {
+            ctx.add_child_operation(
+                id,
+                cleanup_id,
+                cleanup,
+                self.ops.clone(),
+                Some(scope_context),
+                Some(on_implicit_error.clone()),
             );
         }
 
@@ -286,6 +362,7 @@ mod tests {
         prelude::*,
         stream::tests::*,
         testing::*,
+        Cancellation, OperationLabel,
     };
     use serde_json::json;
 
@@ -316,6 +393,17 @@ mod tests {
 
         let result: i64 = fixture.spawn_and_run(&diagram, 4_i64).unwrap();
         assert_eq!(result, 12);
+
+        let labels: Vec<String> = fixture
+            .context
+            .app
+            .world
+            .query::<&OperationLabel>()
+            .iter(&fixture.context.app.world)
+            .map(|label| label.to_string())
+            .collect();
+        assert!(labels.iter().any(|label| label == "scope"));
+        assert!(labels.iter().any(|label| label == "scope:multiply"));
     }
 
     #[derive(Serialize, Deserialize, JsonSchema)]
@@ -500,5 +588,190 @@ mod tests {
         assert_eq!(outcome_stream_string, ["5", "10", "-3", "-27", "hello"]);
     }
 
+    fn check_positive_scope_diagram(err: JsonMessage, on_cancel: Option<JsonMessage>) -> Diagram {
+        let mut scope = json!({
+            "type": "scope",
+            "start": "check",
+            "ops": {
+                "check": {
+                    "type": "node",
+                    "builder": "check_positive",
+                    "next": "fork",
+                },
+                "fork": {
+                    "type": "fork_result",
+                    "ok": { "builtin": "terminate" },
+                    "err": err,
+                },
+            },
+            "next": { "builtin" : "terminate" },
+        });
+
+        if let Some(on_cancel) = on_cancel {
+            scope["on_cancel"] = on_cancel;
+        }
+
+        Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "scope",
+            "ops": {
+                "scope": scope,
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_scope_on_cancel() {
+        let mut fixture = DiagramTestFixture::new();
+
+        let diagram = check_positive_scope_diagram(
+            json!({ "builtin": "cancel" }),
+            Some(json!({ "builtin": "terminate" })),
+        );
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(4))
+            .unwrap();
+        assert_eq!(result, 4);
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(-4))
+            .unwrap();
+        assert_eq!(
+            serde_json::from_value::<SerializedCancellationCause>(result).unwrap(),
+            SerializedCancellationCause::Triggered {
+                value: Some(JsonMessage::from(-4)),
+            },
+        );
+        assert!(fixture.context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_scope_on_cancel_unreachable() {
+        let mut fixture = DiagramTestFixture::new();
+
+        let diagram = check_positive_scope_diagram(
+            json!({ "builtin": "dispose" }),
+            Some(json!({ "builtin": "terminate" })),
+        );
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(-4))
+            .unwrap();
+        assert!(matches!(
+            serde_json::from_value(result).unwrap(),
+            SerializedCancellationCause::Unreachable { .. },
+        ));
+        assert!(fixture.context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_scope_on_cancel_report() {
+        let mut fixture = DiagramTestFixture::new();
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "scope",
+            "ops": {
+                "scope": {
+                    "type": "scope",
+                    "start": "check",
+                    "ops": {
+                        "check": {
+                            "type": "node",
+                            "builder": "check_positive",
+                            "next": "fork",
+                        },
+                        "fork": {
+                            "type": "fork_result",
+                            "ok": { "builtin": "terminate" },
+                            "err": { "builtin": "dispose" },
+                        },
+                    },
+                    "next": { "builtin" : "terminate" },
+                    "on_cancel": { "builtin": "terminate" },
+                    "report_cancellation": true,
+                },
+            },
+        }))
+        .unwrap();
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(-4))
+            .unwrap();
+        let report: CancellationReport = serde_json::from_value(result).unwrap();
+        let CancellationReportCause::Unreachable { disposals, .. } = &report.cause else {
+            panic!("unexpected cause: {report:?}");
+        };
+        assert!(!disposals.is_empty());
+        assert!(fixture.context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_scope_cancel_propagates() {
+        let mut fixture = DiagramTestFixture::new();
+
+        // Without an on_cancel target, cancelling the scope cancels the whole
+        // workflow.
+        for err in [
+            json!({ "builtin": "cancel" }),
+            json!({ "builtin": "dispose" }),
+        ] {
+            let diagram = check_positive_scope_diagram(err, None);
+            let result = fixture.spawn_and_run::<_, i64>(&diagram, -4_i64);
+            assert!(result.unwrap_err().downcast_ref::<Cancellation>().is_some());
+            assert!(fixture.context.no_unhandled_errors());
+        }
+    }
+
+    #[test]
+    fn test_scope_implicit_error_cancels_scope() {
+        let mut fixture = DiagramTestFixture::new();
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "scope",
+            "ops": {
+                "scope": {
+                    "type": "scope",
+                    "start": "transform",
+                    "ops": {
+                        "transform": {
+                            "type": "transform",
+                            "cel": "request",
+                            "next": "multiply",
+                        },
+                        "multiply": {
+                            "type": "node",
+                            "builder": "multiply3",
+                            "next": { "builtin" : "terminate" },
+                        },
+                    },
+                    "next": { "builtin" : "terminate" },
+                    "on_cancel": "cancelled",
+                },
+                "cancelled": {
+                    "type": "transform",
+                    "cel": "request.type",
+                    "next": { "builtin": "terminate" },
+                },
+            },
+        }))
+        .unwrap();
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(4))
+            .unwrap();
+        assert_eq!(result, 12);
+
+        // The implicit deserialization into multiply3 fails inside the scope,
+        // which cancels only the scope.
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from("four"))
+            .unwrap();
+        assert_eq!(result, "triggered");
+    }
+
     // TODO(@mxgrey): Add an interruptibility test
 }
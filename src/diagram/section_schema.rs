@@ -25,9 +25,10 @@ use crate::{
 };
 
 use super::{
-    BuildDiagramOperation, BuildStatus, BuilderId, DiagramContext, DiagramElementRegistry,
-    DiagramErrorCode, DynInputSlot, DynOutput, NamespacedOperation, NextOperation, OperationName,
-    OperationRef, Operations, RedirectConnection, TypeInfo,
+    workflow_builder::ConnectToCancel, BuildDiagramOperation, BuildStatus, BuilderId,
+    BuiltinTarget, DiagramContext, DiagramElementRegistry, DiagramErrorCode, DynInputSlot,
+    DynOutput, NamespacedOperation, NextOperation, OperationName, OperationRef, Operations,
+    RedirectConnection, TypeInfo,
 };
 
 pub use bevy_impulse_derive::Section;
@@ -48,6 +49,23 @@ pub struct SectionSchema {
     pub(super) config: serde_json::Value,
     #[serde(default)]
     pub(super) connect: HashMap<Arc<str>, NextOperation>,
+    /// Where to send a [`SerializedCancellationCause`](super::SerializedCancellationCause)
+    /// if an operation inside of this section triggers a cancellation or has
+    /// an implicit error.
+    ///
+    /// A section does not have its own scope, so only cancellations that are
+    /// triggered from inside of the section can be caught this way. This is
+    /// only supported for sections that come from templates.
+    ///
+    /// If left unspecified, the cancellation will be passed along to the scope
+    /// that contains this section.
+    #[serde(default)]
+    pub(super) on_cancel: Option<NextOperation>,
+    /// Send a [`CancellationReport`](super::CancellationReport) to `on_cancel`
+    /// instead of a [`SerializedCancellationCause`](super::SerializedCancellationCause).
+    /// The report names every operation that was involved in the cancellation.
+    #[serde(default)]
+    pub(super) report_cancellation: bool,
 }
 
 impl BuildDiagramOperation for SectionSchema {
@@ -59,6 +77,12 @@ impl BuildDiagramOperation for SectionSchema {
     ) -> Result<BuildStatus, DiagramErrorCode> {
         match &self.provider {
             SectionProvider::Builder(section_builder) => {
+                if self.on_cancel.is_some() {
+                    return Err(
+                        SectionError::OnCancelUnsupported(Arc::clone(section_builder)).into(),
+                    );
+                }
+
                 let section = ctx
                     .registry
                     .get_section_registration(section_builder)?
@@ -100,8 +124,50 @@ impl BuildDiagramOperation for SectionSchema {
             SectionProvider::Template(section_template) => {
                 let section = ctx.templates.get_template(section_template)?;
 
+                let section_cancel = ctx.into_child_operation_ref(
+                    id,
+                    &NextOperation::Builtin {
+                        builtin: BuiltinTarget::Cancel,
+                    },
+                );
+                let on_implicit_error = if let Some(on_cancel) = &self.on_cancel {
+                    let connect = ConnectToCancel::redirect(
+                        on_cancel,
+                        self.report_cancellation,
+                        builder,
+                        ctx,
+                    )?;
+                    ctx.set_connect_into_target_in_scope(
+                        section_cancel.clone(),
+                        connect,
+                        builder.context,
+                        section_cancel.clone(),
+                    )?;
+                    Some(section_cancel)
+                } else {
+                    // Cancelling from inside the section will cancel the scope
+                    // that the section is in.
+                    let parent_cancel = ctx.into_operation_ref(&NextOperation::Builtin {
+                        builtin: BuiltinTarget::Cancel,
+                    });
+                    ctx.set_connect_into_target_in_scope(
+                        section_cancel,
+                        RedirectConnection::new(parent_cancel),
+                        builder.context,
+                        ctx.get_implicit_error_target(),
+                    )?;
+                    None
+                };
+
                 for (child_id, op) in section.ops.iter() {
-                    ctx.add_child_operation(id, child_id, op, section.ops.clone(), None);
+                    ctx.add_child_operation(
+                        id,
+                        child_id,
+                        op,
+                        section.ops.clone(),
+                        None,
+                        on_implicit_error.clone(),
+                    );
                 }
 
                 section
@@ -380,6 +446,9 @@ impl InputRemapping {
 pub enum SectionError {
     #[error("operation has extra output [{0}] that is not in the section")]
     UnknownOutput(OperationName),
+
+    #[error("section builder [{0}] does not support on_cancel, only section templates do")]
+    OnCancelUnsupported(BuilderId),
 }
 
 #[cfg(test)]
@@ -389,8 +458,10 @@ mod tests {
 
     use crate::{
         diagram::testing::DiagramTestFixture, testing::TestingContext, BufferAccess,
-        BufferAccessMut, BufferKey, BufferSettings, Diagram, IntoBlockingCallback, JsonMessage,
-        Node, NodeBuilderOptions, RequestExt, RunCommandsOnWorldExt, SectionBuilderOptions,
+        BufferAccessMut, BufferKey, BufferSettings, Cancellation, CancellationReport,
+        CancellationReportCause, Diagram, IntoBlockingCallback, JsonMessage, Node,
+        NodeBuilderOptions, RequestExt, RunCommandsOnWorldExt, SectionBuilderOptions,
+        SerializedCancellationCause,
     };
 
     use super::*;
@@ -1281,4 +1352,128 @@ mod tests {
             DiagramErrorCode::CircularTemplateDependency(_),
         ));
     }
+
+    fn check_positive_section_diagram(
+        on_cancel: Option<JsonMessage>,
+        report_cancellation: bool,
+    ) -> Diagram {
+        let mut section = json!({
+            "type": "section",
+            "template": "check_template",
+            "connect": {
+                "output": { "builtin": "terminate" },
+            },
+        });
+
+        if let Some(on_cancel) = on_cancel {
+            section["on_cancel"] = on_cancel;
+        }
+
+        if report_cancellation {
+            section["report_cancellation"] = true.into();
+        }
+
+        Diagram::from_json(json!({
+            "version": "0.1.0",
+            "templates": {
+                "check_template": {
+                    "inputs": ["check"],
+                    "outputs": ["output"],
+                    "ops": {
+                        "check": {
+                            "type": "node",
+                            "builder": "check_positive",
+                            "next": "fork",
+                        },
+                        "fork": {
+                            "type": "fork_result",
+                            "ok": "output",
+                            "err": { "builtin": "cancel" },
+                        },
+                    },
+                },
+            },
+            "start": { "section": "check" },
+            "ops": {
+                "section": section,
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_section_on_cancel() {
+        let mut fixture = DiagramTestFixture::new();
+
+        let diagram =
+            check_positive_section_diagram(Some(json!({ "builtin": "terminate" })), false);
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(4))
+            .unwrap();
+        assert_eq!(result, 4);
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(-4))
+            .unwrap();
+        assert_eq!(
+            serde_json::from_value::<SerializedCancellationCause>(result).unwrap(),
+            SerializedCancellationCause::Triggered {
+                value: Some(JsonMessage::from(-4)),
+            },
+        );
+
+        // The report names the operation that triggered the cancellation.
+        let diagram = check_positive_section_diagram(Some(json!({ "builtin": "terminate" })), true);
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from(-4))
+            .unwrap();
+        let report: CancellationReport = serde_json::from_value(result).unwrap();
+        assert!(matches!(
+            report.cause,
+            CancellationReportCause::Triggered { value: Some(value), .. }
+                if value == -4
+        ));
+
+        // Without on_cancel, the cancellation passes through the section.
+        let diagram = check_positive_section_diagram(None, false);
+        let result = fixture.spawn_and_run::<_, JsonMessage>(&diagram, JsonMessage::from(-4));
+        assert!(result.unwrap_err().downcast_ref::<Cancellation>().is_some());
+
+        // Sections from builders cannot catch cancellations.
+        fixture.registry.register_section_builder(
+            SectionBuilderOptions::new("test_section").with_name("TestSection"),
+            |builder: &mut Builder, _config: ()| {
+                let node = builder.create_map_block(|_: i64| 1_f64);
+                let buffer = builder.create_buffer(BufferSettings::default());
+                TestSection {
+                    foo: node.input,
+                    bar: node.output,
+                    baz: buffer,
+                }
+            },
+        );
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": { "test_section": "foo" },
+            "ops": {
+                "test_section": {
+                    "type": "section",
+                    "builder": "test_section",
+                    "on_cancel": { "builtin": "terminate" },
+                },
+            },
+        }))
+        .unwrap();
+        let err = fixture.spawn_json_io_workflow(&diagram).unwrap_err();
+        assert!(
+            matches!(
+                err.code,
+                DiagramErrorCode::SectionError(SectionError::OnCancelUnsupported(_))
+            ),
+            "{:?}",
+            err
+        );
+    }
 }
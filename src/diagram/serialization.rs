@@ -86,6 +86,13 @@ where
             })
         });
 
+        reg.operations.serialize_value_impl = Some(|value| {
+            let value = value
+                .downcast_ref::<T>()
+                .ok_or_else(|| format!("value is not a [{}]", std::any::type_name::<T>()))?;
+            serde_json::to_value(value).map_err(|err| err.to_string())
+        });
+
         // Serialize and deserialize both generate the schema, so check before
         // generating it.
         if reg.schema.is_none() {
@@ -130,6 +137,12 @@ where
             })
         });
 
+        reg.operations.validate_impl = Some(|message| {
+            T::deserialize(message)
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        });
+
         // Serialize and deserialize both generate the schema, so check before
         // generating it.
         if reg.schema.is_none() {
@@ -247,7 +260,7 @@ impl ImplicitSerialization {
                 serialize.ok.connect_to(&self.serialized_input, builder)?;
 
                 let error_target = ctx.get_implicit_error_target();
-                ctx.add_output_into_target(error_target, serialize.err);
+                ctx.add_output_into_resolved_target(error_target, serialize.err);
 
                 vacant.insert(serialize.input).clone()
             }
@@ -331,7 +344,7 @@ impl ImplicitDeserialization {
                         .connect_to(&self.deserialized_input, builder)?;
 
                     let error_target = ctx.get_implicit_error_target();
-                    ctx.add_output_into_target(error_target, deserialize.err);
+                    ctx.add_output_into_resolved_target(error_target, deserialize.err);
 
                     self.serialized_input = Some(deserialize.input);
                     deserialize.input
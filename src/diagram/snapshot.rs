@@ -0,0 +1,234 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use bevy_ecs::prelude::Entity;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AnyBufferInterfaceStorage, InspectedBuffer, OperationLabel, Service, SessionInspection,
+    SessionStatus, WorkflowInspector,
+};
+
+use super::{DiagramElementRegistry, JsonMessage, OperationReport};
+
+/// A serializable picture of everything that a session of a workflow is
+/// currently holding onto. Use [`WorkflowInspector::snapshot_session`] to
+/// create one.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct SessionSnapshot {
+    /// The session that is running inside of the scope.
+    pub session: String,
+    /// The session that started this one, e.g. the session of the request
+    /// that was sent to the workflow.
+    pub parent_session: String,
+    pub scope: String,
+    /// True if the session has finished and is being cleaned up.
+    pub cleaning_up: bool,
+    /// Operations that are holding an input for this session.
+    pub operations: Vec<OperationReport>,
+    /// Operations that have async tasks running for this session.
+    pub tasks: Vec<OperationReport>,
+    /// Buffers that are holding data for this session.
+    pub buffers: Vec<BufferSnapshot>,
+    /// Sessions of scopes that are nested inside of this session.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<SessionSnapshot>,
+}
+
+/// The contents of one buffer for one session.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct BufferSnapshot {
+    pub buffer: OperationReport,
+    /// The name of the message type that the buffer stores.
+    pub message_type: String,
+    /// Whether the gate of the buffer is open for this session.
+    pub gate_open: bool,
+    /// How many messages are in the buffer.
+    pub len: usize,
+    /// The messages in the buffer, ordered from oldest to newest. This is
+    /// [`None`] if the message type was not registered as serializable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub messages: Option<Vec<JsonMessage>>,
+}
+
+impl<'w> WorkflowInspector<'w> {
+    /// Capture the state of a session of a workflow, including the contents of
+    /// its buffers and any scopes nested inside of it. `session` may be either
+    /// the session of the request that was sent to the workflow or the session
+    /// that the workflow is running it in.
+    ///
+    /// Buffered messages are serialized with `registry`. Buffers whose message
+    /// type cannot be serialized are still reported, but only with their type
+    /// name and length.
+    ///
+    /// Returns [`None`] if the workflow has no such session.
+    pub fn snapshot_session<Request, Response, Streams>(
+        &self,
+        workflow: Service<Request, Response, Streams>,
+        session: Entity,
+        registry: &DiagramElementRegistry,
+    ) -> Option<SessionSnapshot> {
+        self.sessions(workflow)
+            .iter()
+            .find(|inspection| {
+                inspection.session == session || inspection.parent_session == session
+            })
+            .map(|inspection| self.snapshot(inspection, registry))
+    }
+
+    fn snapshot(
+        &self,
+        inspection: &SessionInspection,
+        registry: &DiagramElementRegistry,
+    ) -> SessionSnapshot {
+        SessionSnapshot {
+            session: format!("{:?}", inspection.session),
+            parent_session: format!("{:?}", inspection.parent_session),
+            scope: format!("{:?}", inspection.scope),
+            cleaning_up: inspection.status == SessionStatus::Cleaning,
+            operations: inspection
+                .operations
+                .iter()
+                .map(|op| operation_report(op.operation, op.label))
+                .collect(),
+            tasks: inspection
+                .tasks
+                .iter()
+                .map(|task| operation_report(task.operation, task.label))
+                .collect(),
+            buffers: inspection
+                .buffers
+                .iter()
+                .map(|buffer| self.snapshot_buffer(buffer, registry))
+                .collect(),
+            scopes: inspection
+                .scopes
+                .iter()
+                .map(|scope| self.snapshot(scope, registry))
+                .collect(),
+        }
+    }
+
+    fn snapshot_buffer(
+        &self,
+        buffer: &InspectedBuffer,
+        registry: &DiagramElementRegistry,
+    ) -> BufferSnapshot {
+        let message_type = self
+            .world
+            .get::<AnyBufferInterfaceStorage>(buffer.buffer)
+            .map(|interface| interface.0.message_type_name())
+            .unwrap_or("<unknown>")
+            .to_owned();
+
+        let len = buffer.view.len();
+        let messages: Option<Vec<_>> = (0..len)
+            .map(|i| registry.serialize_any(buffer.view.get(i)?))
+            .collect();
+
+        BufferSnapshot {
+            buffer: operation_report(buffer.buffer, buffer.label),
+            message_type,
+            gate_open: buffer.view.gate().is_open(),
+            len,
+            messages,
+        }
+    }
+}
+
+fn operation_report(operation: Entity, label: Option<&OperationLabel>) -> OperationReport {
+    OperationReport {
+        name: label.map(|label| label.to_string()),
+        entity: format!("{operation:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{diagram::*, prelude::*, testing::*, WorkflowInspector};
+    use serde_json::json;
+
+    struct Opaque;
+
+    #[test]
+    fn test_snapshot_session() {
+        let mut context = TestingContext::minimal_plugins();
+        let delay = context.spawn_async_delayed_map(Duration::from_secs(10), |value: u32| value);
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            let (to_buffer, to_delay) = scope
+                .input
+                .chain(builder)
+                .map_block(|value: u32| (value, value))
+                .unzip();
+
+            let buffer = builder.create_buffer::<u32>(BufferSettings::keep_all());
+            let opaque = builder.create_buffer::<Opaque>(BufferSettings::keep_all());
+            to_buffer
+                .chain(builder)
+                .then_gate_close(buffer)
+                .fork_clone((
+                    |chain: Chain<u32>| chain.connect(buffer.input_slot()),
+                    |chain: Chain<u32>| chain.map_block(|_| Opaque).connect(opaque.input_slot()),
+                ));
+            to_delay.chain(builder).then(delay).connect(scope.terminate);
+        });
+
+        let mut promise =
+            context.command(|commands| commands.request(5_u32, workflow).take_response());
+        context.run(FlushConditions::new().with_update_count(2));
+        assert!(promise.peek().is_pending());
+
+        let registry = DiagramElementRegistry::new();
+        let inspector = WorkflowInspector::new(&context.app.world);
+        let (session, parent_session) = {
+            let sessions = inspector.sessions(workflow);
+            (sessions[0].session, sessions[0].parent_session)
+        };
+        let snapshot = inspector
+            .snapshot_session(workflow, session, &registry)
+            .unwrap();
+
+        assert!(!snapshot.cleaning_up);
+        assert_eq!(snapshot.tasks.len(), 1);
+        assert_eq!(snapshot.buffers.len(), 2);
+
+        let numbers = snapshot
+            .buffers
+            .iter()
+            .find(|b| b.message_type == std::any::type_name::<u32>())
+            .unwrap();
+        assert!(!numbers.gate_open);
+        assert_eq!(numbers.messages, Some(vec![json!(5)]));
+
+        let opaque = snapshot
+            .buffers
+            .iter()
+            .find(|b| b.message_type == std::any::type_name::<Opaque>())
+            .unwrap();
+        assert!(opaque.gate_open);
+        assert_eq!(opaque.len, 1);
+        assert!(opaque.messages.is_none());
+
+        // The snapshot should also be found by the session of the request.
+        let by_parent = inspector
+            .snapshot_session(workflow, parent_session, &registry)
+            .unwrap();
+        assert_eq!(by_parent, snapshot);
+    }
+}
@@ -120,6 +120,14 @@ fn multiply3_5(x: i64) -> (i64, i64) {
     (x * 3, x * 5)
 }
 
+fn check_positive(x: i64) -> Result<i64, i64> {
+    if x > 0 {
+        Ok(x)
+    } else {
+        Err(x)
+    }
+}
+
 struct Unserializable;
 
 fn opaque(_: Unserializable) -> Unserializable {
@@ -159,6 +167,13 @@ fn new_registry_with_basic_nodes() -> DiagramElementRegistry {
         builder.create_map_block(move |a: i64| a + config)
     });
 
+    registry
+        .register_node_builder(
+            NodeBuilderOptions::new("check_positive"),
+            |builder: &mut Builder, _config: ()| builder.create_map_block(check_positive),
+        )
+        .with_fork_result();
+
     registry
         .opt_out()
         .no_deserializing()
@@ -0,0 +1,686 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+//! Record every message that gets handed off between the operations of
+//! selected workflows, and replay the recorded outputs of operations later.
+//!
+//! Add a [`TraceRecorderPlugin`] to your app to start recording. Each handoff
+//! becomes a [`TraceEntry`] which is passed to a [`TraceSink`]. Use
+//! [`ReplayHarness`] to turn recorded entries back into services that respond
+//! with exactly what the original operations produced, or give the harness to
+//! [`DiagramElementRegistry::set_replay`] to replay the nodes of a diagram.
+
+use bevy_app::{App, Plugin};
+use bevy_ecs::{
+    change_detection::DetectChangesMut,
+    prelude::{Commands, Entity, Resource, World},
+    world::EntityWorldMut,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use std::{
+    any::{type_name, Any, TypeId},
+    borrow::Cow,
+    collections::{HashMap, HashSet, VecDeque},
+    fs::File,
+    io::{BufRead, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use thiserror::Error as ThisError;
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::{
+    find_workflow, Builder, DynNode, JsonMessage, OperationLabel, Service, SpawnWorkflowExt,
+};
+
+use super::{DiagramElementRegistry, TypeInfo};
+
+/// Serialize a message of a specific type into a [`JsonMessage`]. The input
+/// must be a reference to the type that the function was registered for.
+pub type SerializeValueFn = fn(&dyn Any) -> Result<JsonMessage, String>;
+
+/// A single message that was handed from one operation to another.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraceEntry {
+    /// The workflow service that the target operation belongs to, if any.
+    #[serde(with = "option_entity_bits")]
+    pub workflow: Option<Entity>,
+    /// The session that the message belongs to.
+    #[serde(with = "entity_bits")]
+    pub session: Entity,
+    /// The operation that produced the message. This will be [`None`] if the
+    /// message did not come from an operation, e.g. it was the initial request
+    /// of a workflow or it was sent through a channel.
+    #[serde(with = "option_entity_bits")]
+    pub source: Option<Entity>,
+    /// The [label](OperationLabel) of the source operation.
+    pub source_label: Option<Arc<str>>,
+    /// The operation that received the message.
+    #[serde(with = "entity_bits")]
+    pub target: Entity,
+    /// The [label](OperationLabel) of the target operation.
+    pub target_label: Option<Arc<str>>,
+    /// When the handoff happened.
+    pub timestamp: SystemTime,
+    /// The Rust type name of the message.
+    pub message_type: Cow<'static, str>,
+    /// The message itself, if its type was registered with a serializer.
+    pub payload: Option<JsonMessage>,
+}
+
+/// Somewhere to send [`TraceEntry`]s as they get recorded.
+pub trait TraceSink: 'static + Send + Sync {
+    fn record(&self, entry: TraceEntry);
+}
+
+/// Keep the most recent trace entries in memory. Clones of this sink share
+/// the same buffer, so keep a clone to inspect the entries later.
+#[derive(Clone, Debug)]
+pub struct TraceRingBuffer {
+    entries: Arc<Mutex<VecDeque<TraceEntry>>>,
+    capacity: usize,
+}
+
+impl TraceRingBuffer {
+    /// Create a ring buffer that keeps at most `capacity` entries. The oldest
+    /// entries will be dropped to make room for new ones.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Default::default(),
+            capacity,
+        }
+    }
+
+    /// Get a copy of the entries that are currently in the buffer.
+    pub fn entries(&self) -> Vec<TraceEntry> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Take all the entries out of the buffer.
+    pub fn drain(&self) -> Vec<TraceEntry> {
+        self.entries.lock().unwrap().drain(..).collect()
+    }
+}
+
+impl TraceSink for TraceRingBuffer {
+    fn record(&self, entry: TraceEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        while entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+}
+
+/// Write each trace entry to a file as one line of JSON. The file can be
+/// loaded with [`ReplayHarness::from_json_lines`].
+pub struct TraceFileSink {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl TraceFileSink {
+    /// Create the file, replacing it if it already exists.
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    /// Make sure all recorded entries have been written to the file.
+    pub fn flush(&self) -> std::io::Result<()> {
+        self.writer.lock().unwrap().flush()
+    }
+}
+
+impl TraceSink for TraceFileSink {
+    fn record(&self, entry: TraceEntry) {
+        let mut writer = self.writer.lock().unwrap();
+        let result = serde_json::to_writer(&mut *writer, &entry)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"));
+
+        if let Err(err) = result {
+            tracing::error!("Failed to write trace entry to file: {err}");
+        }
+    }
+}
+
+/// Send each trace entry through a channel.
+pub struct TraceChannelSink {
+    sender: UnboundedSender<TraceEntry>,
+}
+
+impl TraceChannelSink {
+    /// Create a sink along with the receiver for its entries.
+    pub fn new() -> (Self, UnboundedReceiver<TraceEntry>) {
+        let (sender, receiver) = unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+impl TraceSink for TraceChannelSink {
+    fn record(&self, entry: TraceEntry) {
+        // If the receiver was dropped then nobody is interested in the trace
+        // anymore, so we can quietly discard the entry.
+        let _ = self.sender.send(entry);
+    }
+}
+
+/// Which workflows are being recorded.
+#[derive(Clone, Debug, Default)]
+pub enum TraceSelection {
+    /// Record every handoff, including those that happen outside of workflows.
+    #[default]
+    All,
+    /// Only record handoffs within these workflow services.
+    Workflows(HashSet<Entity>),
+}
+
+impl TraceSelection {
+    fn includes(&self, workflow: Option<Entity>) -> bool {
+        match self {
+            Self::All => true,
+            Self::Workflows(workflows) => workflow.is_some_and(|w| workflows.contains(&w)),
+        }
+    }
+}
+
+/// This plugin records message handoffs into a [`TraceSink`].
+pub struct TraceRecorderPlugin {
+    sink: Arc<dyn TraceSink>,
+    selection: TraceSelection,
+    serializers: HashMap<TypeId, SerializeValueFn>,
+}
+
+impl TraceRecorderPlugin {
+    /// Record into `sink`. By default every workflow will be recorded, but no
+    /// payloads will be serialized. Use [`Self::with_registry`] to serialize
+    /// payloads.
+    pub fn new(sink: impl TraceSink) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            selection: TraceSelection::All,
+            serializers: HashMap::new(),
+        }
+    }
+
+    /// Only record the workflows provided by these services. More workflows
+    /// can be selected later through the [`TraceRecorder`] resource.
+    pub fn with_workflows(mut self, workflows: impl IntoIterator<Item = Entity>) -> Self {
+        self.selection = TraceSelection::Workflows(workflows.into_iter().collect());
+        self
+    }
+
+    /// Serialize the payloads of any message types that are registered as
+    /// serializable in the registry.
+    pub fn with_registry(mut self, registry: &DiagramElementRegistry) -> Self {
+        self.serializers
+            .extend(registry.messages.messages.iter().filter_map(|(info, reg)| {
+                reg.operations
+                    .serialize_value_impl
+                    .map(|serialize| (info.type_id, serialize))
+            }));
+        self
+    }
+}
+
+impl Plugin for TraceRecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TraceRecorder {
+            sink: Arc::clone(&self.sink),
+            selection: self.selection.clone(),
+            serializers: self.serializers.clone(),
+            current_operation: None,
+        });
+    }
+}
+
+/// The resource that records message handoffs. This is added by
+/// [`TraceRecorderPlugin`] and can be modified to change which workflows are
+/// being recorded. Remove this resource to stop recording.
+#[derive(Resource)]
+pub struct TraceRecorder {
+    sink: Arc<dyn TraceSink>,
+    selection: TraceSelection,
+    serializers: HashMap<TypeId, SerializeValueFn>,
+    current_operation: Option<Entity>,
+}
+
+impl TraceRecorder {
+    /// Check which workflows are being recorded.
+    pub fn selection(&self) -> &TraceSelection {
+        &self.selection
+    }
+
+    /// Start recording every workflow.
+    pub fn record_all(&mut self) {
+        self.selection = TraceSelection::All;
+    }
+
+    /// Start recording a workflow. If every workflow was being recorded then
+    /// only this workflow will be recorded from now on.
+    pub fn select_workflow<Request, Response, Streams>(
+        &mut self,
+        workflow: Service<Request, Response, Streams>,
+    ) {
+        match &mut self.selection {
+            TraceSelection::All => {
+                self.selection = TraceSelection::Workflows(HashSet::from([workflow.provider()]));
+            }
+            TraceSelection::Workflows(workflows) => {
+                workflows.insert(workflow.provider());
+            }
+        }
+    }
+
+    /// Stop recording a workflow.
+    pub fn deselect_workflow<Request, Response, Streams>(
+        &mut self,
+        workflow: Service<Request, Response, Streams>,
+    ) {
+        if let TraceSelection::Workflows(workflows) = &mut self.selection {
+            workflows.remove(&workflow.provider());
+        }
+    }
+}
+
+/// Remember which operation is currently executing so that its outputs can be
+/// attributed to it. Returns the operation that was previously executing.
+pub(crate) fn set_trace_source(operation: Option<Entity>, world: &mut World) -> Option<Entity> {
+    let mut recorder = world.get_resource_mut::<TraceRecorder>()?;
+    std::mem::replace(
+        &mut recorder.bypass_change_detection().current_operation,
+        operation,
+    )
+}
+
+/// Record a message that is being handed to the `target` operation.
+pub(crate) fn record_handoff<T: 'static>(target: &EntityWorldMut, session: Entity, data: &T) {
+    let world = target.world();
+    let Some(recorder) = world.get_resource::<TraceRecorder>() else {
+        return;
+    };
+
+    let workflow = find_workflow(target.id(), world);
+    if !recorder.selection.includes(workflow) {
+        return;
+    }
+
+    let label = |entity: Entity| {
+        world
+            .get::<OperationLabel>(entity)
+            .map(|label| Arc::clone(&**label))
+    };
+
+    let source = recorder.current_operation;
+    let payload = recorder
+        .serializers
+        .get(&TypeId::of::<T>())
+        .and_then(|serialize| serialize(data).ok());
+
+    recorder.sink.record(TraceEntry {
+        workflow,
+        session,
+        source,
+        source_label: source.and_then(label),
+        target: target.id(),
+        target_label: label(target.id()),
+        timestamp: SystemTime::now(),
+        message_type: type_name::<T>().into(),
+        payload,
+    });
+}
+
+/// Feeds recorded outputs of operations back into a workflow in place of the
+/// live providers of those operations.
+///
+/// Outputs are matched up with operations by their [`OperationLabel`], since
+/// entities will not be the same when the workflow is rebuilt. Operations
+/// built from a diagram are labelled automatically.
+///
+/// When the harness is set with [`DiagramElementRegistry::set_replay`], each
+/// node of a diagram that has recorded outputs is replaced by a replay of
+/// those outputs while the diagram is being built. Replayed nodes take their
+/// requests and give their responses as [`JsonMessage`], so the usual implicit
+/// (de)serialization is applied around them. Streams of replayed nodes are not
+/// replayed.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayHarness {
+    outputs: HashMap<Arc<str>, Vec<(Cow<'static, str>, JsonMessage)>>,
+}
+
+impl ReplayHarness {
+    /// Create a harness from recorded entries, in the order they were recorded.
+    /// Entries without a source label or without a payload are ignored.
+    pub fn new(entries: impl IntoIterator<Item = TraceEntry>) -> Self {
+        let mut outputs: HashMap<_, Vec<_>> = HashMap::new();
+        for entry in entries {
+            let (Some(label), Some(payload)) = (entry.source_label, entry.payload) else {
+                continue;
+            };
+
+            outputs
+                .entry(label)
+                .or_default()
+                .push((entry.message_type, payload));
+        }
+
+        Self { outputs }
+    }
+
+    /// Load the entries that were written by a [`TraceFileSink`].
+    pub fn from_json_lines(reader: impl BufRead) -> Result<Self, ReplayError> {
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line.map_err(|err| ReplayError::Read(err.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry =
+                serde_json::from_str(&line).map_err(|err| ReplayError::Read(err.to_string()))?;
+            entries.push(entry);
+        }
+
+        Ok(Self::new(entries))
+    }
+
+    /// Get the recorded outputs of the operation with this label whose type
+    /// matches `T`.
+    pub fn recorded_outputs<T>(&self, label: &str) -> Vec<JsonMessage> {
+        self.outputs
+            .get(label)
+            .into_iter()
+            .flatten()
+            .filter(|(message_type, _)| message_type == type_name::<T>())
+            .map(|(_, payload)| payload.clone())
+            .collect()
+    }
+
+    /// Spawn a service that responds to each request with the next output
+    /// that was recorded for the operation with this label. Once the recorded
+    /// outputs run out, requests will be cancelled with [`ReplayError::Exhausted`].
+    ///
+    /// The service can be put into a [`ServiceCatalog`](super::ServiceCatalog)
+    /// under the name of the live service to replay the `call` operations of a
+    /// diagram.
+    pub fn spawn_service<Request, Response>(
+        &self,
+        label: &str,
+        commands: &mut Commands,
+    ) -> Service<Request, Response>
+    where
+        Request: 'static + Send + Sync,
+        Response: 'static + Send + Sync + DeserializeOwned,
+    {
+        let label: Arc<str> = label.into();
+        let mut outputs: VecDeque<_> = self.recorded_outputs::<Response>(&label).into();
+        commands.spawn_io_workflow(move |scope, builder| {
+            scope
+                .input
+                .chain(builder)
+                .map_block(move |_: Request| {
+                    let output = outputs
+                        .pop_front()
+                        .ok_or_else(|| ReplayError::Exhausted(Arc::clone(&label)))?;
+
+                    serde_json::from_value::<Response>(output).map_err(|err| {
+                        ReplayError::Deserialize {
+                            label: Arc::clone(&label),
+                            error: err.to_string(),
+                        }
+                    })
+                })
+                .cancel_on_err()
+                .connect(scope.terminate);
+        })
+    }
+
+    /// Create a node that replays the outputs recorded for the operation with
+    /// this label, if there are any whose type matches `response`.
+    pub(super) fn create_node(
+        &self,
+        label: &str,
+        response: &TypeInfo,
+        builder: &mut Builder,
+    ) -> Option<DynNode> {
+        let mut outputs: VecDeque<_> = self
+            .outputs
+            .get(label)?
+            .iter()
+            .filter(|(message_type, _)| message_type == response.type_name)
+            .map(|(_, payload)| payload.clone())
+            .collect();
+
+        if outputs.is_empty() {
+            return None;
+        }
+
+        let label: Arc<str> = label.into();
+        let node = builder.create_map_block(move |_: JsonMessage| {
+            outputs
+                .pop_front()
+                .ok_or_else(|| ReplayError::Exhausted(Arc::clone(&label)))
+        });
+        let output = node.output.chain(builder).cancel_on_err().output();
+
+        Some(DynNode {
+            input: node.input.into(),
+            output: output.into(),
+            streams: Default::default(),
+        })
+    }
+}
+
+#[derive(ThisError, Debug, Clone)]
+pub enum ReplayError {
+    #[error("no more recorded outputs for operation [{0}]")]
+    Exhausted(Arc<str>),
+    #[error("failed to deserialize a recorded output of operation [{label}]: {error}")]
+    Deserialize { label: Arc<str>, error: String },
+    #[error("failed to read the trace: {0}")]
+    Read(String),
+}
+
+mod entity_bits {
+    use bevy_ecs::prelude::Entity;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(entity: &Entity, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(entity.to_bits())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Entity, D::Error> {
+        Ok(Entity::from_bits(u64::deserialize(deserializer)?))
+    }
+}
+
+mod option_entity_bits {
+    use bevy_ecs::prelude::Entity;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        entity: &Option<Entity>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        entity.map(|e| e.to_bits()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Entity>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Entity::from_bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{diagram::testing::*, *};
+    use serde_json::json;
+
+    fn multiply_diagram() -> Diagram {
+        Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "multiply",
+            "ops": {
+                "multiply": {
+                    "type": "node",
+                    "builder": "multiply3",
+                    "next": { "builtin": "terminate" },
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut fixture = DiagramTestFixture::new();
+        let ring = TraceRingBuffer::new(100);
+        fixture
+            .context
+            .app
+            .add_plugins(TraceRecorderPlugin::new(ring.clone()).with_registry(&fixture.registry));
+
+        let result: i64 = fixture.spawn_and_run(&multiply_diagram(), 4_i64).unwrap();
+        assert_eq!(result, 12);
+
+        let entries = ring.entries();
+        let output = entries
+            .iter()
+            .find(|entry| entry.source_label.as_deref() == Some("multiply"))
+            .expect("missing entry for the output of the multiply node");
+        assert_eq!(output.payload, Some(json!(12)));
+        assert!(output.workflow.is_some());
+
+        // The recorded entries should survive a round trip through the file
+        // format.
+        let lines: String = entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+            .collect();
+        let harness = ReplayHarness::from_json_lines(lines.as_bytes()).unwrap();
+        assert_eq!(harness.recorded_outputs::<i64>("multiply"), vec![json!(12)]);
+
+        let replay = fixture
+            .context
+            .command(|commands| harness.spawn_service::<i64, i64>("multiply", commands));
+
+        let mut promise = fixture
+            .context
+            .command(|commands| commands.request(100_i64, replay).take_response());
+        fixture.context.run_while_pending(&mut promise);
+        assert_eq!(promise.take().available(), Some(12));
+
+        // The recorded outputs have run out, so the next request gets cancelled.
+        let mut promise = fixture
+            .context
+            .command(|commands| commands.request(100_i64, replay).take_response());
+        fixture.context.run_while_pending(&mut promise);
+        assert!(promise.take().is_cancelled());
+    }
+
+    #[test]
+    fn test_replay_diagram() {
+        let mut fixture = DiagramTestFixture::new();
+        let ring = TraceRingBuffer::new(100);
+        fixture
+            .context
+            .app
+            .add_plugins(TraceRecorderPlugin::new(ring.clone()).with_registry(&fixture.registry));
+
+        let result: i64 = fixture.spawn_and_run(&multiply_diagram(), 4_i64).unwrap();
+        assert_eq!(result, 12);
+
+        // Replay the recorded run in a fresh app where the live node can no
+        // longer be built.
+        let mut fixture = DiagramTestFixture::new();
+        fixture.registry.register_node_builder(
+            NodeBuilderOptions::new("multiply3"),
+            |_builder: &mut Builder, _config: ()| -> Node<i64, i64> {
+                panic!("the live node should not be built during a replay")
+            },
+        );
+        fixture
+            .registry
+            .set_replay(Some(ReplayHarness::new(ring.entries())));
+
+        let workflow = fixture
+            .spawn_io_workflow::<i64, i64>(&multiply_diagram())
+            .unwrap();
+
+        let mut promise = fixture
+            .context
+            .command(|commands| commands.request(100_i64, workflow).take_response());
+        fixture.context.run_while_pending(&mut promise);
+        assert_eq!(promise.take().available(), Some(12));
+
+        // The recorded outputs have run out, so the next request gets cancelled.
+        let mut promise = fixture
+            .context
+            .command(|commands| commands.request(100_i64, workflow).take_response());
+        fixture.context.run_while_pending(&mut promise);
+        assert!(promise.take().is_cancelled());
+    }
+
+    #[test]
+    fn test_trace_selection() {
+        let mut fixture = DiagramTestFixture::new();
+        let ring = TraceRingBuffer::new(100);
+        fixture
+            .context
+            .app
+            .add_plugins(TraceRecorderPlugin::new(ring.clone()).with_workflows([]));
+
+        let result: i64 = fixture.spawn_and_run(&multiply_diagram(), 4_i64).unwrap();
+        assert_eq!(result, 12);
+        assert!(ring.entries().is_empty());
+
+        let workflow = fixture
+            .spawn_io_workflow::<i64, i64>(&multiply_diagram())
+            .unwrap();
+        fixture
+            .context
+            .app
+            .world
+            .resource_mut::<TraceRecorder>()
+            .select_workflow(workflow);
+
+        let mut promise = fixture
+            .context
+            .command(|commands| commands.request(4_i64, workflow).take_response());
+        fixture.context.run_while_pending(&mut promise);
+        assert_eq!(promise.take().available(), Some(12));
+
+        let entries = ring.entries();
+        assert!(!entries.is_empty());
+        assert!(entries
+            .iter()
+            .all(|entry| entry.workflow == Some(workflow.provider())));
+        // Payloads are not serialized unless a registry was given.
+        assert!(entries.iter().all(|entry| entry.payload.is_none()));
+    }
+}
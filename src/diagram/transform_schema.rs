@@ -15,9 +15,14 @@
  *
 */
 
-use std::error::Error;
+use std::{cell::RefCell, collections::HashMap, error::Error, sync::Arc};
 
-use cel_interpreter::{Context, ExecutionError, ParseError, Program};
+use cel_interpreter::{
+    extractors::{Identifier, This},
+    objects::{Key, ValueType},
+    Context, ExecutionError, FunctionContext, ParseError, Value,
+};
+use cel_parser::{ArithmeticOp, Expression, Member, RelationOp};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -37,10 +42,689 @@ pub enum TransformError {
     #[error(transparent)]
     Execution(#[from] ExecutionError),
 
+    #[error("CEL expression has a length of {length} which exceeds the limit of {limit}")]
+    SourceLengthExceeded { length: usize, limit: usize },
+
+    #[error("CEL expression has a nesting depth of {depth} which exceeds the limit of {limit}")]
+    DepthLimitExceeded { depth: usize, limit: usize },
+
+    #[error(
+        "CEL evaluation encountered a string of length {length} which exceeds the limit of {limit}"
+    )]
+    StringLengthExceeded { length: usize, limit: usize },
+
+    #[error("CEL evaluation encountered a list of size {size} which exceeds the limit of {limit}")]
+    ListSizeExceeded { size: usize, limit: usize },
+
+    #[error("CEL evaluation exceeded the cost limit of {limit}")]
+    EvaluationCostExceeded { limit: usize },
+
     #[error(transparent)]
     Other(#[from] Box<dyn Error + Send + Sync + 'static>),
 }
 
+/// Limits that are applied to every CEL program used by a diagram. Diagrams
+/// may come from untrusted sources, so these limits are configured on the
+/// [`DiagramElementRegistry`](super::DiagramElementRegistry) rather than in
+/// the diagram itself.
+///
+/// * `max_source_length` and `max_depth` are checked once when the program is
+///   compiled. The nesting of brackets is checked before the expression gets
+///   parsed, so neither the parser nor the evaluator can overflow the stack. A
+///   program that exceeds them will cause the workflow build to fail.
+/// * `max_string_length` and `max_list_size` are checked against the request,
+///   every value built by `+` or by a macro such as `map`, and the result of
+///   each evaluation. `max_evaluation_cost` bounds the total work of one
+///   evaluation. A violation produces a [`TransformError`] which gets sent to
+///   the `on_error` target of the transform operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CelLimits {
+    /// The maximum length (in bytes) of the source of a CEL expression.
+    pub max_source_length: usize,
+    /// The maximum nesting depth of a CEL expression.
+    pub max_depth: usize,
+    /// The maximum length (in bytes) of any string, including object keys.
+    pub max_string_length: usize,
+    /// The maximum number of elements in any list.
+    pub max_list_size: usize,
+    /// The maximum cost of one evaluation. Every item visited by a macro such
+    /// as `map` or `all` costs 1, and so does every element of a value that a
+    /// macro produces. Every element that `+` copies or that `in` scans costs
+    /// 1 as well.
+    pub max_evaluation_cost: usize,
+}
+
+impl CelLimits {
+    /// Limits that will never be exceeded.
+    pub fn unlimited() -> Self {
+        Self {
+            max_source_length: usize::MAX,
+            max_depth: usize::MAX,
+            max_string_length: usize::MAX,
+            max_list_size: usize::MAX,
+            max_evaluation_cost: usize::MAX,
+        }
+    }
+
+    /// Check the source of an expression before it gets parsed. The parser is
+    /// recursive, so this is what protects it from deeply nested input.
+    fn check_source(&self, source: &str) -> Result<(), TransformError> {
+        if source.len() > self.max_source_length {
+            return Err(TransformError::SourceLengthExceeded {
+                length: source.len(),
+                limit: self.max_source_length,
+            });
+        }
+
+        let depth = bracket_depth(source);
+        if depth > self.max_depth {
+            return Err(TransformError::DepthLimitExceeded {
+                depth,
+                limit: self.max_depth,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_depth(&self, expression: &Expression) -> Result<(), TransformError> {
+        let depth = expression_depth(expression);
+        if depth > self.max_depth {
+            return Err(TransformError::DepthLimitExceeded {
+                depth,
+                limit: self.max_depth,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_message(&self, message: &JsonMessage) -> Result<(), TransformError> {
+        let mut queue = vec![message];
+        while let Some(top) = queue.pop() {
+            match top {
+                JsonMessage::String(s) => self.check_string(s)?,
+                JsonMessage::Array(list) => {
+                    self.check_list_size(list.len())?;
+                    queue.extend(list);
+                }
+                JsonMessage::Object(map) => {
+                    for (key, value) in map {
+                        self.check_string(key)?;
+                        queue.push(value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_string(&self, s: &str) -> Result<(), TransformError> {
+        self.check_string_length(s.len())
+    }
+
+    fn check_string_length(&self, length: usize) -> Result<(), TransformError> {
+        if length > self.max_string_length {
+            return Err(TransformError::StringLengthExceeded {
+                length,
+                limit: self.max_string_length,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_list_size(&self, size: usize) -> Result<(), TransformError> {
+        if size > self.max_list_size {
+            return Err(TransformError::ListSizeExceeded {
+                size,
+                limit: self.max_list_size,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for CelLimits {
+    fn default() -> Self {
+        Self {
+            max_source_length: 1 << 14,
+            max_depth: 64,
+            max_string_length: 1 << 20,
+            max_list_size: 1 << 16,
+            max_evaluation_cost: 1 << 24,
+        }
+    }
+}
+
+/// Find the deepest nesting of brackets in the source of an expression,
+/// ignoring brackets inside of string literals and comments.
+///
+/// In CEL, raw strings (with an `r` or `R` prefix) do not process escapes and
+/// triple-quoted strings may contain unescaped quotes. The lexer of cel-parser
+/// does not follow either rule yet: it processes escapes in raw strings and
+/// reads `'''` as an empty string followed by a quote. The source is scanned
+/// both ways and the deeper result is used, so the limit holds no matter which
+/// way the parser reads a literal.
+fn bracket_depth(source: &str) -> usize {
+    scan_bracket_depth(source, false).max(scan_bracket_depth(source, true))
+}
+
+fn scan_bracket_depth(source: &str, follow_spec: bool) -> usize {
+    // Every character that matters here is ASCII, and the bytes of a multi-byte
+    // UTF-8 character are never ASCII, so it is safe to scan bytes.
+    let bytes = source.as_bytes();
+    let mut max_depth = 0;
+    let mut depth: usize = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' | b'[' | b'{' => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            }
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            b'"' | b'\'' => {
+                i = string_literal_end(bytes, i, follow_spec);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' && bytes[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    max_depth
+}
+
+/// Find the index just past the end of the string literal whose opening quote
+/// is at `start`.
+fn string_literal_end(bytes: &[u8], start: usize, follow_spec: bool) -> usize {
+    let quote = bytes[start];
+    let (raw, delimiter) = if follow_spec {
+        let prefix_start = bytes[..start]
+            .iter()
+            .rposition(|b| !b.is_ascii_alphanumeric() && *b != b'_')
+            .map_or(0, |i| i + 1);
+        let prefix = &bytes[prefix_start..start];
+        let raw = prefix.len() <= 2
+            && prefix.iter().any(|b| matches!(b, b'r' | b'R'))
+            && prefix
+                .iter()
+                .all(|b| matches!(b, b'r' | b'R' | b'b' | b'B'));
+        let triple = bytes[start..].starts_with(&[quote; 3]);
+        (raw, if triple { 3 } else { 1 })
+    } else {
+        (false, 1)
+    };
+
+    let mut i = start + delimiter;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && !raw {
+            i += 2;
+        } else if bytes[i..].starts_with(&[quote; 3][..delimiter]) {
+            return i + delimiter;
+        } else {
+            i += 1;
+        }
+    }
+
+    bytes.len()
+}
+
+/// Calculate the nesting depth of an expression without recursion, so that
+/// pathological expressions cannot overflow the stack.
+fn expression_depth(expression: &Expression) -> usize {
+    let mut max_depth = 0;
+    let mut queue = vec![(expression, 1)];
+    while let Some((top, depth)) = queue.pop() {
+        max_depth = max_depth.max(depth);
+        let depth = depth + 1;
+        match top {
+            Expression::Arithmetic(e1, _, e2)
+            | Expression::Relation(e1, _, e2)
+            | Expression::Or(e1, e2)
+            | Expression::And(e1, e2) => {
+                queue.push((e1, depth));
+                queue.push((e2, depth));
+            }
+            Expression::Ternary(e1, e2, e3) => {
+                queue.push((e1, depth));
+                queue.push((e2, depth));
+                queue.push((e3, depth));
+            }
+            Expression::Unary(_, e) => queue.push((e, depth)),
+            Expression::Member(e, member) => {
+                queue.push((e, depth));
+                match member.as_ref() {
+                    Member::Attribute(_) => {}
+                    Member::Index(index) => queue.push((index, depth)),
+                    Member::Fields(fields) => {
+                        queue.extend(fields.iter().map(|(_, e)| (e, depth)));
+                    }
+                }
+            }
+            Expression::FunctionCall(name, target, args) => {
+                queue.push((name, depth));
+                if let Some(target) = target {
+                    queue.push((target, depth));
+                }
+                queue.extend(args.iter().map(|e| (e, depth)));
+            }
+            Expression::List(list) => queue.extend(list.iter().map(|e| (e, depth))),
+            Expression::Map(map) => {
+                for (k, v) in map {
+                    queue.push((k, depth));
+                    queue.push((v, depth));
+                }
+            }
+            Expression::Atom(_) | Expression::Ident(_) => {}
+        }
+    }
+
+    max_depth
+}
+
+/// Replace the operators that can build large values or scan them with calls
+/// to the guarded functions of [`guarded_context`]. The interpreter evaluates
+/// operators internally, so this is the only way to check them.
+fn guard_operators(expression: &mut Expression) {
+    let mut queue = vec![expression];
+    while let Some(top) = queue.pop() {
+        let guarded = match top {
+            Expression::Arithmetic(_, ArithmeticOp::Add, _) => Some(GUARDED_ADD),
+            Expression::Relation(_, RelationOp::In, _) => Some(GUARDED_IN),
+            _ => None,
+        };
+        if let Some(guarded) = guarded {
+            let (Expression::Arithmetic(left, _, right) | Expression::Relation(left, _, right)) =
+                std::mem::replace(top, Expression::List(Vec::new()))
+            else {
+                unreachable!("only arithmetic and relations get guarded");
+            };
+            *top = Expression::FunctionCall(
+                Box::new(Expression::Ident(Arc::new(guarded.to_owned()))),
+                None,
+                vec![*left, *right],
+            );
+        }
+
+        match top {
+            Expression::Arithmetic(e1, _, e2)
+            | Expression::Relation(e1, _, e2)
+            | Expression::Or(e1, e2)
+            | Expression::And(e1, e2) => {
+                queue.push(e1);
+                queue.push(e2);
+            }
+            Expression::Ternary(e1, e2, e3) => {
+                queue.push(e1);
+                queue.push(e2);
+                queue.push(e3);
+            }
+            Expression::Unary(_, e) => queue.push(e),
+            Expression::Member(e, member) => {
+                queue.push(e);
+                match member.as_mut() {
+                    Member::Attribute(_) => {}
+                    Member::Index(index) => queue.push(index),
+                    Member::Fields(fields) => queue.extend(fields.iter_mut().map(|(_, e)| e)),
+                }
+            }
+            Expression::FunctionCall(_, target, args) => {
+                if let Some(target) = target {
+                    queue.push(target);
+                }
+                queue.extend(args.iter_mut());
+            }
+            Expression::List(list) => queue.extend(list.iter_mut()),
+            Expression::Map(map) => {
+                for (k, v) in map {
+                    queue.push(k);
+                    queue.push(v);
+                }
+            }
+            Expression::Atom(_) | Expression::Ident(_) => {}
+        }
+    }
+}
+
+// These names cannot be written in CEL, so expressions can only reach the
+// guarded functions through the operators that they replace.
+const GUARDED_ADD: &str = "@add";
+const GUARDED_IN: &str = "@in";
+
+/// Create the root context of CEL programs. The builtin macros are replaced by
+/// versions that enforce [`CelLimits`] on every item that they visit.
+fn guarded_context() -> Context<'static> {
+    let mut context = Context::default();
+    context.add_function("map", guarded_map);
+    context.add_function("filter", guarded_filter);
+    context.add_function("all", guarded_all);
+    context.add_function("exists", guarded_exists);
+    context.add_function("exists_one", guarded_exists_one);
+    context.add_function(GUARDED_ADD, guarded_add);
+    context.add_function(GUARDED_IN, guarded_in);
+    context
+}
+
+thread_local! {
+    /// The evaluation that is running on this thread. The interpreter does not
+    /// pass any state of ours into the functions that it calls, so this is how
+    /// the guarded functions find the limits and the remaining budget.
+    static EVALUATION: RefCell<Option<Evaluation>> = const { RefCell::new(None) };
+}
+
+struct Evaluation {
+    limits: CelLimits,
+    cost: usize,
+    /// The first limit that was exceeded. Functions can only return an
+    /// [`ExecutionError`] to the interpreter, so the original error is kept
+    /// here to be reported instead.
+    violation: Option<TransformError>,
+}
+
+impl Evaluation {
+    fn new(limits: CelLimits) -> Self {
+        Self {
+            limits,
+            cost: 0,
+            violation: None,
+        }
+    }
+
+    fn charge(&mut self, cost: usize) -> Result<(), TransformError> {
+        self.cost = self.cost.saturating_add(cost);
+        if self.cost > self.limits.max_evaluation_cost {
+            return Err(TransformError::EvaluationCostExceeded {
+                limit: self.limits.max_evaluation_cost,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check the size of every string and list inside of a value. Every
+    /// element costs 1, so values that share their contents many times over
+    /// cannot make this check itself expensive.
+    fn check_value(&mut self, value: &Value) -> Result<(), TransformError> {
+        let mut queue = vec![value];
+        while let Some(top) = queue.pop() {
+            self.charge(1)?;
+            match top {
+                Value::String(s) => self.limits.check_string(s)?,
+                Value::List(list) => {
+                    self.limits.check_list_size(list.len())?;
+                    queue.extend(list.iter());
+                }
+                Value::Map(map) => {
+                    for (key, value) in map.map.iter() {
+                        if let Key::String(key) = key {
+                            self.limits.check_string(key)?;
+                        }
+                        queue.push(value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Apply a check to the evaluation that is running on this thread, turning a
+/// violation into an error that the interpreter will pass along.
+fn guard(
+    ftx: &FunctionContext,
+    check: impl FnOnce(&mut Evaluation) -> Result<(), TransformError>,
+) -> Result<(), ExecutionError> {
+    EVALUATION.with_borrow_mut(|evaluation| {
+        let Some(evaluation) = evaluation else {
+            return Ok(());
+        };
+
+        check(evaluation).map_err(|err| {
+            let error = ftx.error(&err);
+            evaluation.violation.get_or_insert(err);
+            error
+        })
+    })
+}
+
+/// Evaluate the expression of a macro for each item of a list or each key of
+/// a map until `visit` returns false. Every item costs 1.
+fn for_each_item(
+    ftx: &FunctionContext,
+    this: Value,
+    ident: &Identifier,
+    expr: &Expression,
+    mut visit: impl FnMut(&Value, Value) -> Result<bool, ExecutionError>,
+) -> Result<(), ExecutionError> {
+    let mut ptx = ftx.ptx.new_inner_scope();
+    let mut visit_item = |item: Value| -> Result<bool, ExecutionError> {
+        guard(ftx, |evaluation| evaluation.charge(1))?;
+        ptx.add_variable_from_value(ident, item.clone());
+        let value = ptx.resolve(expr)?;
+        visit(&item, value)
+    };
+
+    match this {
+        Value::List(items) => {
+            for item in items.iter() {
+                if !visit_item(item.clone())? {
+                    break;
+                }
+            }
+        }
+        Value::Map(map) => {
+            for key in map.map.keys() {
+                if !visit_item(key.clone().into())? {
+                    break;
+                }
+            }
+        }
+        _ => return Err(this.error_expected_type(ValueType::List)),
+    }
+
+    Ok(())
+}
+
+fn guarded_map(
+    ftx: &FunctionContext,
+    This(this): This<Value>,
+    ident: Identifier,
+    expr: Expression,
+) -> Result<Value, ExecutionError> {
+    let mut values = Vec::new();
+    for_each_item(ftx, this, &ident, &expr, |_, value| {
+        guard(ftx, |evaluation| {
+            evaluation.limits.check_list_size(values.len() + 1)?;
+            evaluation.check_value(&value)
+        })?;
+        values.push(value);
+        Ok(true)
+    })?;
+    Ok(Value::List(values.into()))
+}
+
+fn guarded_filter(
+    ftx: &FunctionContext,
+    This(this): This<Value>,
+    ident: Identifier,
+    expr: Expression,
+) -> Result<Value, ExecutionError> {
+    let mut values = Vec::new();
+    for_each_item(ftx, this, &ident, &expr, |item, value| {
+        if let Value::Bool(true) = value {
+            values.push(item.clone());
+        }
+        Ok(true)
+    })?;
+    Ok(Value::List(values.into()))
+}
+
+fn guarded_all(
+    ftx: &FunctionContext,
+    This(this): This<Value>,
+    ident: Identifier,
+    expr: Expression,
+) -> Result<bool, ExecutionError> {
+    let mut all = true;
+    for_each_item(ftx, this, &ident, &expr, |_, value| {
+        all = !matches!(value, Value::Bool(false));
+        Ok(all)
+    })?;
+    Ok(all)
+}
+
+fn guarded_exists(
+    ftx: &FunctionContext,
+    This(this): This<Value>,
+    ident: Identifier,
+    expr: Expression,
+) -> Result<bool, ExecutionError> {
+    let mut exists = false;
+    for_each_item(ftx, this, &ident, &expr, |_, value| {
+        exists = matches!(value, Value::Bool(true));
+        Ok(!exists)
+    })?;
+    Ok(exists)
+}
+
+fn guarded_exists_one(
+    ftx: &FunctionContext,
+    This(this): This<Value>,
+    ident: Identifier,
+    expr: Expression,
+) -> Result<bool, ExecutionError> {
+    let mut count = 0;
+    for_each_item(ftx, this, &ident, &expr, |_, value| {
+        if let Value::Bool(true) = value {
+            count += 1;
+        }
+        Ok(count < 2)
+    })?;
+    Ok(count == 1)
+}
+
+fn guarded_add(ftx: &FunctionContext, left: Value, right: Value) -> Result<Value, ExecutionError> {
+    guard(ftx, |evaluation| match (&left, &right) {
+        (Value::List(l), Value::List(r)) => {
+            evaluation.limits.check_list_size(l.len() + r.len())?;
+            evaluation.charge(l.len() + r.len())
+        }
+        (Value::String(l), Value::String(r)) => {
+            evaluation.limits.check_string_length(l.len() + r.len())?;
+            evaluation.charge(l.len() + r.len())
+        }
+        (Value::Map(l), Value::Map(r)) => evaluation.charge(l.map.len() + r.map.len()),
+        _ => evaluation.charge(1),
+    })?;
+    left + right
+}
+
+fn guarded_in(ftx: &FunctionContext, left: Value, right: Value) -> Result<Value, ExecutionError> {
+    guard(ftx, |evaluation| match &right {
+        Value::List(list) => evaluation.charge(list.len()),
+        Value::String(s) => evaluation.charge(s.len()),
+        _ => evaluation.charge(1),
+    })?;
+    let found = match (left, right) {
+        (Value::String(l), Value::String(r)) => r.contains(l.as_str()),
+        (any, Value::List(list)) => list.contains(&any),
+        (any, Value::Map(map)) => match any.try_into() {
+            Ok(key) => map.map.contains_key(&key),
+            Err(_) => false,
+        },
+        (left, right) => return Err(ExecutionError::ValuesNotComparable(left, right)),
+    };
+    Ok(Value::Bool(found))
+}
+
+/// A CEL program that has been compiled and checked against [`CelLimits`].
+pub struct CelProgram {
+    expression: Expression,
+    context: Arc<Context<'static>>,
+    limits: CelLimits,
+}
+
+impl CelProgram {
+    /// Evaluate the program with `request` as the input message.
+    pub fn evaluate(&self, request: JsonMessage) -> Result<JsonMessage, TransformError> {
+        self.limits.check_message(&request)?;
+        let mut context = self.context.new_inner_scope();
+        context
+            .add_variable("request", request)
+            // cannot keep the original error because it is not Send + Sync
+            .map_err(|err| TransformError::Other(err.to_string().into()))?;
+
+        let previous = EVALUATION.replace(Some(Evaluation::new(self.limits)));
+        let result = Value::resolve(&self.expression, &context);
+        let mut evaluation = EVALUATION
+            .replace(previous)
+            .expect("the evaluation should still be set after resolving");
+        let value = match result {
+            Ok(value) => value,
+            Err(err) => return Err(evaluation.violation.take().unwrap_or(err.into())),
+        };
+
+        // Check the value before converting it, since the conversion copies
+        // any contents that the value shares.
+        evaluation.check_value(&value)?;
+        value
+            .json()
+            // cel_interpreter::json is private so we have to type erase ConvertToJsonError
+            .map_err(|err| TransformError::Other(err.to_string().into()))
+    }
+}
+
+/// Compiled CEL programs for one workflow, keyed by their expression text. All
+/// programs share a single root [`Context`] so the builtin functions only need
+/// to be registered once.
+#[derive(Default)]
+pub(super) struct CelCache {
+    context: Option<Arc<Context<'static>>>,
+    programs: HashMap<Arc<str>, Arc<CelProgram>>,
+}
+
+impl CelCache {
+    pub(super) fn get_or_compile(
+        &mut self,
+        source: &str,
+        limits: &CelLimits,
+    ) -> Result<Arc<CelProgram>, TransformError> {
+        if let Some(program) = self.programs.get(source) {
+            return Ok(Arc::clone(program));
+        }
+
+        limits.check_source(source)?;
+        let mut expression = cel_parser::parse(source)?;
+        limits.check_depth(&expression)?;
+        guard_operators(&mut expression);
+
+        let context = Arc::clone(
+            self.context
+                .get_or_insert_with(|| Arc::new(guarded_context())),
+        );
+
+        let program = Arc::new(CelProgram {
+            expression,
+            context,
+            limits: *limits,
+        });
+        self.programs.insert(source.into(), Arc::clone(&program));
+        Ok(program)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct TransformSchema {
@@ -64,19 +748,10 @@ impl BuildDiagramOperation for TransformSchema {
         builder: &mut Builder,
         ctx: &mut DiagramContext,
     ) -> Result<BuildStatus, DiagramErrorCode> {
-        let program = Program::compile(&self.cel).map_err(TransformError::Parse)?;
+        let program = ctx.compile_cel(&self.cel)?;
         let node = builder.create_map_block(
             move |req: JsonMessage| -> Result<JsonMessage, TransformError> {
-                let mut context = Context::default();
-                context
-                    .add_variable("request", req)
-                    // cannot keep the original error because it is not Send + Sync
-                    .map_err(|err| TransformError::Other(err.to_string().into()))?;
-                program
-                    .execute(&context)?
-                    .json()
-                    // cel_interpreter::json is private so we have to type erase ConvertToJsonError
-                    .map_err(|err| TransformError::Other(err.to_string().into()))
+                program.evaluate(req)
             },
         );
 
@@ -93,7 +768,7 @@ impl BuildDiagramOperation for TransformSchema {
         let (ok, _) = node.output.chain(builder).fork_result(
             |ok| ok.output(),
             |err| {
-                ctx.add_output_into_target(error_target.clone(), err.output().into());
+                ctx.add_output_into_resolved_target(error_target.clone(), err.output().into());
             },
         );
 
@@ -108,7 +783,12 @@ mod tests {
     use serde_json::json;
     use test_log::test;
 
-    use crate::{diagram::testing::DiagramTestFixture, Diagram, JsonMessage};
+    use crate::{
+        diagram::testing::DiagramTestFixture, Cancellation, CancellationCause, Diagram,
+        DiagramErrorCode, JsonMessage,
+    };
+
+    use super::*;
 
     #[test]
     fn test_transform_node_response() {
@@ -238,4 +918,271 @@ mod tests {
         assert!(fixture.context.no_unhandled_errors());
         assert_eq!(result, 40);
     }
+
+    #[test]
+    fn test_cel_programs_are_cached() {
+        let mut cache = CelCache::default();
+        let limits = CelLimits::default();
+        let a = cache.get_or_compile("int(request) * 3", &limits).unwrap();
+        let b = cache.get_or_compile("int(request) * 3", &limits).unwrap();
+        let c = cache.get_or_compile("int(request) * 4", &limits).unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert!(Arc::ptr_eq(&a.context, &c.context));
+    }
+
+    #[test]
+    fn test_cel_depth_limit() {
+        let mut fixture = DiagramTestFixture::new();
+        fixture.registry.set_cel_limits(CelLimits {
+            max_depth: 4,
+            ..Default::default()
+        });
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "transform",
+            "ops": {
+                "transform": {
+                    "type": "transform",
+                    "cel": "((((int(request) + 1) + 1) + 1) + 1)",
+                    "next": { "builtin": "terminate" },
+                },
+            },
+        }))
+        .unwrap();
+
+        let err = fixture.spawn_json_io_workflow(&diagram).unwrap_err();
+        assert!(
+            matches!(
+                err.code,
+                DiagramErrorCode::CannotTransform(TransformError::DepthLimitExceeded { .. })
+            ),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_cel_string_limit_goes_to_on_error() {
+        let mut fixture = DiagramTestFixture::new();
+        fixture.registry.set_cel_limits(CelLimits {
+            max_string_length: 8,
+            ..Default::default()
+        });
+
+        let diagram = Diagram::from_json(json!({
+            "version": "0.1.0",
+            "start": "transform",
+            "ops": {
+                "transform": {
+                    "type": "transform",
+                    "cel": "request + request",
+                    "next": { "builtin": "terminate" },
+                    "on_error": { "builtin": "cancel" },
+                },
+            },
+        }))
+        .unwrap();
+
+        let result: JsonMessage = fixture
+            .spawn_and_run(&diagram, JsonMessage::from("abc"))
+            .unwrap();
+        assert_eq!(result, "abcabc");
+
+        let err = fixture
+            .spawn_and_run::<_, JsonMessage>(&diagram, JsonMessage::from("abcdef"))
+            .unwrap_err();
+        let cancellation = err.downcast_ref::<Cancellation>().unwrap();
+        let CancellationCause::Triggered(triggered) = cancellation.cause.as_ref() else {
+            panic!("unexpected cancellation: {cancellation:?}");
+        };
+        assert!(triggered
+            .value
+            .as_ref()
+            .is_some_and(|value| value.contains("exceeds the limit of 8")));
+    }
+
+    #[test]
+    fn test_cel_nesting_is_checked_before_parsing() {
+        let mut cache = CelCache::default();
+        let limits = CelLimits::default();
+
+        // Deep enough to overflow the stack of the parser if it were parsed.
+        let n = 100_000;
+        let source = format!("{}1{}", "[".repeat(n), "]".repeat(n));
+        let err = cache.get_or_compile(&source, &limits).err().unwrap();
+        assert!(
+            matches!(err, TransformError::SourceLengthExceeded { .. }),
+            "{err:?}"
+        );
+
+        let n = 4096;
+        let source = format!("{}1{}", "(".repeat(n), ")".repeat(n));
+        let err = cache.get_or_compile(&source, &limits).err().unwrap();
+        assert!(
+            matches!(err, TransformError::DepthLimitExceeded { depth: 4096, .. }),
+            "{err:?}"
+        );
+
+        // Brackets inside of strings and comments do not count.
+        let limits = CelLimits {
+            max_depth: 4,
+            ..Default::default()
+        };
+        let program = cache
+            .get_or_compile("'((((((' + request // ]]]]]]\n", &limits)
+            .unwrap();
+        assert_eq!(program.evaluate("abc".into()).unwrap(), "((((((abc");
+    }
+
+    #[test]
+    fn test_cel_nesting_in_raw_strings() {
+        // By the CEL spec this raw string ends at the second quote, but
+        // cel-parser reads the backslash as an escape. Either reading must
+        // see the brackets that the other one treats as code.
+        assert_eq!(bracket_depth(r#"r"\" + ((((1))))"#), 4);
+        assert_eq!(bracket_depth(r#"r"\" " + ((((1))))"#), 4);
+        assert_eq!(bracket_depth(r#"R'\' ' + [[[[1]]]]"#), 4);
+        assert_eq!(bracket_depth(r#"r"((((\\" + (1)"#), 1);
+
+        let mut cache = CelCache::default();
+        let limits = CelLimits {
+            max_depth: 3,
+            ..Default::default()
+        };
+        let err = cache
+            .get_or_compile(r#"r"\" " == '' ? 1 : ((((1))))"#, &limits)
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, TransformError::DepthLimitExceeded { depth: 4, .. }),
+            "{err:?}"
+        );
+
+        let program = cache
+            .get_or_compile(r#"r"(((\d" + request"#, &limits)
+            .unwrap();
+        assert_eq!(program.evaluate("abc".into()).unwrap(), r"(((\dabc");
+    }
+
+    #[test]
+    fn test_cel_limits_apply_to_intermediate_values() {
+        let mut cache = CelCache::default();
+        let limits = CelLimits {
+            max_string_length: 8,
+            max_list_size: 4,
+            ..Default::default()
+        };
+
+        let program = cache
+            .get_or_compile("size(request + request + request)", &limits)
+            .unwrap();
+        assert_eq!(program.evaluate("ab".into()).unwrap(), 6);
+        let err = program.evaluate("abc".into()).unwrap_err();
+        assert!(
+            matches!(err, TransformError::StringLengthExceeded { length: 9, .. }),
+            "{err:?}"
+        );
+
+        let program = cache
+            .get_or_compile("size(request.map(x, request + request))", &limits)
+            .unwrap();
+        assert_eq!(program.evaluate(json!([1, 2])).unwrap(), 2);
+        let err = program.evaluate(json!([1, 2, 3])).unwrap_err();
+        assert!(
+            matches!(err, TransformError::ListSizeExceeded { size: 6, .. }),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn test_cel_evaluation_cost_limit() {
+        let mut cache = CelCache::default();
+        let limits = CelLimits {
+            max_evaluation_cost: 1 << 20,
+            ..Default::default()
+        };
+        let request = JsonMessage::Array(vec![0.into(); limits.max_list_size]);
+
+        // Every item of the result shares the request, so the interpreter
+        // builds it cheaply, but its size is the square of the request.
+        let program = cache
+            .get_or_compile("request.map(x, request)", &limits)
+            .unwrap();
+        let err = program.evaluate(request.clone()).unwrap_err();
+        assert!(
+            matches!(err, TransformError::EvaluationCostExceeded { .. }),
+            "{err:?}"
+        );
+
+        let program = cache
+            .get_or_compile("request.all(x, request.all(y, x == y))", &limits)
+            .unwrap();
+        let err = program.evaluate(request.clone()).unwrap_err();
+        assert!(
+            matches!(err, TransformError::EvaluationCostExceeded { .. }),
+            "{err:?}"
+        );
+
+        let program = cache
+            .get_or_compile("request.filter(x, 1 in request)", &limits)
+            .unwrap();
+        let err = program.evaluate(request.clone()).unwrap_err();
+        assert!(
+            matches!(err, TransformError::EvaluationCostExceeded { .. }),
+            "{err:?}"
+        );
+
+        let program = cache
+            .get_or_compile("request.map(x, int(x) + 1)", &limits)
+            .unwrap();
+        let response = program.evaluate(request).unwrap();
+        assert_eq!(response.as_array().unwrap().len(), limits.max_list_size);
+    }
+
+    #[test]
+    fn test_cel_guarded_macros() {
+        let mut cache = CelCache::default();
+        let limits = CelLimits::default();
+        let evaluate = |cache: &mut CelCache, source: &str| {
+            cache
+                .get_or_compile(source, &limits)
+                .unwrap()
+                .evaluate(json!([1, 2, 3]))
+                .unwrap()
+        };
+
+        assert_eq!(
+            evaluate(&mut cache, "request.map(x, int(x) * 2)"),
+            json!([2, 4, 6])
+        );
+        assert_eq!(
+            evaluate(&mut cache, "request.filter(x, x > 1)"),
+            json!([2, 3])
+        );
+        assert_eq!(evaluate(&mut cache, "request.all(x, x > 0)"), true);
+        assert_eq!(evaluate(&mut cache, "request.all(x, x > 1)"), false);
+        assert_eq!(evaluate(&mut cache, "request.exists(x, x > 2)"), true);
+        assert_eq!(evaluate(&mut cache, "request.exists(x, x > 3)"), false);
+        assert_eq!(evaluate(&mut cache, "request.exists_one(x, x > 2)"), true);
+        assert_eq!(evaluate(&mut cache, "request.exists_one(x, x > 1)"), false);
+        assert_eq!(evaluate(&mut cache, "request + [4]"), json!([1, 2, 3, 4]));
+        assert_eq!(
+            evaluate(&mut cache, "2 in request && !(4 in request)"),
+            true
+        );
+        assert_eq!(
+            evaluate(&mut cache, "{'a': 1}.map(k, k + 'b')"),
+            json!(["ab"])
+        );
+    }
+
+    #[test]
+    fn test_cel_nesting_in_triple_quoted_strings() {
+        assert_eq!(bracket_depth(r#"'''(((('''"#), 0);
+        assert_eq!(bracket_depth(r#"""" "(((( """"#), 4);
+        assert_eq!(bracket_depth(r#"'''a'(((('''"#), 4);
+        assert_eq!(bracket_depth(r#"""")))"""((((1))))"#), 4);
+    }
 }
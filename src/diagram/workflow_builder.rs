@@ -16,24 +16,28 @@
 */
 
 use std::{
+    any::Any,
     borrow::Cow,
     collections::{hash_map::Entry, HashMap, HashSet},
     sync::Arc,
 };
 
 use crate::{
-    dyn_node::DynStreamInputPack, AnyBuffer, BufferIdentifier, BufferMap, Builder,
-    BuilderScopeContext, JsonMessage, Scope, StreamPack,
+    dyn_node::DynStreamInputPack, AnyBuffer, BlockingMap, BufferIdentifier, BufferMap, Builder,
+    BuilderScopeContext, Cancellation, JsonMessage, OperationLabel, Scope, StreamPack,
+    TriggeredCancellation,
 };
 
 use super::{
-    BufferSelection, BuiltinTarget, Diagram, DiagramElementRegistry, DiagramError,
-    DiagramErrorCode, DynInputSlot, DynOutput, FinishingErrors, ImplicitDeserialization,
-    ImplicitSerialization, ImplicitStringify, NamespacedOperation, NextOperation, OperationName,
-    Operations, Templates, TypeInfo,
+    cleanup_schema::validate_cleanup_names, contract::MessageContract, transform_schema::CelCache,
+    BufferSelection, BuiltinTarget, CelProgram, ContractSide, Diagram, DiagramElementRegistry,
+    DiagramError, DiagramErrorCode, DynInputSlot, DynOutput, FinishingErrors,
+    ImplicitDeserialization, ImplicitSerialization, ImplicitStringify, NamespacedOperation,
+    NextOperation, OperationName, Operations, SerializedCancellationCause, Templates,
+    TransformError, TypeInfo,
 };
 
-use bevy_ecs::prelude::Entity;
+use bevy_ecs::prelude::{Commands, Entity};
 
 use smallvec::{smallvec, SmallVec};
 
@@ -307,6 +311,10 @@ struct DiagramConstruction {
     buffers: HashMap<OperationRef, BufferRef>,
     /// Operations that were spawned by another operation.
     generated_operations: Vec<UnfinishedOperation>,
+    /// CEL programs that have been compiled for this workflow.
+    cel_programs: CelCache,
+    /// Labels that should be given to the operations of the diagram.
+    labels: Vec<(Entity, Arc<str>)>,
 }
 
 impl<'a> DiagramConstruction {
@@ -320,6 +328,14 @@ impl<'a> DiagramConstruction {
             unfinished
         }));
     }
+
+    fn apply_labels(&mut self, commands: &mut Commands) {
+        for (operation, label) in self.labels.drain(..) {
+            commands
+                .entity(operation)
+                .insert(OperationLabel::new(label));
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -425,6 +441,14 @@ impl<'a, 'c> DiagramContext<'a, 'c> {
     /// * `output` - The output channel that needs to be connected into the target.
     pub fn add_output_into_target(&mut self, target: impl Into<OperationRef>, output: DynOutput) {
         let target = self.into_operation_ref(target);
+        self.add_output_into_resolved_target(target, output);
+    }
+
+    /// Same as [`Self::add_output_into_target`] except the target has already
+    /// been resolved, e.g. by [`Self::into_operation_ref`] or
+    /// [`Self::get_implicit_error_target`], so the namespaces of the current
+    /// operation will not be applied to it again.
+    pub fn add_output_into_resolved_target(&mut self, target: OperationRef, output: DynOutput) {
         self.construction
             .outputs_into_target
             .entry(target)
@@ -456,10 +480,30 @@ impl<'a, 'c> DiagramContext<'a, 'c> {
         input: DynInputSlot,
     ) -> Result<(), DiagramErrorCode> {
         let operation = self.into_operation_ref(operation);
+        self.label_operation(input.id(), &operation);
         let connect = standard_input_connection(input, &self.registry)?;
         self.impl_connect_into_target(operation, connect)
     }
 
+    /// Give the entity of an operation an [`OperationLabel`] based on its name
+    /// and namespaces. Only named operations receive labels.
+    ///
+    /// The operation must already be fully resolved, e.g. by
+    /// [`Self::into_operation_ref`].
+    pub(super) fn label_operation(&mut self, entity: Entity, operation: &OperationRef) {
+        let OperationRef::Named(named) = operation else {
+            return;
+        };
+
+        // Exposed inputs of sections are only aliases for operations that get
+        // labelled with their own names.
+        if named.exposed_namespace.is_none() {
+            self.construction
+                .labels
+                .push((entity, named.to_string().into()));
+        }
+    }
+
     /// Set the implementation for how outputs connect into this target. This is
     /// a more general method than [`Self::set_input_for_target`].
     ///
@@ -482,6 +526,37 @@ impl<'a, 'c> DiagramContext<'a, 'c> {
         self.impl_connect_into_target(operation, connect)
     }
 
+    /// Same as [`Self::set_connect_into_target`] except the connection will be
+    /// made inside of the given scope instead of the scope of the current
+    /// operation. This is used by operations that create a new scope and need
+    /// to provide builtin targets inside of it.
+    ///
+    /// The operation must already be fully resolved, e.g. by
+    /// [`Self::into_child_operation_ref`].
+    pub(super) fn set_connect_into_target_in_scope<C: ConnectIntoTarget + 'static>(
+        &mut self,
+        operation: OperationRef,
+        connect: C,
+        scope: BuilderScopeContext,
+        on_implicit_error: OperationRef,
+    ) -> Result<(), DiagramErrorCode> {
+        match self
+            .construction
+            .connect_into_target
+            .entry(operation.clone())
+        {
+            Entry::Occupied(_) => Err(DiagramErrorCode::DuplicateInputsCreated(operation)),
+            Entry::Vacant(vacant) => {
+                vacant.insert(Target {
+                    connector: Box::new(connect),
+                    scope,
+                    on_implicit_error,
+                });
+                Ok(())
+            }
+        }
+    }
+
     /// Internal implementation of adding a connection into a target
     fn impl_connect_into_target(
         &mut self,
@@ -500,6 +575,7 @@ impl<'a, 'c> DiagramContext<'a, 'c> {
                 vacant.insert(Target {
                     connector,
                     scope: self.scope,
+                    on_implicit_error: self.on_implicit_error.clone(),
                 });
             }
         }
@@ -575,6 +651,16 @@ impl<'a, 'c> DiagramContext<'a, 'c> {
         }
     }
 
+    /// Get a compiled CEL program for the given expression. Each unique
+    /// expression is only compiled once per workflow, no matter how many
+    /// operations use it. The program will be checked against the
+    /// [`CelLimits`](super::CelLimits) of the registry.
+    pub fn compile_cel(&mut self, source: &str) -> Result<Arc<CelProgram>, TransformError> {
+        self.construction
+            .cel_programs
+            .get_or_compile(source, self.registry.cel_limits())
+    }
+
     pub fn get_implicit_error_target(&self) -> OperationRef {
         self.on_implicit_error.clone()
     }
@@ -606,6 +692,10 @@ impl<'a, 'c> DiagramContext<'a, 'c> {
     ///
     /// Use the scope argument if the child operation exists in a different scope
     /// than the parent. For the child of a Section, this is None.
+    ///
+    /// Use the on_implicit_error argument if implicit errors of the child
+    /// operation should be handled differently than those of the parent. If
+    /// this is None then the child will use the same target as the parent.
     pub fn add_child_operation<T: BuildDiagramOperation + 'static>(
         &mut self,
         id: &OperationName,
@@ -613,6 +703,7 @@ impl<'a, 'c> DiagramContext<'a, 'c> {
         op: &Arc<T>,
         sibling_ops: Operations,
         scope: Option<BuilderScopeContext>,
+        on_implicit_error: Option<OperationRef>,
     ) {
         let mut namespaces = self.namespaces.clone();
         namespaces.push(Arc::clone(id));
@@ -626,6 +717,8 @@ impl<'a, 'c> DiagramContext<'a, 'c> {
                 op: op.into(),
                 sibling_ops: sibling_ops.clone(),
                 scope: scope.unwrap_or(self.scope),
+                on_implicit_error: on_implicit_error
+                    .unwrap_or_else(|| self.on_implicit_error.clone()),
             });
     }
 
@@ -870,8 +963,23 @@ where
     Streams: StreamPack,
 {
     diagram.validate_operation_names()?;
+    validate_cleanup_names(&diagram.cleanup, &diagram.ops)?;
     diagram.validate_template_usage()?;
 
+    let input_contract = diagram
+        .input_schema
+        .as_ref()
+        .map(|name| MessageContract::new::<Request>(ContractSide::Input, name, &registry.messages))
+        .transpose()?;
+
+    let output_contract = diagram
+        .output_schema
+        .as_ref()
+        .map(|name| {
+            MessageContract::new::<Response>(ContractSide::Output, name, &registry.messages)
+        })
+        .transpose()?;
+
     let mut construction = DiagramConstruction::default();
 
     let default_on_implicit_error = OperationRef::Cancel(NamespaceList::new());
@@ -885,6 +993,8 @@ where
     initialize_builtin_operations(
         diagram.start.clone(),
         scope,
+        input_contract,
+        output_contract,
         builder,
         &mut DiagramContext {
             construction: &mut construction,
@@ -906,8 +1016,18 @@ where
                 as_build_diagram_operation(op),
                 &diagram.ops,
                 builder.context,
+                on_implicit_error.clone(),
             )
         })
+        .chain(diagram.cleanup.iter().map(|(id, cleanup)| {
+            UnfinishedOperation::new(
+                Arc::clone(id),
+                as_build_diagram_operation(cleanup),
+                &diagram.ops,
+                builder.context,
+                on_implicit_error.clone(),
+            )
+        }))
         .collect();
     let mut deferred_operations: Vec<UnfinishedOperation> = Vec::new();
     let mut deferred_statuses: Vec<(OperationRef, BuildStatus)> = Vec::new();
@@ -927,7 +1047,7 @@ where
                 registry,
                 operations: unfinished.sibling_ops.clone(),
                 templates: &diagram.templates,
-                on_implicit_error,
+                on_implicit_error: &unfinished.on_implicit_error,
                 namespaces: unfinished.namespaces.clone(),
                 scope: unfinished.scope,
             };
@@ -945,6 +1065,7 @@ where
 
             ctx.construction
                 .transfer_generated_operations(&mut deferred_operations, &mut made_progress);
+            ctx.construction.apply_labels(builder.commands());
 
             made_progress |= status.made_progress();
             if !status.is_finished() {
@@ -978,7 +1099,7 @@ where
                     registry,
                     operations: diagram.ops.clone(),
                     templates: &diagram.templates,
-                    on_implicit_error,
+                    on_implicit_error: &target.on_implicit_error,
                     // TODO(@mxgrey): The namespace while connecting into targets
                     // is always empty since the ConnectIntoTargets implementation
                     // is expected to provide targets that are already fully
@@ -1020,6 +1141,7 @@ where
 
             connector_construction
                 .transfer_generated_operations(&mut unfinished_operations, &mut made_progress);
+            connector_construction.apply_labels(builder.commands());
 
             // TODO(@mxgrey): Consider draining new connect_into_target entries
             // out of connector_construction.
@@ -1078,11 +1200,14 @@ struct UnfinishedOperation {
     /// The scope of this operation. This is used to create the correct Builder
     /// for the operation.
     scope: BuilderScopeContext,
+    /// Where implicit errors of this operation should be sent.
+    on_implicit_error: OperationRef,
 }
 
 struct Target {
     connector: Box<dyn ConnectIntoTarget>,
     scope: BuilderScopeContext,
+    on_implicit_error: OperationRef,
 }
 
 impl std::fmt::Debug for UnfinishedOperation {
@@ -1100,6 +1225,7 @@ impl UnfinishedOperation {
         op: Arc<dyn BuildDiagramOperation>,
         sibling_ops: &Operations,
         scope: BuilderScopeContext,
+        on_implicit_error: OperationRef,
     ) -> Self {
         Self {
             id,
@@ -1107,6 +1233,7 @@ impl UnfinishedOperation {
             sibling_ops: sibling_ops.clone(),
             namespaces: Default::default(),
             scope,
+            on_implicit_error,
         }
     }
 
@@ -1123,6 +1250,8 @@ impl UnfinishedOperation {
 fn initialize_builtin_operations<Request, Response, Streams>(
     start: NextOperation,
     scope: Scope<Request, Response, Streams>,
+    input_contract: Option<MessageContract>,
+    output_contract: Option<MessageContract>,
     builder: &mut Builder,
     ctx: &mut DiagramContext,
 ) -> Result<(), DiagramError>
@@ -1131,13 +1260,30 @@ where
     Response: 'static + Send + Sync,
     Streams: StreamPack,
 {
-    // Put the input message into the diagram
-    ctx.add_output_into_target(&start, scope.input.into());
-
-    // Add the terminate operation
+    // Put the input message into the diagram, validating it first if the
+    // diagram has an input contract.
+    let mut input: DynOutput = scope.input.into();
+    if let Some((validate, validated)) = input_contract.and_then(|c| c.create_validation(builder)) {
+        input
+            .connect_to(&validate, builder)
+            .map_err(DiagramErrorCode::from)?;
+        input = validated;
+    }
+    ctx.add_output_into_target(&start, input);
+
+    // Add the terminate operation, validating the response first if the
+    // diagram has an output contract.
+    let mut terminate: DynInputSlot = scope.terminate.into();
+    if let Some((validate, validated)) = output_contract.and_then(|c| c.create_validation(builder))
+    {
+        validated
+            .connect_to(&terminate, builder)
+            .map_err(DiagramErrorCode::from)?;
+        terminate = validate;
+    }
     ctx.impl_connect_into_target(
         OperationRef::Terminate(NamespaceList::new()),
-        standard_input_connection(scope.terminate.into(), &ctx.registry)?,
+        standard_input_connection(terminate, ctx.registry)?,
     )?;
 
     let mut streams = DynStreamInputPack::default();
@@ -1274,7 +1420,7 @@ impl ConnectIntoTarget for BasicConnect {
     }
 }
 
-struct ConnectToCancel {
+pub(super) struct ConnectToCancel {
     quiet_cancel: DynInputSlot,
     implicit_serialization: ImplicitSerialization,
     implicit_stringify: ImplicitStringify,
@@ -1282,7 +1428,8 @@ struct ConnectToCancel {
 }
 
 impl ConnectToCancel {
-    fn new(builder: &mut Builder) -> Result<Self, DiagramErrorCode> {
+    /// Cancel the scope of the builder when a message arrives.
+    pub(super) fn new(builder: &mut Builder) -> Result<Self, DiagramErrorCode> {
         Ok(Self {
             quiet_cancel: builder.create_quiet_cancel().into(),
             implicit_serialization: ImplicitSerialization::new(
@@ -1292,6 +1439,62 @@ impl ConnectToCancel {
             triggers: Default::default(),
         })
     }
+
+    /// Instead of cancelling anything, send a [`SerializedCancellationCause`]
+    /// describing the triggered cancellation to the target. If `report` is
+    /// true then a [`CancellationReport`](super::CancellationReport) is sent
+    /// instead.
+    pub(super) fn redirect(
+        target: &NextOperation,
+        report: bool,
+        builder: &mut Builder,
+        ctx: &mut DiagramContext,
+    ) -> Result<Self, DiagramErrorCode> {
+        let (quiet, json, string) = if report {
+            fn triggered<T>(source: Entity, message: Option<T>) -> JsonMessage
+            where
+                T: 'static + Send + Sync + ToString,
+            {
+                let cancellation: Cancellation = TriggeredCancellation {
+                    cancelled_at_node: source,
+                    value: message.as_ref().map(ToString::to_string),
+                    message: message.map(|m| Arc::new(m) as Arc<dyn Any + Send + Sync>),
+                }
+                .into();
+                cancellation.report().to_json()
+            }
+
+            let quiet = builder
+                .create_map(|input: BlockingMap<()>| triggered::<String>(input.source, None));
+            let json = builder.create_map(|input: BlockingMap<JsonMessage>| {
+                triggered(input.source, Some(input.request))
+            });
+            let string = builder.create_map(|input: BlockingMap<String>| {
+                triggered(input.source, Some(input.request))
+            });
+            (quiet, json, string)
+        } else {
+            fn triggered(value: Option<JsonMessage>) -> JsonMessage {
+                SerializedCancellationCause::Triggered { value }.to_json()
+            }
+
+            let quiet = builder.create_map_block(|_: ()| triggered(None));
+            let json = builder.create_map_block(|value: JsonMessage| triggered(Some(value)));
+            let string = builder.create_map_block(|value: String| triggered(Some(value.into())));
+            (quiet, json, string)
+        };
+
+        for output in [quiet.output, json.output, string.output] {
+            ctx.add_output_into_target(target, output.into());
+        }
+
+        Ok(Self {
+            quiet_cancel: quiet.input.into(),
+            implicit_serialization: ImplicitSerialization::new(json.input.into())?,
+            implicit_stringify: ImplicitStringify::new(string.input.into())?,
+            triggers: Default::default(),
+        })
+    }
 }
 
 impl ConnectIntoTarget for ConnectToCancel {
@@ -22,32 +22,47 @@ use bevy_ecs::{
 
 use backtrace::Backtrace;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
 
 use smallvec::SmallVec;
 
 use thiserror::Error as ThisError;
 
 use crate::{
-    operation::ScopeStorage, Cancel, Cancellation, DisposalFailure, ImpulseMarker, OperationResult,
-    OperationRoster, OrBroken, UnhandledErrors, UnusedTarget,
+    disposal_span, operation::ScopeStorage, record_disposal, report_unhandled_error, Cancel,
+    Cancellation, DisplayFn, DisposalFailure, ImpulseMarker, OperationLabels, OperationResult,
+    OperationRoster, OrBroken, UnusedTarget,
 };
 
 #[derive(ThisError, Debug, Clone)]
 #[error("The output of an operation in a workflow was disposed")]
 pub struct Disposal {
     pub cause: Arc<DisposalCause>,
+    /// Labels of the operations that are referred to by the cause. These are
+    /// captured by the workflow when the disposal is emitted.
+    pub(crate) labels: OperationLabels,
 }
 
 impl<T: Into<DisposalCause>> From<T> for Disposal {
     fn from(value: T) -> Self {
         Disposal {
             cause: Arc::new(value.into()),
+            labels: Default::default(),
         }
     }
 }
 
 impl Disposal {
+    /// Labels of the operations that are referred to by the cause of this
+    /// disposal.
+    pub fn labels(&self) -> &OperationLabels {
+        &self.labels
+    }
+
     pub fn service_unavailable(service: Entity, for_node: Entity) -> Disposal {
         ServiceUnavailable { service, for_node }.into()
     }
@@ -193,6 +208,151 @@ pub enum DisposalCause {
     IncompleteSplit(IncompleteSplit),
 }
 
+impl DisposalCause {
+    /// Display this cause, including the labels of any operations that it
+    /// refers to.
+    pub fn display_with<'a>(&'a self, labels: &'a OperationLabels) -> impl Display + 'a {
+        DisplayFn(move |f: &mut Formatter<'_>| self.fmt_with_labels(labels, f))
+    }
+
+    fn fmt_with_labels(&self, labels: &OperationLabels, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Supplanted(supplanted) => write!(
+                f,
+                "the request at operation {} was supplanted by operation {}",
+                labels.entity(supplanted.supplanted_at_node),
+                labels.entity(supplanted.supplanted_by_node),
+            ),
+            Self::Filtered(filtered) => {
+                write!(
+                    f,
+                    "filtered at operation {}",
+                    labels.entity(filtered.filtered_at_node),
+                )?;
+                if let Some(reason) = &filtered.reason {
+                    write!(f, ": {reason}")?;
+                }
+                Ok(())
+            }
+            Self::Branching(branch) => {
+                write!(
+                    f,
+                    "operation {} disposed its branch to {}",
+                    labels.entity(branch.branched_at_node),
+                    labels.entity(branch.disposed_for_target),
+                )?;
+                if let Some(reason) = &branch.reason {
+                    write!(f, ": {reason}")?;
+                }
+                Ok(())
+            }
+            Self::BufferKey(key) => write!(
+                f,
+                "operation {} disposed its key for buffer {}",
+                labels.entity(key.accessor_node),
+                labels.entity(key.key_for_buffer),
+            ),
+            Self::ServiceUnavailable(unavailable) => write!(
+                f,
+                "service {:?} is unavailable for operation {}",
+                unavailable.service,
+                labels.entity(unavailable.for_node),
+            ),
+            Self::TaskDespawned(despawned) => write!(
+                f,
+                "task {:?} of operation {} was despawned",
+                despawned.task,
+                labels.entity(despawned.node),
+            ),
+            Self::PoisonedMutex(poisoned) => write!(
+                f,
+                "a mutex was poisoned in operation {}",
+                labels.entity(poisoned.for_node),
+            ),
+            Self::Scope(cancellation) => write!(
+                f,
+                "a scope was cancelled: {}",
+                cancellation.cause.display_with(&cancellation.labels),
+            ),
+            Self::UnusedStreams(unused) => write!(
+                f,
+                "operation {} did not use its streams {:?}",
+                labels.entity(unused.node),
+                unused.streams,
+            ),
+            Self::Trimming(trimming) => {
+                write!(f, "operation {} trimmed", labels.entity(trimming.trimmer))?;
+                for node in &trimming.nodes {
+                    write!(f, " {}", labels.entity(*node))?;
+                }
+                Ok(())
+            }
+            Self::ClosedGate(gate) => {
+                write!(f, "gate {} closed", labels.entity(gate.gate_node))?;
+                for buffer in &gate.closed_buffers {
+                    write!(f, " {}", labels.entity(*buffer))?;
+                }
+                Ok(())
+            }
+            Self::EmptySpread(spread) => write!(
+                f,
+                "operation {} had nothing to spread",
+                labels.entity(spread.spread_node),
+            ),
+            Self::DeficientCollection(collection) => write!(
+                f,
+                "operation {} collected {} items but needed at least {}",
+                labels.entity(collection.collect_node),
+                collection.actual,
+                collection.min,
+            ),
+            Self::IncompleteSplit(split) => write!(
+                f,
+                "operation {} did not send a value for keys {:?}",
+                labels.entity(split.split_node),
+                split.missing_keys,
+            ),
+        }
+    }
+
+    /// The operations that are referred to by this cause.
+    pub(crate) fn operations(&self) -> SmallVec<[Entity; 4]> {
+        match self {
+            Self::Supplanted(supplanted) => SmallVec::from_slice(&[
+                supplanted.supplanted_at_node,
+                supplanted.supplanted_by_node,
+            ]),
+            Self::Filtered(filtered) => SmallVec::from_slice(&[filtered.filtered_at_node]),
+            Self::Branching(branch) => {
+                SmallVec::from_slice(&[branch.branched_at_node, branch.disposed_for_target])
+            }
+            Self::BufferKey(key) => SmallVec::from_slice(&[key.accessor_node, key.key_for_buffer]),
+            Self::ServiceUnavailable(unavailable) => SmallVec::from_slice(&[unavailable.for_node]),
+            Self::TaskDespawned(despawned) => SmallVec::from_slice(&[despawned.node]),
+            Self::PoisonedMutex(poisoned) => SmallVec::from_slice(&[poisoned.for_node]),
+            Self::Scope(_) => SmallVec::new(),
+            Self::UnusedStreams(unused) => SmallVec::from_slice(&[unused.node]),
+            Self::Trimming(trimming) => std::iter::once(trimming.trimmer)
+                .chain(trimming.nodes.iter().copied())
+                .collect(),
+            Self::ClosedGate(gate) => std::iter::once(gate.gate_node)
+                .chain(gate.closed_buffers.iter().copied())
+                .collect(),
+            Self::EmptySpread(spread) => SmallVec::from_slice(&[spread.spread_node]),
+            Self::DeficientCollection(collection) => {
+                SmallVec::from_slice(&[collection.collect_node])
+            }
+            Self::IncompleteSplit(split) => SmallVec::from_slice(&[split.split_node]),
+        }
+    }
+}
+
+impl Display for DisposalCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.fmt_with_labels(&OperationLabels::default(), f)
+    }
+}
+
 /// A variant of [`DisposalCause`]
 #[derive(Debug, Clone, Copy)]
 pub struct Supplanted {
@@ -432,7 +592,17 @@ pub trait InspectDisposals {
 }
 
 impl<'w> ManageDisposal for EntityWorldMut<'w> {
-    fn emit_disposal(&mut self, session: Entity, disposal: Disposal, roster: &mut OperationRoster) {
+    fn emit_disposal(
+        &mut self,
+        session: Entity,
+        mut disposal: Disposal,
+        roster: &mut OperationRoster,
+    ) {
+        let operations = disposal.cause.operations();
+        disposal.labels.capture(operations, self.world());
+        let source = self.id();
+        self.world_scope(|world| record_disposal(source, world));
+        let _span = disposal_span(self.id(), session, &disposal.cause, self.world()).entered();
         let Some(scope) = self.get::<ScopeStorage>() else {
             if self.contains::<ImpulseMarker>() {
                 // If an impulse has been supplanted, we trigger a cancellation
@@ -461,14 +631,14 @@ impl<'w> ManageDisposal for EntityWorldMut<'w> {
                 // unused targets cannot affect the reachability of a workflow.
                 let broken_node = self.id();
                 self.world_scope(|world| {
-                    world
-                        .get_resource_or_insert_with(UnhandledErrors::default)
-                        .disposals
-                        .push(DisposalFailure {
+                    report_unhandled_error(
+                        DisposalFailure {
                             disposal,
                             broken_node,
                             backtrace: Some(Backtrace::new()),
-                        });
+                        },
+                        world,
+                    );
                 });
             }
             return;
@@ -548,14 +718,14 @@ pub fn emit_disposal(
     if let Some(mut source_mut) = world.get_entity_mut(source) {
         source_mut.emit_disposal(session, disposal, roster);
     } else {
-        world
-            .get_resource_or_insert_with(UnhandledErrors::default)
-            .disposals
-            .push(DisposalFailure {
+        report_unhandled_error(
+            DisposalFailure {
                 disposal,
                 broken_node: source,
                 backtrace: Some(Backtrace::new()),
-            });
+            },
+            world,
+        );
     }
 }
 
@@ -15,18 +15,31 @@
  *
 */
 
-use bevy_ecs::prelude::{Entity, Resource};
+use bevy_ecs::{
+    event::Events,
+    prelude::{Entity, EventReader, Resource, World},
+};
 
 use backtrace::Backtrace;
 
 use anyhow::Error as Anyhow;
 
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
 
-use crate::{Broken, Cancel, Disposal, OperationError};
+use crate::{Broken, Cancel, Disposal, Livelock, OperationError};
 
 /// This resource stores errors that have occurred that could not be handled
 /// internally or communicated to the user by any other means.
+///
+/// Every error is also sent as an [`UnhandledError`] event (as long as that
+/// event type has been added to the app, which [`ImpulsePlugin`](crate::ImpulsePlugin)
+/// does) and passed to the [hook](UnhandledErrorSettings::hook) if one is set.
+/// Use [`UnhandledErrorSettings::retention`] to limit how many errors get kept
+/// in this resource.
 #[derive(Resource, Default, Clone, Debug)]
 pub struct UnhandledErrors {
     pub setup: Vec<SetupFailure>,
@@ -38,6 +51,7 @@ pub struct UnhandledErrors {
     pub unused_targets: Vec<UnusedTargetDrop>,
     pub connections: Vec<ConnectionFailure>,
     pub duplicate_streams: Vec<DuplicateStream>,
+    pub livelocks: Vec<Livelock>,
     pub miscellaneous: Vec<MiscellaneousFailure>,
 }
 
@@ -52,8 +66,247 @@ impl UnhandledErrors {
             && self.unused_targets.is_empty()
             && self.connections.is_empty()
             && self.duplicate_streams.is_empty()
+            && self.livelocks.is_empty()
             && self.miscellaneous.is_empty()
     }
+
+    /// Store an error in the category that it belongs to, then apply the
+    /// retention policy to that category.
+    pub fn push(&mut self, error: impl Into<UnhandledError>, retention: UnhandledErrorRetention) {
+        fn store<T>(list: &mut Vec<T>, item: T, retention: UnhandledErrorRetention) {
+            match retention {
+                UnhandledErrorRetention::KeepAll => list.push(item),
+                UnhandledErrorRetention::KeepLast(limit) => {
+                    list.push(item);
+                    if list.len() > limit {
+                        let excess = list.len() - limit;
+                        list.drain(..excess);
+                    }
+                }
+                UnhandledErrorRetention::KeepNone => {}
+            }
+        }
+
+        match error.into() {
+            UnhandledError::Setup(e) => store(&mut self.setup, e, retention),
+            UnhandledError::Cancellation(e) => store(&mut self.cancellations, e, retention),
+            UnhandledError::Operation(e) => store(&mut self.operations, e, retention),
+            UnhandledError::Disposal(e) => store(&mut self.disposals, e, retention),
+            UnhandledError::StopTask(e) => store(&mut self.stop_tasks, e, retention),
+            UnhandledError::Broken(e) => store(&mut self.broken, e, retention),
+            UnhandledError::UnusedTarget(e) => store(&mut self.unused_targets, e, retention),
+            UnhandledError::Connection(e) => store(&mut self.connections, e, retention),
+            UnhandledError::DuplicateStream(e) => store(&mut self.duplicate_streams, e, retention),
+            UnhandledError::Livelock(e) => store(&mut self.livelocks, e, retention),
+            UnhandledError::Miscellaneous(e) => store(&mut self.miscellaneous, e, retention),
+        }
+    }
+
+    /// Remove all the errors that have been stored so far.
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+}
+
+/// Decide how many errors get kept in the [`UnhandledErrors`] resource.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnhandledErrorRetention {
+    /// Keep every error. This is the default, but the resource will grow
+    /// without bound unless something drains it.
+    #[default]
+    KeepAll,
+    /// Keep only the most recent errors of each category, up to this limit.
+    KeepLast(usize),
+    /// Do not keep any errors in the resource. They will still be sent as
+    /// [`UnhandledError`] events and passed to the hook.
+    KeepNone,
+}
+
+/// A callback that gets triggered for each [`UnhandledError`] as it is recorded.
+pub type UnhandledErrorHook = Arc<dyn Fn(&UnhandledError) + Send + Sync>;
+
+/// Insert this resource to configure how unhandled errors get reported.
+#[derive(Resource, Default, Clone)]
+pub struct UnhandledErrorSettings {
+    pub retention: UnhandledErrorRetention,
+    pub hook: Option<UnhandledErrorHook>,
+}
+
+impl UnhandledErrorSettings {
+    pub fn with_retention(mut self, retention: UnhandledErrorRetention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    pub fn with_hook(mut self, hook: impl Fn(&UnhandledError) + Send + Sync + 'static) -> Self {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+}
+
+impl std::fmt::Debug for UnhandledErrorSettings {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("UnhandledErrorSettings")
+            .field("retention", &self.retention)
+            .field("hook", &self.hook.is_some())
+            .finish()
+    }
+}
+
+/// A single error from any of the categories of [`UnhandledErrors`]. This is
+/// sent as a bevy event each time an unhandled error is recorded.
+#[derive(bevy_ecs::prelude::Event, Clone, Debug)]
+pub enum UnhandledError {
+    Setup(SetupFailure),
+    Cancellation(CancelFailure),
+    Operation(OperationError),
+    Disposal(DisposalFailure),
+    StopTask(StopTaskFailure),
+    Broken(Broken),
+    UnusedTarget(UnusedTargetDrop),
+    Connection(ConnectionFailure),
+    DuplicateStream(DuplicateStream),
+    Livelock(Livelock),
+    Miscellaneous(MiscellaneousFailure),
+}
+
+impl UnhandledError {
+    /// The backtrace of the error, if one was captured.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        match self {
+            Self::Setup(e) => broken(&e.error),
+            Self::Cancellation(e) => broken(&e.error),
+            Self::Operation(e) => broken(e),
+            Self::Disposal(e) => e.backtrace.as_ref(),
+            Self::StopTask(e) => e.backtrace.as_ref(),
+            Self::Broken(e) => e.backtrace.as_ref(),
+            Self::UnusedTarget(_) => None,
+            Self::Connection(e) => Some(&e.backtrace),
+            Self::DuplicateStream(_) => None,
+            Self::Livelock(_) => None,
+            Self::Miscellaneous(e) => e.backtrace.as_ref(),
+        }
+    }
+}
+
+fn broken(error: &OperationError) -> Option<&Backtrace> {
+    match error {
+        OperationError::Broken(backtrace) => backtrace.as_ref(),
+        OperationError::NotReady => None,
+    }
+}
+
+impl Display for UnhandledError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Setup(e) => write!(
+                f,
+                "failed to set up operation {:?}: {}",
+                e.broken_node, e.error
+            ),
+            Self::Cancellation(e) => write!(
+                f,
+                "failed to deliver a cancellation: {} [{}]",
+                e.error
+                    .display_with(e.cancel.target, &e.cancel.cancellation.labels),
+                e.cancel.cancellation,
+            ),
+            Self::Operation(e) => write!(f, "{e}"),
+            Self::Disposal(e) => write!(
+                f,
+                "operation {:?} failed to report a disposal: {}",
+                e.broken_node, e.disposal,
+            ),
+            Self::StopTask(e) => write!(f, "unable to stop task {:?}", e.task),
+            Self::Broken(e) => write!(f, "operation {:?} is broken", e.node),
+            Self::UnusedTarget(e) => write!(
+                f,
+                "impulse target {:?} was dropped without being used or detached, \
+                which dropped impulses {:?}",
+                e.unused_target, e.dropped_impulses,
+            ),
+            Self::Connection(e) => write!(
+                f,
+                "failed to redirect {:?} to {:?}",
+                e.original_target, e.new_target,
+            ),
+            Self::DuplicateStream(e) => {
+                write!(f, "duplicate stream of type {}", e.type_name)?;
+                if let Some(name) = &e.stream_name {
+                    write!(f, " named \"{name}\"")?;
+                }
+                write!(f, " for target {:?} will never receive data", e.target)
+            }
+            Self::Livelock(e) => write!(f, "{e}"),
+            Self::Miscellaneous(e) => write!(f, "{}", e.error),
+        }
+    }
+}
+
+macro_rules! impl_from_unhandled {
+    ($($variant:ident($failure:ty)),* $(,)?) => {
+        $(
+            impl From<$failure> for UnhandledError {
+                fn from(value: $failure) -> Self {
+                    Self::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_unhandled!(
+    Setup(SetupFailure),
+    Cancellation(CancelFailure),
+    Operation(OperationError),
+    Disposal(DisposalFailure),
+    StopTask(StopTaskFailure),
+    Broken(Broken),
+    UnusedTarget(UnusedTargetDrop),
+    Connection(ConnectionFailure),
+    DuplicateStream(DuplicateStream),
+    Livelock(Livelock),
+    Miscellaneous(MiscellaneousFailure),
+);
+
+/// Report an error that could not be handled any other way. The error will be
+/// passed to the hook, sent as an event, and stored in [`UnhandledErrors`]
+/// according to the [`UnhandledErrorSettings`].
+pub(crate) fn report_unhandled_error(error: impl Into<UnhandledError>, world: &mut World) {
+    let error = error.into();
+    let settings = world.get_resource::<UnhandledErrorSettings>();
+    let retention = settings.map(|s| s.retention).unwrap_or_default();
+    if let Some(hook) = settings.and_then(|s| s.hook.clone()) {
+        hook(&error);
+    }
+
+    if let Some(mut events) = world.get_resource_mut::<Events<UnhandledError>>() {
+        events.send(error.clone());
+    }
+
+    world
+        .get_resource_or_insert_with(UnhandledErrors::default)
+        .push(error, retention);
+}
+
+/// A system that logs each [`UnhandledError`] event through [`tracing`],
+/// including its backtrace when one is available.
+///
+/// ```
+/// use bevy_app::{App, Update};
+/// use bevy_impulse::{log_unhandled_errors, ImpulseAppPlugin};
+///
+/// App::new()
+///     .add_plugins(ImpulseAppPlugin::default())
+///     .add_systems(Update, log_unhandled_errors);
+/// ```
+pub fn log_unhandled_errors(mut errors: EventReader<UnhandledError>) {
+    for error in errors.read() {
+        match error.backtrace() {
+            Some(backtrace) => tracing::error!("unhandled error: {error}\n{backtrace:?}"),
+            None => tracing::error!("unhandled error: {error}"),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -138,3 +391,44 @@ pub struct DuplicateStream {
     /// this will be [`None`]).
     pub stream_name: Option<Cow<'static, str>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::*, *};
+    use bevy_ecs::event::Events;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn test_unhandled_error_reporting() {
+        let mut context = TestingContext::minimal_plugins();
+        context.app.add_event::<UnhandledError>();
+        let hook_count = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&hook_count);
+        context.app.insert_resource(
+            UnhandledErrorSettings::default()
+                .with_retention(UnhandledErrorRetention::KeepLast(1))
+                .with_hook(move |error| {
+                    assert!(matches!(error, UnhandledError::UnusedTarget(_)));
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }),
+        );
+
+        for _ in 0..3 {
+            // Neither detaching nor taking the impulse is a usage error which
+            // gets reported as an unhandled error.
+            context.command(|commands| {
+                commands.provide(5_u32);
+            });
+        }
+        context.run(1);
+
+        assert_eq!(hook_count.load(Ordering::SeqCst), 3);
+        let events = context.app.world.resource::<Events<UnhandledError>>();
+        assert_eq!(events.len(), 3);
+        let errors = context.get_unhandled_errors().unwrap();
+        assert_eq!(errors.unused_targets.len(), 1);
+    }
+}
@@ -29,13 +29,20 @@ use anyhow::anyhow;
 
 use backtrace::Backtrace;
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    awaken_task, dispose_for_despawned_service, execute_operation, AddImpulse, ChannelQueue,
-    Detached, DisposalNotice, Finished, ImpulseLifecycleChannel, MiscellaneousFailure,
-    OperationError, OperationRequest, OperationRoster, ServiceHook, ServiceLifecycle,
-    ServiceLifecycleChannel, UnhandledErrors, UnusedTarget, UnusedTargetDrop,
+    apply_debugger_actions, awaken_instrumented_task, dispose_for_despawned_service,
+    execute_instrumented_operation, hold_for_debugger, operation_kind, peek_session,
+    pop_next_operation, report_unhandled_error, AddImpulse, Cancellation, ChannelQueue, Detached,
+    DisposalNotice, Finished, ImpulseLifecycleChannel, Instrumentation, ManageCancellation,
+    MiscellaneousFailure, OperationError, OperationLabel, OperationRequest, OperationRoster,
+    ServiceHook, ServiceLifecycle, ServiceLifecycleChannel, UnusedTarget, UnusedTargetDrop,
     ValidateScopeReachability, ValidationRequest, WakeQueue,
 };
 
@@ -56,7 +63,19 @@ pub struct FlushParameters {
     /// during the next flush.
     ///
     /// A value of `None` means the flush can loop indefinitely (this is the default).
+    ///
+    /// If any operations kept executing because their own output came back to
+    /// them before the limit was reached, a [`Livelock`] will be reported to
+    /// the [`UnhandledErrors`](crate::UnhandledErrors).
     pub flush_loop_limit: Option<usize>,
+    /// When the [`flush_loop_limit`](Self::flush_loop_limit) is reached, cancel
+    /// the sessions that the repeating operations were executing for. This
+    /// breaks loops such as cycles of blocking services, which would otherwise
+    /// pick up where they left off during the next flush.
+    ///
+    /// This has no effect unless `flush_loop_limit` is set. The default is
+    /// false.
+    pub cancel_livelocked_sessions: bool,
     /// When using the single_threaded_async feature, async futures get polled
     /// during the impulse flush. If async futures repeatedly spawn more async
     /// tasks then the flush could get stuck in a loop. This parameter lets you
@@ -65,6 +84,227 @@ pub struct FlushParameters {
     ///
     /// A value of `None` means the futures can be polled indefinitely (this is the default).
     pub single_threaded_poll_limit: Option<usize>,
+    /// Put a limit on how much wall-clock time a flush may spend working
+    /// through the [`OperationRoster`]. This is useful for applications that
+    /// need to keep a steady frame rate. When the budget runs out, anything
+    /// remaining in the roster will be moved into a deferred roster which will
+    /// be processed during the next flush.
+    ///
+    /// The budget is checked between operations, so a single operation that
+    /// takes a long time can still make the flush exceed its budget. Each flush
+    /// will always execute at least one operation so that workflows continue to
+    /// make progress even with a very small budget.
+    ///
+    /// A value of `None` means the flush has no time limit (this is the default).
+    pub flush_time_budget: Option<Duration>,
+}
+
+/// Statistics about how the impulse flush has been limited by the
+/// [`FlushParameters`]. This resource is updated at the end of every flush.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct FlushTelemetry {
+    /// How many flushes have run.
+    pub flushes: usize,
+    /// How many flushes stopped early because they ran out of
+    /// [time](FlushParameters::flush_time_budget).
+    pub time_budget_exceeded: usize,
+    /// How many flushes stopped early because they reached the
+    /// [loop limit](FlushParameters::flush_loop_limit).
+    pub loop_limit_reached: usize,
+    /// How many items of work were deferred to the next flush by the most
+    /// recent flush.
+    pub last_deferred: usize,
+    /// How many items of work have been deferred across all flushes.
+    pub total_deferred: usize,
+    /// How long the most recent flush took.
+    pub last_flush_duration: Duration,
+    /// The longest time that any flush has taken.
+    pub longest_flush_duration: Duration,
+}
+
+impl FlushTelemetry {
+    fn record(&mut self, duration: Duration, deferred: usize) {
+        self.flushes += 1;
+        self.last_deferred = deferred;
+        self.total_deferred += deferred;
+        self.last_flush_duration = duration;
+        self.longest_flush_duration = self.longest_flush_duration.max(duration);
+    }
+}
+
+/// A diagnostic that gets reported when a flush reaches its
+/// [loop limit](FlushParameters::flush_loop_limit) while some operations were
+/// executing repeatedly, e.g. because of a cycle of blocking services.
+///
+/// When [`FlushParameters::cancel_livelocked_sessions`] is on, this is also
+/// the cause of the cancellation of each affected session.
+#[derive(Debug, Clone)]
+pub struct Livelock {
+    /// The operations that kept executing during the flush because their own
+    /// output came back to them, in the order that they first executed.
+    pub operations: Vec<LivelockOperation>,
+    /// The sessions that the repeating operations were executing for.
+    pub sessions: Vec<Entity>,
+    /// Whether the sessions were cancelled to break the loop.
+    pub cancelled: bool,
+}
+
+/// An operation that was executing repeatedly during a [`Livelock`].
+#[derive(Debug, Clone)]
+pub struct LivelockOperation {
+    pub operation: Entity,
+    /// The kind of operation, e.g. `Service` or `BlockingMap`.
+    pub kind: &'static str,
+    pub label: Option<OperationLabel>,
+    /// How many times the operation executed during the flush for the
+    /// sessions that were looping.
+    pub executions: usize,
+}
+
+impl Display for Livelock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "the flush loop limit was reached while operations kept executing:"
+        )?;
+        for (i, op) in self.operations.iter().enumerate() {
+            let separator = if i == 0 { " " } else { " -> " };
+            write!(f, "{separator}{} {:?}", op.kind, op.operation)?;
+            if let Some(label) = &op.label {
+                write!(f, " (\"{label}\")")?;
+            }
+            write!(f, " x{}", op.executions)?;
+        }
+        write!(f, " for sessions {:?}", self.sessions)
+    }
+}
+
+/// Keeps track of how many times each operation executes for each session
+/// during one flush, and which operations each execution fed, so that loops
+/// can be diagnosed when the flush loop limit is reached.
+#[derive(Default)]
+struct ExecutionTracker {
+    executions: HashMap<(Entity, Entity), usize>,
+    order: Vec<(Entity, Entity)>,
+    feeds: HashMap<Entity, HashSet<Entity>>,
+}
+
+impl ExecutionTracker {
+    /// Call this before executing an operation.
+    fn record(&mut self, source: Entity, world: &World, roster: &mut OperationRoster) {
+        // An operation can only loop within a session, so executions that do
+        // not belong to a session are not tracked.
+        let Some(session) = peek_session(source, world) else {
+            return;
+        };
+        let count = self.executions.entry((source, session)).or_insert_with(|| {
+            self.order.push((source, session));
+            0
+        });
+        *count += 1;
+        roster.fed = Some(Vec::new());
+    }
+
+    /// Call this after executing an operation to remember which operations it
+    /// fed.
+    fn record_fed(&mut self, source: Entity, roster: &mut OperationRoster) {
+        let Some(fed) = roster.fed.take() else {
+            return;
+        };
+        if !fed.is_empty() {
+            self.feeds.entry(source).or_default().extend(fed);
+        }
+    }
+
+    /// Check whether the output of `operation` can find its way back to
+    /// `operation` by only passing through operations that repeated.
+    fn feeds_itself(&self, operation: Entity, repeated: &HashSet<Entity>) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![operation];
+        while let Some(next) = stack.pop() {
+            let Some(fed) = self.feeds.get(&next) else {
+                continue;
+            };
+            for target in fed {
+                if *target == operation {
+                    return true;
+                }
+                if repeated.contains(target) && visited.insert(*target) {
+                    stack.push(*target);
+                }
+            }
+        }
+        false
+    }
+
+    /// Report the operations that kept executing for the same session because
+    /// their own output came back to them, and cancel those sessions if
+    /// requested.
+    fn report(self, cancel: bool, world: &mut World, roster: &mut OperationRoster) {
+        let repeated: HashSet<Entity> = self
+            .executions
+            .iter()
+            .filter(|(_, executions)| **executions > 1)
+            .map(|((source, _), _)| *source)
+            .collect();
+        let looping: HashSet<Entity> = repeated
+            .iter()
+            .copied()
+            .filter(|operation| self.feeds_itself(*operation, &repeated))
+            .collect();
+
+        let mut operations: Vec<LivelockOperation> = Vec::new();
+        let mut sessions: Vec<(Entity, Entity)> = Vec::new();
+        for (source, session) in &self.order {
+            let (source, session) = (*source, *session);
+            if !looping.contains(&source) {
+                continue;
+            }
+            let Some(executions) = self.executions.get(&(source, session)).copied() else {
+                continue;
+            };
+            if executions < 2 {
+                continue;
+            }
+
+            if let Some(op) = operations.iter_mut().find(|op| op.operation == source) {
+                op.executions += executions;
+            } else {
+                operations.push(LivelockOperation {
+                    operation: source,
+                    kind: operation_kind(source, world),
+                    label: world.get::<OperationLabel>(source).cloned(),
+                    executions,
+                });
+            }
+            if !sessions.iter().any(|(_, s)| *s == session) {
+                sessions.push((source, session));
+            }
+        }
+
+        if operations.is_empty() {
+            // No operation fed itself within a session, so the flush simply
+            // had a lot of work to do.
+            return;
+        }
+
+        let livelock = Livelock {
+            operations,
+            sessions: sessions.iter().map(|(_, session)| *session).collect(),
+            cancelled: cancel,
+        };
+
+        if cancel {
+            for (source, session) in sessions {
+                if let Some(mut source_mut) = world.get_entity_mut(source) {
+                    let cancellation: Cancellation = livelock.clone().into();
+                    source_mut.emit_cancel(session, cancellation, roster);
+                }
+            }
+        }
+
+        report_unhandled_error(livelock, world);
+    }
 }
 
 pub fn flush_impulses() -> SystemConfigs {
@@ -75,17 +315,25 @@ fn flush_impulses_impl(
     world: &mut World,
     new_service_query: &mut QueryState<(Entity, &mut ServiceHook), Added<ServiceHook>>,
 ) {
+    let start = Instant::now();
     let parameters = world.get_resource_or_insert_with(FlushParameters::default);
     let single_threaded_poll_limit = parameters.single_threaded_poll_limit;
     let mut roster = OperationRoster::new();
+    let instrumentation = Instrumentation::of(world);
     collect_from_channels(
         single_threaded_poll_limit,
         new_service_query,
         world,
         &mut roster,
     );
+    apply_debugger_actions(world, &mut roster);
 
     let mut loop_count = 0;
+    let mut deferred = 0;
+    let mut tracker = world
+        .get_resource_or_insert_with(FlushParameters::default)
+        .flush_loop_limit
+        .map(|_| ExecutionTracker::default());
     while !roster.is_empty() {
         for e in roster.deferred_despawn.drain(..) {
             if let Some(e_mut) = world.get_entity_mut(e) {
@@ -96,9 +344,30 @@ fn flush_impulses_impl(
         let parameters = world.get_resource_or_insert_with(FlushParameters::default);
         let flush_loop_limit = parameters.flush_loop_limit;
         let single_threaded_poll_limit = parameters.single_threaded_poll_limit;
-        if flush_loop_limit.is_some_and(|limit| limit <= loop_count) {
-            // We have looped beyoond the limit, so we will defer anything that
-            // remains in the roster and stop looping from here.
+        let time_budget = parameters.flush_time_budget;
+        let cancel_livelocked_sessions = parameters.cancel_livelocked_sessions;
+        let out_of_loops = flush_loop_limit.is_some_and(|limit| limit <= loop_count);
+        let out_of_time =
+            loop_count > 0 && time_budget.is_some_and(|budget| budget <= start.elapsed());
+        if out_of_loops || out_of_time {
+            // We have looped beyoond the limit or run out of time, so we will
+            // defer anything that remains in the roster and stop looping from
+            // here.
+            let mut telemetry = world.get_resource_or_insert_with(FlushTelemetry::default);
+            if out_of_time {
+                telemetry.time_budget_exceeded += 1;
+            }
+            if out_of_loops {
+                telemetry.loop_limit_reached += 1;
+                if let Some(tracker) = tracker.take() {
+                    tracker.report(cancel_livelocked_sessions, world, &mut roster);
+                    // Deliver any cancellations right away so the loop does
+                    // not get another chance to run in the next flush.
+                    garbage_cleanup(world, &mut roster);
+                }
+            }
+
+            deferred = roster.len();
             world
                 .get_resource_or_insert_with(DeferredRoster::default)
                 .append(&mut roster);
@@ -115,25 +384,46 @@ fn flush_impulses_impl(
             garbage_cleanup(world, &mut roster);
         }
 
-        while let Some(source) = roster.queue.pop_front() {
-            execute_operation(OperationRequest {
-                source,
-                world,
-                roster: &mut roster,
-            });
+        while let Some(source) = pop_next_operation(&mut roster) {
+            if hold_for_debugger(source, world) {
+                continue;
+            }
+
+            if let Some(tracker) = &mut tracker {
+                tracker.record(source, world, &mut roster);
+            }
+
+            execute_instrumented_operation(
+                OperationRequest {
+                    source,
+                    world,
+                    roster: &mut roster,
+                },
+                instrumentation,
+            );
             garbage_cleanup(world, &mut roster);
+            if let Some(tracker) = &mut tracker {
+                tracker.record_fed(source, &mut roster);
+            }
             loop_count += 1;
             if flush_loop_limit.is_some_and(|limit| limit < loop_count) {
                 break;
             }
+
+            if time_budget.is_some_and(|budget| budget <= start.elapsed()) {
+                break;
+            }
         }
 
         while let Some(source) = roster.awake.pop_front() {
-            awaken_task(OperationRequest {
-                source,
-                world,
-                roster: &mut roster,
-            });
+            awaken_instrumented_task(
+                OperationRequest {
+                    source,
+                    world,
+                    roster: &mut roster,
+                },
+                instrumentation,
+            );
             garbage_cleanup(world, &mut roster);
         }
 
@@ -144,6 +434,10 @@ fn flush_impulses_impl(
             &mut roster,
         );
     }
+
+    world
+        .get_resource_or_insert_with(FlushTelemetry::default)
+        .record(start.elapsed(), deferred);
 }
 
 fn garbage_cleanup(world: &mut World, roster: &mut OperationRoster) {
@@ -239,7 +533,7 @@ fn collect_from_channels(
         dropped_targets.push(dropped_target);
     }
 
-    for target in drop_targets {
+    for target in dropped_targets {
         drop_target(target, world, roster, false);
     }
 
@@ -250,16 +544,16 @@ fn collect_from_channels(
     }) = roster.disposed.pop()
     {
         let Some(validate) = world.get::<ValidateScopeReachability>(source) else {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .miscellaneous
-                .push(MiscellaneousFailure {
+            report_unhandled_error(
+                MiscellaneousFailure {
                     error: Arc::new(anyhow!(
                         "Scope {source:?} for disposal notification does not \
                         have validation component",
                     )),
                     backtrace: Some(Backtrace::new()),
-                });
+                },
+                world,
+            );
             continue;
         };
 
@@ -272,15 +566,15 @@ fn collect_from_channels(
             roster,
         };
         if let Err(OperationError::Broken(backtrace)) = validate(req) {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .miscellaneous
-                .push(MiscellaneousFailure {
+            report_unhandled_error(
+                MiscellaneousFailure {
                     error: Arc::new(anyhow!(
                         "Scope {source:?} broken while validating a disposal"
                     )),
                     backtrace,
-                });
+                },
+                world,
+            );
         }
     }
 
@@ -343,13 +637,13 @@ fn drop_target(target: Entity, world: &mut World, roster: &mut OperationRoster,
     }
 
     if unused {
-        world
-            .get_resource_or_insert_with(UnhandledErrors::default)
-            .unused_targets
-            .push(UnusedTargetDrop {
+        report_unhandled_error(
+            UnusedTargetDrop {
                 unused_target: target,
                 dropped_impulses,
-            });
+            },
+            world,
+        );
     }
 }
 
@@ -357,3 +651,321 @@ fn drop_target(target: Entity, world: &mut World, roster: &mut OperationRoster,
 /// where the regular roster is not available.
 #[derive(Resource, Default, Deref, DerefMut)]
 pub(crate) struct DeferredRoster(pub OperationRoster);
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, CancellationCause, FlushParameters, FlushTelemetry};
+    use bevy_app::{First, PostUpdate, PreUpdate};
+    use bevy_ecs::{
+        prelude::apply_deferred,
+        schedule::{IntoSystemConfigs, SystemSet},
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Resource)]
+    struct Requester {
+        workflow: Service<u32, u32>,
+        promise: Option<Promise<u32>>,
+    }
+
+    fn make_request(mut requester: ResMut<Requester>, mut commands: Commands) {
+        if requester.promise.is_none() {
+            let workflow = requester.workflow;
+            requester.promise = Some(commands.request(1_u32, workflow).take_response());
+        }
+    }
+
+    fn app_with_requester(plugin: ImpulsePlugin) -> App {
+        let mut app = App::new();
+        app.add_plugins(plugin);
+        let workflow = app.world.command(|commands| {
+            commands.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+                scope
+                    .input
+                    .chain(builder)
+                    .map_block(|value: u32| value + 1)
+                    .connect(scope.terminate);
+            })
+        });
+        app.insert_resource(Requester {
+            workflow,
+            promise: None,
+        });
+        app
+    }
+
+    fn take_response(app: &mut App) -> Option<u32> {
+        app.world
+            .resource_mut::<Requester>()
+            .promise
+            .as_mut()?
+            .take()
+            .available()
+    }
+
+    #[test]
+    fn test_flush_point_in_later_schedule() {
+        let mut app = app_with_requester(ImpulsePlugin::new(FlushPoint::new(PostUpdate)));
+        app.add_systems(Update, make_request);
+
+        // The request made during Update is delivered by the PostUpdate flush
+        // within the same frame.
+        app.update();
+        assert_eq!(take_response(&mut app), Some(2));
+    }
+
+    fn relay_response(mut requester: ResMut<Requester>, mut commands: Commands) {
+        let workflow = requester.workflow;
+        let Some(promise) = &mut requester.promise else {
+            return;
+        };
+        if let Some(value) = promise.take().available() {
+            requester.promise = Some(commands.request(value, workflow).take_response());
+        }
+    }
+
+    #[test]
+    fn test_multiple_flush_points() {
+        let mut app = app_with_requester(
+            ImpulsePlugin::new(FlushPoint::new(PreUpdate))
+                .with_flush_point(FlushPoint::new(PostUpdate)),
+        );
+        app.add_systems(First, make_request);
+        app.add_systems(Update, relay_response);
+
+        // The PreUpdate flush delivers the first response in time for it to be
+        // relayed during Update, and the PostUpdate flush delivers the relayed
+        // response, all within one frame.
+        app.update();
+        assert_eq!(take_response(&mut app), Some(3));
+    }
+
+    #[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+    struct Flush;
+
+    #[test]
+    fn test_flush_point_in_set() {
+        let mut app = app_with_requester(ImpulsePlugin::new(FlushPoint::new(Update).in_set(Flush)));
+        app.add_systems(Update, (make_request, apply_deferred).chain().before(Flush));
+
+        app.update();
+        assert_eq!(take_response(&mut app), Some(2));
+    }
+
+    #[test]
+    fn test_flush_time_budget() {
+        let mut context = TestingContext::minimal_plugins();
+        context
+            .app
+            .world
+            .get_resource_or_insert_with(FlushParameters::default)
+            .flush_time_budget = Some(Duration::ZERO);
+
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .map_block(|value: u32| value + 1)
+                .map_block(|value: u32| value * 2)
+                .map_block(|value: u32| value + 3)
+                .connect(scope.terminate);
+        });
+
+        let mut promise =
+            context.command(|commands| commands.request(1_u32, workflow).take_response());
+        context.run(1);
+        assert!(promise.peek().is_pending());
+
+        let telemetry = context.app.world.resource::<FlushTelemetry>();
+        assert!(telemetry.time_budget_exceeded > 0);
+        assert!(telemetry.total_deferred > 0);
+        assert_eq!(telemetry.loop_limit_reached, 0);
+
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some_and(|v| v == 7));
+        assert!(context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_dropped_promise_cancels_request() {
+        struct DropFlag(Arc<Mutex<bool>>);
+        impl Drop for DropFlag {
+            fn drop(&mut self) {
+                *self.0.lock().unwrap() = true;
+            }
+        }
+
+        let mut context = TestingContext::minimal_plugins();
+        let dropped = Arc::new(Mutex::new(false));
+        let service = {
+            let dropped = Arc::clone(&dropped);
+            context.command(|commands| {
+                commands.spawn_service(move |In(_): AsyncServiceInput<()>| {
+                    let flag = DropFlag(Arc::clone(&dropped));
+                    async move {
+                        let _flag = flag;
+                        futures::future::pending::<()>().await;
+                    }
+                })
+            })
+        };
+
+        let promise = context.command(|commands| commands.request((), service).take_response());
+        context.run(3);
+        assert!(!*dropped.lock().unwrap());
+
+        // Dropping the promise should drop the impulse chain, which stops the
+        // task of the service.
+        drop(promise);
+        for _ in 0..100 {
+            if *dropped.lock().unwrap() {
+                break;
+            }
+            context.run(1);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(*dropped.lock().unwrap());
+        context.run(3);
+        assert!(context.no_unhandled_errors());
+    }
+
+    fn spawn_endless_loop(context: &mut TestingContext) -> Service<u32, u32> {
+        context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            // The terminal is reachable, but the loop never lets the value
+            // get there.
+            let node = builder.create_map_block(|value: u32| value.checked_add(1).ok_or(value));
+            builder.connect(scope.input, node.input);
+            builder
+                .chain(node.output)
+                .branch_for_err(|chain| chain.connect(scope.terminate))
+                .connect(node.input);
+        })
+    }
+
+    #[test]
+    fn test_livelock_diagnostic() {
+        let mut context = TestingContext::minimal_plugins();
+        context.set_flush_loop_limit(Some(20));
+        let workflow = spawn_endless_loop(&mut context);
+
+        let mut promise =
+            context.command(|commands| commands.request(0_u32, workflow).take_response());
+        context.run(1);
+        assert!(promise.peek().is_pending());
+
+        let errors = context.get_unhandled_errors().unwrap();
+        assert_eq!(errors.livelocks.len(), 1);
+        let livelock = &errors.livelocks[0];
+        assert!(!livelock.cancelled);
+        assert_eq!(livelock.sessions.len(), 1);
+        assert!(livelock
+            .operations
+            .iter()
+            .any(|op| op.kind == "BlockingMap" && op.executions > 1));
+    }
+
+    #[test]
+    fn test_livelock_cancels_session() {
+        let mut context = TestingContext::minimal_plugins();
+        context.set_flush_loop_limit(Some(20));
+        context
+            .app
+            .world
+            .resource_mut::<FlushParameters>()
+            .cancel_livelocked_sessions = true;
+        let workflow = spawn_endless_loop(&mut context);
+
+        let mut promise =
+            context.command(|commands| commands.request(0_u32, workflow).take_response());
+        context.run_with_conditions(&mut promise, 5);
+        let cancellation = promise.peek().cancellation().cloned().unwrap();
+        assert!(matches!(
+            cancellation.cause.as_ref(),
+            CancellationCause::Livelock(livelock) if livelock.cancelled
+        ));
+
+        let mut errors = context.get_unhandled_errors().unwrap().clone();
+        assert_eq!(errors.livelocks.len(), 1);
+        errors.livelocks.clear();
+        assert!(errors.is_empty());
+
+        // The loop was broken, so later flushes have nothing left to do.
+        context.run(3);
+        assert_eq!(
+            context
+                .app
+                .world
+                .resource::<FlushTelemetry>()
+                .loop_limit_reached,
+            1
+        );
+    }
+
+    #[test]
+    fn test_spread_is_not_livelocked() {
+        let mut context = TestingContext::minimal_plugins();
+        context.set_flush_loop_limit(Some(20));
+        context
+            .app
+            .world
+            .resource_mut::<FlushParameters>()
+            .cancel_livelocked_sessions = true;
+        let workflow = context.spawn_io_workflow(|scope: Scope<Vec<u32>, Vec<u32>>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .spread()
+                .map_block(|value: u32| value * 2)
+                .collect_all::<64>()
+                .map_block(|values| values.into_vec())
+                .connect(scope.terminate);
+        });
+
+        // The map runs once for each spread item within one session, which is
+        // more than the loop limit, but none of its output returns to it.
+        let mut promise = context.command(|commands| {
+            commands
+                .request((0..50_u32).collect::<Vec<_>>(), workflow)
+                .take_response()
+        });
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        let values = promise.take().available().unwrap();
+        assert_eq!(values, (0..50_u32).map(|v| v * 2).collect::<Vec<_>>());
+
+        let telemetry = context.app.world.resource::<FlushTelemetry>();
+        assert!(telemetry.loop_limit_reached > 0);
+        assert!(context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_independent_sessions_are_not_livelocked() {
+        let mut context = TestingContext::minimal_plugins();
+        context.set_flush_loop_limit(Some(4));
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .map_block(|value: u32| value + 1)
+                .map_block(|value: u32| value * 2)
+                .map_block(|value: u32| value + 3)
+                .connect(scope.terminate);
+        });
+
+        // Each session runs every operation once, so the operations repeat
+        // across sessions without looping within any of them.
+        let mut first =
+            context.command(|commands| commands.request(1_u32, workflow).take_response());
+        let mut second =
+            context.command(|commands| commands.request(2_u32, workflow).take_response());
+        context.run(1);
+
+        context.run_with_conditions(&mut first, Duration::from_secs(2));
+        context.run_with_conditions(&mut second, Duration::from_secs(2));
+        assert!(first.take().available().is_some_and(|v| v == 7));
+        assert!(second.take().available().is_some_and(|v| v == 9));
+
+        let telemetry = context.app.world.resource::<FlushTelemetry>();
+        assert!(telemetry.loop_limit_reached > 0);
+        assert!(context.no_unhandled_errors());
+    }
+}
@@ -15,14 +15,15 @@
  *
 */
 
-use bevy_ecs::prelude::{Bundle, Commands, Component, Entity, Event};
+use bevy_ecs::prelude::{Bundle, Commands, Component, Entity, Event, World};
 use bevy_hierarchy::BuildChildren;
 
 use std::future::Future;
 
 use crate::{
-    AsMapOnce, Cancellable, IntoAsyncMapOnce, IntoBlockingMapOnce, Promise, ProvideOnce, Sendish,
-    StreamPack, StreamTargetMap, UnusedTarget,
+    set_session_priority, AsMapOnce, Cancellable, DeferredRoster, IntoAsyncMapOnce,
+    IntoBlockingMapOnce, Priority, Promise, ProvideOnce, Sendish, StreamPack, StreamTargetMap,
+    UnusedTarget,
 };
 
 mod detach;
@@ -54,6 +55,7 @@ pub(crate) use taken::*;
 
 /// Impulses can be chained as a simple sequence of [providers](crate::Provider).
 pub struct Impulse<'w, 's, 'a, Response, Streams> {
+    pub(crate) session: Entity,
     pub(crate) source: Entity,
     pub(crate) target: Entity,
     pub(crate) commands: &'a mut Commands<'w, 's>,
@@ -85,6 +87,22 @@ where
         self
     }
 
+    /// Run every operation of this impulse chain, including any workflows that
+    /// it uses, with a [`Priority`]. Operations of higher priority sessions run
+    /// before operations of lower priority sessions whenever both are ready.
+    pub fn with_priority(self, priority: Priority) -> Impulse<'w, 's, 'a, Response, Streams> {
+        let session = self.session;
+        self.commands.add(move |world: &mut World| {
+            set_session_priority(session, priority, world);
+            // The input of the first operation of the chain was given when the
+            // request was made, before this priority was known.
+            world
+                .get_resource_or_insert_with(DeferredRoster::default)
+                .requeue_with_priority(session, priority);
+        });
+        self
+    }
+
     /// Take the data that comes out of the request, including both the response
     /// and the streams.
     #[must_use]
@@ -135,6 +153,7 @@ where
             .set_parent(target);
         provider.connect(None, source, target, self.commands);
         Impulse {
+            session: self.session,
             source,
             target,
             commands: self.commands,
@@ -232,6 +251,7 @@ where
             .insert((stream_targets, map));
 
         Impulse {
+            session: self.session,
             source: self.source,
             target: self.target,
             commands: self.commands,
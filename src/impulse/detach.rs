@@ -26,7 +26,7 @@ use backtrace::Backtrace;
 
 use std::sync::Arc;
 
-use crate::{MiscellaneousFailure, UnhandledErrors, UnusedTarget};
+use crate::{report_unhandled_error, MiscellaneousFailure, UnusedTarget};
 
 #[derive(Component, Default)]
 pub(crate) struct Detached(bool);
@@ -64,9 +64,6 @@ impl Command for Detach {
             error: Arc::new(anyhow!("Unable to detach target {:?}", self.target)),
             backtrace: Some(backtrace),
         };
-        world
-            .get_resource_or_insert_with(UnhandledErrors::default)
-            .miscellaneous
-            .push(failure);
+        report_unhandled_error(failure, world);
     }
 }
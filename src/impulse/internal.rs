@@ -34,9 +34,10 @@ use anyhow::anyhow;
 use std::sync::Arc;
 
 use crate::{
-    Broken, Cancel, CancelFailure, Cancellable, ManageCancellation, MiscellaneousFailure,
-    OperationCancel, OperationError, OperationExecuteStorage, OperationRequest, OperationResult,
-    OperationSetup, SetupFailure, SingleTargetStorage, UnhandledErrors, UnusedTarget,
+    report_unhandled_error, Broken, Cancel, CancelFailure, Cancellable, ManageCancellation,
+    MiscellaneousFailure, OperationCancel, OperationError, OperationExecuteStorage,
+    OperationRequest, OperationResult, OperationSetup, SetupFailure, SingleTargetStorage,
+    UnusedTarget,
 };
 
 pub(crate) trait Impulsive {
@@ -64,13 +65,13 @@ impl<I: Impulsive + 'static + Sync + Send> Command for AddImpulse<I> {
             source: self.source,
             world,
         }) {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .setup
-                .push(SetupFailure {
+            report_unhandled_error(
+                SetupFailure {
                     broken_node: self.source,
                     error,
-                });
+                },
+                world,
+            );
         }
         world
             .entity_mut(self.source)
@@ -105,10 +106,8 @@ fn perform_impulse<I: Impulsive>(
             if let Some(mut source_mut) = world.get_entity_mut(source) {
                 source_mut.emit_broken(backtrace, roster);
             } else {
-                world
-                    .get_resource_or_insert_with(UnhandledErrors::default)
-                    .cancellations
-                    .push(CancelFailure {
+                report_unhandled_error(
+                    CancelFailure {
                         error: OperationError::Broken(Some(Backtrace::new())),
                         cancel: Cancel {
                             origin: source,
@@ -120,7 +119,9 @@ fn perform_impulse<I: Impulsive>(
                             }
                             .into(),
                         },
-                    });
+                    },
+                    world,
+                );
             }
         }
     }
@@ -154,13 +155,13 @@ pub(crate) fn cancel_impulse(
                 // Do nothing
             }
             Err(OperationError::Broken(backtrace)) => {
-                world
-                    .get_resource_or_insert_with(UnhandledErrors::default)
-                    .cancellations
-                    .push(CancelFailure {
+                report_unhandled_error(
+                    CancelFailure {
                         error: OperationError::Broken(backtrace),
                         cancel,
-                    });
+                    },
+                    world,
+                );
             }
         }
     }
@@ -235,15 +236,15 @@ pub(crate) fn add_lifecycle_dependency(source: Entity, target: Entity, world: &m
     } else {
         // The target is already despawned
         if let Err(err) = sender.send(source) {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .miscellaneous
-                .push(MiscellaneousFailure {
+            report_unhandled_error(
+                MiscellaneousFailure {
                     error: Arc::new(anyhow!(
                         "Failed to notify that a target is already despawned: {err}"
                     )),
                     backtrace: Some(Backtrace::new()),
-                })
+                },
+                world,
+            )
         }
     }
 }
@@ -22,8 +22,8 @@ use std::future::Future;
 use crate::{
     async_execution::{spawn_task, task_cancel_sender},
     make_stream_buffers_from_world, ActiveTasksStorage, AsyncMap, BlockingMap, CallAsyncMapOnce,
-    CallBlockingMapOnce, Channel, ChannelQueue, Impulsive, Input, InputBundle, ManageInput,
-    OperateTask, OperationRequest, OperationResult, OperationSetup, OrBroken, Sendish,
+    CallBlockingMapOnce, CancellationToken, Channel, ChannelQueue, Impulsive, Input, InputBundle,
+    ManageInput, OperateTask, OperationRequest, OperationResult, OperationSetup, OrBroken, Sendish,
     SingleTargetStorage, StreamPack, UnusedStreams,
 };
 
@@ -203,6 +203,7 @@ where
         let channel = Channel::new(source, session, sender.clone());
         let streams = channel.for_streams::<Streams>(world)?;
 
+        let cancellation_token = CancellationToken::new();
         let task = spawn_task(
             f.call(AsyncMap {
                 request,
@@ -210,6 +211,7 @@ where
                 channel,
                 source,
                 session,
+                cancellation_token: cancellation_token.clone(),
             }),
             world,
         );
@@ -223,6 +225,7 @@ where
             target,
             task,
             cancel_sender,
+            cancellation_token,
             None,
             sender,
         )
@@ -25,10 +25,13 @@ use smallvec::SmallVec;
 
 use backtrace::Backtrace;
 
+use std::any::Any;
+
 use crate::{
-    Broken, BufferStorage, Cancel, Cancellation, CancellationCause, DeferredRoster, Detached,
-    MiscellaneousFailure, OperationError, OperationRoster, OrBroken, SessionStatus,
-    UnhandledErrors, UnusedTarget,
+    input_insertion_index, queue_priority, record_coverage, release_cleaned_session,
+    report_unhandled_error, Broken, BufferStorage, Cancel, Cancellation, CancellationCause,
+    DeferredRoster, Detached, MiscellaneousFailure, OperationError, OperationRoster, OrBroken,
+    SessionStatus, UnusedTarget, WorkflowDebugger,
 };
 
 /// This contains data that has been provided as input into an operation, along
@@ -71,17 +74,115 @@ impl<T> Default for InputStorage<T> {
 }
 
 /// Used to keep track of the expected input type for an operation
-#[derive(Component)]
+#[derive(Component, Clone, Copy)]
 pub(crate) struct InputTypeIndicator {
     pub(crate) name: &'static str,
+    peek_session: fn(&EntityRef) -> Option<Entity>,
+    has_session: fn(&EntityRef, Entity) -> bool,
+    sessions: fn(&EntityRef) -> SmallVec<[Entity; 16]>,
+    prioritize: fn(&mut EntityWorldMut, usize),
+    peek_input: fn(Entity, Entity, &World) -> Option<&dyn Any>,
 }
 
 impl InputTypeIndicator {
-    fn new<T>() -> Self {
+    fn new<T: 'static + Send + Sync>() -> Self {
         Self {
             name: std::any::type_name::<T>(),
+            peek_session: peek_session::<T>,
+            has_session: has_session::<T>,
+            sessions: sessions::<T>,
+            prioritize: prioritize::<T>,
+            peek_input: peek_input::<T>,
         }
     }
+
+    /// Get the session of the input that the operation will take next, if
+    /// there is one.
+    pub(crate) fn peek_session(&self, source: &EntityRef) -> Option<Entity> {
+        (self.peek_session)(source)
+    }
+
+    /// Check whether the operation is holding any input for a session.
+    pub(crate) fn has_session(&self, source: &EntityRef, session: Entity) -> bool {
+        (self.has_session)(source, session)
+    }
+
+    /// Get the sessions of every input that the operation is holding, starting
+    /// with the input that it will take next.
+    pub(crate) fn sessions(&self, source: &EntityRef) -> SmallVec<[Entity; 16]> {
+        (self.sessions)(source)
+    }
+
+    /// Move an input so that the operation will take it next. The index counts
+    /// from the input that would otherwise be taken next, in the same order as
+    /// [`Self::sessions`].
+    pub(crate) fn prioritize(&self, source: &mut EntityWorldMut, index: usize) {
+        (self.prioritize)(source, index)
+    }
+
+    /// Get the oldest input that the operation is holding for a session.
+    pub(crate) fn peek_input<'w>(
+        &self,
+        source: Entity,
+        session: Entity,
+        world: &'w World,
+    ) -> Option<&'w dyn Any> {
+        (self.peek_input)(source, session, world)
+    }
+}
+
+fn peek_input<T: 'static + Send + Sync>(
+    source: Entity,
+    session: Entity,
+    world: &World,
+) -> Option<&dyn Any> {
+    world
+        .get::<InputStorage<T>>(source)?
+        .reverse_queue
+        .iter()
+        .rev()
+        .find(|input| input.session == session)
+        .map(|input| &input.data as &dyn Any)
+}
+
+fn sessions<T: 'static + Send + Sync>(source: &EntityRef) -> SmallVec<[Entity; 16]> {
+    source
+        .get::<InputStorage<T>>()
+        .map(|storage| {
+            storage
+                .reverse_queue
+                .iter()
+                .rev()
+                .map(|input| input.session)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn prioritize<T: 'static + Send + Sync>(source: &mut EntityWorldMut, index: usize) {
+    let Some(mut storage) = source.get_mut::<InputStorage<T>>() else {
+        return;
+    };
+    let queue = &mut storage.reverse_queue;
+    let Some(position) = queue.len().checked_sub(index + 1) else {
+        return;
+    };
+    let input = queue.remove(position);
+    queue.push(input);
+}
+
+fn has_session<T: 'static + Send + Sync>(source: &EntityRef, session: Entity) -> bool {
+    source
+        .get::<InputStorage<T>>()
+        .is_some_and(|storage| storage.contains_session(session))
+}
+
+fn peek_session<T: 'static + Send + Sync>(source: &EntityRef) -> Option<Entity> {
+    source
+        .get::<InputStorage<T>>()?
+        .reverse_queue
+        .last()
+        .map(|input| input.session)
 }
 
 #[derive(Bundle)]
@@ -169,7 +270,8 @@ impl<'w> ManageInput for EntityWorldMut<'w> {
         roster: &mut OperationRoster,
     ) -> Result<(), OperationError> {
         if unsafe { self.sneak_input(session, data, true, roster)? } {
-            roster.queue(self.id());
+            let priority = queue_priority(session, self.world());
+            roster.queue_with_priority(self.id(), priority);
         }
         Ok(())
     }
@@ -209,8 +311,17 @@ impl<'w> ManageInput for EntityWorldMut<'w> {
             }
         }
 
-        if let Some(mut storage) = self.get_mut::<InputStorage<T>>() {
-            storage.reverse_queue.insert(0, Input { session, data });
+        #[cfg(feature = "diagram")]
+        if self.contains::<InputStorage<T>>() {
+            crate::diagram::record_handoff(self, session, &data);
+        }
+
+        if let Some(storage) = self.get::<InputStorage<T>>() {
+            let sessions = storage.reverse_queue.iter().map(|input| input.session);
+            let index = input_insertion_index(session, sessions, self.world());
+            let mut storage = self.get_mut::<InputStorage<T>>().or_broken()?;
+            storage.reverse_queue.insert(index, Input { session, data });
+            record_coverage(self);
         } else if !self.contains::<UnusedTarget>() {
             let id = self.id();
             if let Some(detached) = self.get::<Detached>() {
@@ -236,10 +347,8 @@ impl<'w> ManageInput for EntityWorldMut<'w> {
             // have the correct input storage type. This indicates a bug in
             // bevy_impulse itself, since the API should ensure that connection
             // mismatches are impossible.
-            self.world_mut()
-                .get_resource_or_insert_with(|| UnhandledErrors::default())
-                .miscellaneous
-                .push(MiscellaneousFailure {
+            report_unhandled_error(
+                MiscellaneousFailure {
                     error: std::sync::Arc::new(anyhow::anyhow!(
                         "Incorrect input type for operation [{:?}]: received [{}], expected [{}]",
                         id,
@@ -247,7 +356,9 @@ impl<'w> ManageInput for EntityWorldMut<'w> {
                         expected.unwrap_or("<null>"),
                     )),
                     backtrace: Some(Backtrace::new()),
-                });
+                },
+                self.world_mut(),
+            );
             None.or_broken()?;
         }
         Ok(true)
@@ -265,6 +376,11 @@ impl<'w> ManageInput for EntityWorldMut<'w> {
     }
 
     fn cleanup_inputs<T: 'static + Send + Sync>(&mut self, session: Entity) {
+        if self.world().contains_resource::<WorkflowDebugger>() {
+            let source = self.id();
+            self.world_scope(|world| release_cleaned_session(source, session, world));
+        }
+
         if self.contains::<BufferStorage<T>>() {
             // Buffers are handled in a special way because the data of some
             // buffers will be used during cancellation. Therefore we do not
@@ -343,9 +459,10 @@ impl<T: 'static + Send + Sync> Command for InputCommand<T> {
                     },
                 );
 
+                let priority = queue_priority(self.session, world);
                 world
                     .get_resource_or_insert_with(DeferredRoster::default)
-                    .queue(self.target);
+                    .queue_with_priority(self.target, priority);
             }
             None => {
                 let cause = CancellationCause::Broken(Broken {
@@ -0,0 +1,285 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use bevy_ecs::{
+    prelude::{Entity, World},
+    system::SystemParam,
+};
+
+use crate::{
+    ActiveTasksStorage, AnyBufferInterfaceStorage, AnyBufferKey, AnyBufferView, BufferKeyTag,
+    GetBufferedSessionsFn, InputTypeIndicator, OperationLabel, ScopeContents, ScopedSessionStorage,
+    Service, SessionStatus, WorkflowStorage,
+};
+
+/// Look into the sessions that are currently running inside of a workflow.
+///
+/// This can be used as a [`SystemParam`] or created directly from a [`World`]
+/// with [`WorkflowInspector::new`].
+///
+/// ```
+/// use bevy_impulse::{prelude::*, WorkflowInspector};
+///
+/// fn show_progress(
+///     In(workflow): In<Service<u32, u32>>,
+///     inspector: WorkflowInspector,
+/// ) {
+///     for session in inspector.sessions(workflow) {
+///         for operation in &session.operations {
+///             println!("{:?} is working on {:?}", operation.operation, session.session);
+///         }
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct WorkflowInspector<'w> {
+    pub(crate) world: &'w World,
+}
+
+impl<'w> WorkflowInspector<'w> {
+    pub fn new(world: &'w World) -> Self {
+        Self { world }
+    }
+
+    /// List the sessions of a workflow that are currently running or being
+    /// cleaned up. If `workflow` is not a workflow service, this will be empty.
+    pub fn sessions<Request, Response, Streams>(
+        &self,
+        workflow: Service<Request, Response, Streams>,
+    ) -> Vec<SessionInspection<'w>> {
+        let Some(storage) = self.world.get::<WorkflowStorage>(workflow.provider()) else {
+            return Vec::new();
+        };
+
+        self.inspect_scope(storage.scope(), None)
+    }
+
+    /// Inspect the sessions of a scope. If `parent_session` is given, only the
+    /// scoped sessions that belong to that parent session will be inspected.
+    fn inspect_scope(
+        &self,
+        scope: Entity,
+        parent_session: Option<Entity>,
+    ) -> Vec<SessionInspection<'w>> {
+        let Some(pairs) = self.world.get::<ScopedSessionStorage>(scope) else {
+            return Vec::new();
+        };
+
+        pairs
+            .iter()
+            .filter(|(parent, _)| parent_session.is_none_or(|p| p == *parent))
+            .filter_map(|(parent, session)| {
+                let status = *self.world.get::<SessionStatus>(session)?;
+                Some(self.inspect_session(scope, parent, session, status))
+            })
+            .collect()
+    }
+
+    fn inspect_session(
+        &self,
+        scope: Entity,
+        parent_session: Entity,
+        session: Entity,
+        status: SessionStatus,
+    ) -> SessionInspection<'w> {
+        let mut inspection = SessionInspection {
+            session,
+            parent_session,
+            scope,
+            status,
+            operations: Vec::new(),
+            tasks: Vec::new(),
+            buffers: Vec::new(),
+            scopes: Vec::new(),
+        };
+
+        let nodes = self
+            .world
+            .get::<ScopeContents>(scope)
+            .map(|contents| contents.nodes().as_slice())
+            .unwrap_or(&[]);
+
+        for node in nodes {
+            let Some(node_ref) = self.world.get_entity(*node) else {
+                continue;
+            };
+            let label = node_ref.get::<OperationLabel>();
+
+            if node_ref
+                .get::<InputTypeIndicator>()
+                .is_some_and(|indicator| indicator.has_session(&node_ref, session))
+            {
+                inspection.operations.push(InspectedOperation {
+                    operation: *node,
+                    label,
+                });
+            }
+
+            if let Some(tasks) = node_ref.get::<ActiveTasksStorage>() {
+                inspection
+                    .tasks
+                    .extend(tasks.tasks_of(session).map(|task| InspectedTask {
+                        task,
+                        operation: *node,
+                        label,
+                    }));
+            }
+
+            if let Some(buffer) = self.inspect_buffer(*node, session, label) {
+                inspection.buffers.push(buffer);
+            }
+
+            inspection
+                .scopes
+                .extend(self.inspect_scope(*node, Some(session)));
+        }
+
+        inspection
+    }
+
+    fn inspect_buffer(
+        &self,
+        buffer: Entity,
+        session: Entity,
+        label: Option<&'w OperationLabel>,
+    ) -> Option<InspectedBuffer<'w>> {
+        let get_sessions = self.world.get::<GetBufferedSessionsFn>(buffer)?;
+        if !(get_sessions.0)(buffer, self.world)
+            .ok()?
+            .contains(&session)
+        {
+            return None;
+        }
+
+        let interface = self.world.get::<AnyBufferInterfaceStorage>(buffer)?.0;
+        let key = AnyBufferKey {
+            tag: BufferKeyTag {
+                buffer,
+                session,
+                accessor: buffer,
+                lifecycle: None,
+            },
+            interface,
+        };
+
+        let view = interface.create_any_buffer_view(&key, self.world).ok()?;
+        Some(InspectedBuffer {
+            buffer,
+            label,
+            view,
+        })
+    }
+}
+
+/// The state of one session of a workflow or a scope.
+#[derive(Debug)]
+pub struct SessionInspection<'w> {
+    /// The session that is running inside of the scope.
+    pub session: Entity,
+    /// The session that started this one, e.g. the session of the request
+    /// that was sent to the workflow.
+    pub parent_session: Entity,
+    /// The scope that the session is running in.
+    pub scope: Entity,
+    /// Whether the session is still active or is being cleaned up.
+    pub status: SessionStatus,
+    /// Operations that are holding an input for this session.
+    pub operations: Vec<InspectedOperation<'w>>,
+    /// Async tasks that are still working on this session.
+    pub tasks: Vec<InspectedTask<'w>>,
+    /// Buffers that are holding data for this session.
+    pub buffers: Vec<InspectedBuffer<'w>>,
+    /// Sessions of scopes that are nested inside of this session.
+    pub scopes: Vec<SessionInspection<'w>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct InspectedOperation<'w> {
+    pub operation: Entity,
+    pub label: Option<&'w OperationLabel>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct InspectedTask<'w> {
+    /// The entity that is managing the task.
+    pub task: Entity,
+    /// The operation that spawned the task.
+    pub operation: Entity,
+    pub label: Option<&'w OperationLabel>,
+}
+
+pub struct InspectedBuffer<'w> {
+    pub buffer: Entity,
+    pub label: Option<&'w OperationLabel>,
+    /// View the contents of the buffer for this session.
+    pub view: AnyBufferView<'w>,
+}
+
+impl<'w> std::fmt::Debug for InspectedBuffer<'w> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InspectedBuffer")
+            .field("buffer", &self.buffer)
+            .field("label", &self.label)
+            .field("len", &self.view.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, SessionStatus, WorkflowInspector};
+
+    #[test]
+    fn test_inspect_pending_session() {
+        let mut context = TestingContext::minimal_plugins();
+        let delay = context.spawn_async_delayed_map(Duration::from_secs(10), |value: u32| value);
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            let (to_buffer, to_delay) = scope
+                .input
+                .chain(builder)
+                .map_block(|value: u32| (value, value))
+                .unzip();
+
+            let buffer = builder.create_buffer::<u32>(BufferSettings::keep_all());
+            builder.connect(to_buffer, buffer.input_slot());
+            to_delay.chain(builder).then(delay).connect(scope.terminate);
+        });
+
+        let mut promise =
+            context.command(|commands| commands.request(5_u32, workflow).take_response());
+        context.run(FlushConditions::new().with_update_count(2));
+        assert!(promise.peek().is_pending());
+
+        let inspector = WorkflowInspector::new(&context.app.world);
+        let sessions = inspector.sessions(workflow);
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert_eq!(session.status, SessionStatus::Active);
+        assert_eq!(session.tasks.len(), 1);
+        assert_eq!(session.buffers.len(), 1);
+        assert_eq!(session.buffers[0].view.len(), 1);
+        assert_eq!(
+            session.buffers[0]
+                .view
+                .oldest()
+                .unwrap()
+                .downcast_ref::<u32>(),
+            Some(&5)
+        );
+        assert!(session.scopes.is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use bevy_derive::Deref;
+use bevy_ecs::prelude::{Component, Entity, World};
+
+use smallvec::SmallVec;
+
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+
+/// A human-readable label for an operation in a workflow. Labels are used when
+/// displaying cancellations, disposals, and tracing spans so that an operation
+/// can be recognized by something more meaningful than its [`Entity`].
+///
+/// Use [`Chain::label`](crate::Chain::label) or [`Node::with_label`](crate::Node::with_label)
+/// to label operations while building a workflow. Operations that are created
+/// from a [diagram](crate::Diagram) are automatically labelled with their
+/// operation name, prefixed by the namespaces of any sections or scopes that
+/// they are nested inside of, e.g. `my_section:my_node`.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash, Deref)]
+pub struct OperationLabel(Arc<str>);
+
+impl OperationLabel {
+    pub fn new(label: impl Into<Arc<str>>) -> Self {
+        Self(label.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for OperationLabel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(&self.0)
+    }
+}
+
+/// The labels of the operations that are referred to by a [`Cancellation`](crate::Cancellation)
+/// or a [`Disposal`](crate::Disposal). These are captured while the world is
+/// still available so that the cancellation or disposal can be displayed later
+/// with labels, even if the operations have been despawned by then.
+///
+/// The labels are shared behind an [`Arc`] so they add very little to the size
+/// of a cancellation or disposal and are cheap to clone.
+#[derive(Clone, Debug, Default)]
+pub struct OperationLabels(Option<Arc<[(Entity, OperationLabel)]>>);
+
+impl OperationLabels {
+    /// Get the label of an operation, if it has one.
+    pub fn get(&self, operation: Entity) -> Option<&OperationLabel> {
+        self.iter()
+            .find(|(e, _)| *e == operation)
+            .map(|(_, label)| label)
+    }
+
+    /// Iterate over the labelled operations.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &OperationLabel)> {
+        self.0
+            .iter()
+            .flat_map(|labels| labels.iter().map(|(e, label)| (*e, label)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Display an operation, including its label if it has one.
+    pub fn entity(&self, operation: Entity) -> LabeledEntity<'_> {
+        LabeledEntity {
+            entity: operation,
+            label: self.get(operation),
+        }
+    }
+
+    /// Capture the labels of any operations that do not already have a label
+    /// in this set.
+    pub(crate) fn capture(&mut self, operations: impl IntoIterator<Item = Entity>, world: &World) {
+        let mut captured: SmallVec<[(Entity, OperationLabel); 4]> = SmallVec::new();
+        for operation in operations {
+            if self.get(operation).is_some() || captured.iter().any(|(e, _)| *e == operation) {
+                continue;
+            }
+
+            if let Some(label) = world.get::<OperationLabel>(operation) {
+                captured.push((operation, label.clone()));
+            }
+        }
+
+        if captured.is_empty() {
+            return;
+        }
+
+        let previous = self.0.iter().flat_map(|labels| labels.iter().cloned());
+        self.0 = Some(previous.chain(captured).collect());
+    }
+}
+
+/// Displays an [`Entity`] along with the label of its operation, e.g.
+/// `5v1 ("parse_request")`.
+#[derive(Clone, Copy, Debug)]
+pub struct LabeledEntity<'a> {
+    pub entity: Entity,
+    pub label: Option<&'a OperationLabel>,
+}
+
+impl<'a> Display for LabeledEntity<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.label {
+            Some(label) => write!(f, "{:?} (\"{label}\")", self.entity),
+            None => write!(f, "{:?}", self.entity),
+        }
+    }
+}
+
+/// Adapter for implementing [`Display`] with a closure.
+pub(crate) struct DisplayFn<F>(pub(crate) F);
+
+impl<F: Fn(&mut Formatter<'_>) -> FmtResult> Display for DisplayFn<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        (self.0)(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        prelude::*, testing::*, OperationError, OperationLabel, OperationLabels, PromiseState,
+    };
+    use bevy_ecs::prelude::World;
+
+    #[test]
+    fn test_cancellation_display_includes_label() {
+        let mut context = TestingContext::minimal_plugins();
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .map_block(|_: u32| Err::<u32, _>(std::fmt::Error))
+                .label("reject_everything")
+                .cancel_on_err()
+                .connect(scope.terminate);
+        });
+
+        let mut promise =
+            context.command(|commands| commands.request(5_u32, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        let PromiseState::Cancelled(cancellation) = promise.take() else {
+            panic!("workflow should have been cancelled");
+        };
+
+        let message = cancellation
+            .cause
+            .display_with(cancellation.labels())
+            .to_string();
+        assert!(message.contains("\"reject_everything\""), "{message}");
+        assert!(cancellation
+            .cause
+            .to_string()
+            .starts_with("filtered at operation"));
+    }
+
+    #[test]
+    fn test_operation_error_display_includes_label() {
+        let mut world = World::new();
+        let labeled = world.spawn(OperationLabel::new("parse_request")).id();
+        let unlabeled = world.spawn(()).id();
+        let mut labels = OperationLabels::default();
+        labels.capture([labeled, unlabeled], &world);
+
+        let message = OperationError::broken_here()
+            .display_with(labeled, &labels)
+            .to_string();
+        assert_eq!(
+            message,
+            format!("operation {labeled:?} (\"parse_request\") is broken")
+        );
+
+        let message = OperationError::NotReady
+            .display_with(unlabeled, &labels)
+            .to_string();
+        assert_eq!(message, format!("operation {unlabeled:?} is not ready"));
+    }
+
+    #[test]
+    fn test_node_with_label() {
+        let mut context = TestingContext::minimal_plugins();
+        let mut node_id = None;
+        context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            let node = builder
+                .create_map_block(|value: u32| value + 1)
+                .with_label("increment", builder);
+            node_id = Some(node.input.id());
+            builder.connect(scope.input, node.input);
+            builder.connect(node.output, scope.terminate);
+        });
+
+        let label = context
+            .app
+            .world
+            .get::<OperationLabel>(node_id.unwrap())
+            .unwrap();
+        assert_eq!(label.as_str(), "increment");
+    }
+
+    #[test]
+    fn test_chain_label_then_connect() {
+        let mut context = TestingContext::minimal_plugins();
+        let mut node_ids = None;
+        context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            let unlabeled = builder.create_map_block(|value: u32| value + 1);
+            let labeled = builder
+                .create_map_block(|value: u32| value * 2)
+                .with_label("double", builder);
+            node_ids = Some((unlabeled.input.id(), labeled.input.id()));
+
+            scope
+                .input
+                .chain(builder)
+                .label("increment")
+                .connect(unlabeled.input);
+            builder
+                .chain(unlabeled.output)
+                .label("ignored")
+                .connect(labeled.input);
+            builder.connect(labeled.output, scope.terminate);
+        });
+
+        // The label is carried over to the operation that the chain was
+        // connected to, but it does not replace a label that was already set.
+        let (unlabeled, labeled) = node_ids.unwrap();
+        let world = &context.app.world;
+        assert_eq!(
+            world.get::<OperationLabel>(unlabeled).unwrap().as_str(),
+            "increment"
+        );
+        assert_eq!(
+            world.get::<OperationLabel>(labeled).unwrap().as_str(),
+            "double"
+        );
+    }
+}
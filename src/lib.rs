@@ -67,7 +67,9 @@
 //! result.
 
 mod async_execution;
-pub use async_execution::Sendish;
+#[cfg(feature = "tokio_async")]
+pub use async_execution::TokioExecutor;
+pub use async_execution::{AsyncExecutor, AsyncTaskExecutor, ExecutorJob, Sendish};
 
 pub mod buffer;
 pub use buffer::*;
@@ -83,12 +85,18 @@ pub use callback::*;
 pub mod cancel;
 pub use cancel::*;
 
+pub mod cancellation_token;
+pub use cancellation_token::*;
+
 pub mod chain;
 pub use chain::*;
 
 pub mod channel;
 pub use channel::*;
 
+pub mod coverage;
+pub use coverage::*;
+
 #[cfg(feature = "diagram")]
 pub mod diagram;
 #[cfg(feature = "diagram")]
@@ -112,6 +120,18 @@ pub use impulse::*;
 pub mod input;
 pub use input::*;
 
+pub mod debug;
+pub use debug::*;
+
+pub mod inspect;
+pub use inspect::*;
+
+pub mod metrics;
+pub use metrics::*;
+
+pub mod label;
+pub use label::*;
+
 pub mod map;
 pub use map::*;
 
@@ -124,15 +144,24 @@ pub use node::*;
 pub mod operation;
 pub use operation::*;
 
+pub mod priority;
+pub use priority::*;
+
 pub mod promise;
 pub use promise::*;
 
 pub mod provider;
 pub use provider::*;
 
+pub mod remote;
+pub use remote::*;
+
 pub mod request;
 pub use request::*;
 
+pub mod runtime;
+pub use runtime::*;
+
 pub mod service;
 pub use service::*;
 
@@ -142,6 +171,12 @@ pub use stream::*;
 pub mod workflow;
 pub use workflow::*;
 
+mod despawned;
+pub(crate) use despawned::*;
+
+mod spans;
+pub(crate) use spans::*;
+
 pub mod testing;
 
 pub mod trim;
@@ -150,7 +185,12 @@ pub use trim::*;
 pub mod type_info;
 
 use bevy_app::prelude::{App, Plugin, Update};
-use bevy_ecs::prelude::{Entity, In};
+use bevy_ecs::{
+    prelude::{Entity, In},
+    schedule::{
+        InternedScheduleLabel, InternedSystemSet, IntoSystemConfigs, ScheduleLabel, SystemSet,
+    },
+};
 
 extern crate self as bevy_impulse;
 
@@ -222,11 +262,71 @@ pub struct AsyncService<Request, Streams: StreamPack = ()> {
     pub source: Entity,
     /// The unique session ID for the workflow
     pub session: Entity,
+    /// Lets the task find out when it is being cancelled, and optionally ask
+    /// for a grace period to clean up.
+    pub cancellation_token: CancellationToken,
 }
 
 /// Use this to reduce backet noise when you need `In<`[`AsyncService<R, S>`]`>`.
 pub type AsyncServiceInput<Request, Streams = ()> = In<AsyncService<Request, Streams>>;
 
+/// Use `ComputeService` to indicate that your system is a compute [`Service`].
+/// A compute service is for heavy CPU work, such as path planning, that should
+/// not stall the flush of the workflows.
+///
+/// The system itself runs with access to the world, just like a blocking
+/// service. It should take a snapshot of whatever data it needs and return a
+/// function that does the actual work using only that snapshot. The function
+/// will be run on the [`ComputeTaskPool`](bevy_tasks::ComputeTaskPool), or on
+/// the [`ComputeServicePool`] if that resource has been inserted. Its output is
+/// delivered as the response of the service.
+///
+/// Compute services accept the same delivery settings as async services, and
+/// their requests can be cancelled the same way.
+///
+/// ```
+/// use bevy_ecs::prelude::*;
+/// use bevy_impulse::prelude::*;
+///
+/// #[derive(Resource)]
+/// struct Map {
+///     obstacles: Vec<[f32; 2]>,
+/// }
+///
+/// fn plan_path(
+///     In(srv): ComputeServiceInput<[f32; 2]>,
+///     map: Res<Map>,
+/// ) -> impl FnOnce() -> Vec<[f32; 2]> {
+///     let obstacles = map.obstacles.clone();
+///     let goal = srv.request;
+///     move || {
+///         // ... search for a path around the obstacles ...
+///         let _ = obstacles;
+///         vec![goal]
+///     }
+/// }
+/// ```
+#[non_exhaustive]
+pub struct ComputeService<Request, Streams: StreamPack = ()> {
+    /// The input data of the request
+    pub request: Request,
+    /// Stream channels that can be moved into the compute function to send
+    /// stream information while it runs.
+    pub streams: Streams::StreamChannels,
+    /// The entity providing the service
+    pub provider: Entity,
+    /// The node in a workflow or impulse chain that asked for the service
+    pub source: Entity,
+    /// The unique session ID for the workflow
+    pub session: Entity,
+    /// Move this into the compute function so that it can check whether it
+    /// has been cancelled and stop early.
+    pub cancellation_token: CancellationToken,
+}
+
+/// Use this to reduce bracket noise when you need `In<`[`ComputeService<R, S>`]`>`.
+pub type ComputeServiceInput<Request, Streams = ()> = In<ComputeService<Request, Streams>>;
+
 /// Use `ContinuousService` to indicate that your system is a [`Service`] that
 /// runs incrementally inside of a schedule with each update of the Bevy ECS.
 pub struct ContinuousService<Request, Response, Streams: StreamPack = ()> {
@@ -281,6 +381,9 @@ pub struct AsyncCallback<Request, Streams: StreamPack = ()> {
     pub source: Entity,
     /// The unique session ID for the workflow
     pub session: Entity,
+    /// Lets the task find out when it is being cancelled, and optionally ask
+    /// for a grace period to clean up.
+    pub cancellation_token: CancellationToken,
 }
 
 /// Use this to reduce bracket noise when you need `In<`[`AsyncCallback<R, S>`]`>`.
@@ -322,20 +425,117 @@ pub struct AsyncMap<Request, Streams: StreamPack = ()> {
     pub source: Entity,
     /// The unique session ID for the workflow
     pub session: Entity,
+    /// Lets the task find out when it is being cancelled, and optionally ask
+    /// for a grace period to clean up.
+    pub cancellation_token: CancellationToken,
 }
 
-/// This plugin simply adds [`flush_impulses()`] to the [`Update`] schedule of your
-/// applicatation. For more fine-grained control you can call `flush_impulses`
-/// yourself and configure its relationship to other systems as you see fit.
+/// This plugin adds [`flush_impulses()`] to your application. By default the
+/// flush runs in the [`Update`] schedule, but you can choose other schedules,
+/// put the flush in a [`SystemSet`] so it can be ordered relative to your own
+/// systems, and add extra [flush points](FlushPoint) so that workflows can react
+/// within the same frame as the systems that they depend on.
 ///
-/// If you do not have at least one usage of `flush_impulses()` somewhere in
-/// your application then workflows will not work.
-#[derive(Default)]
-pub struct ImpulsePlugin {}
+/// ```
+/// use bevy_app::{PostUpdate, PreUpdate};
+/// use bevy_impulse::{FlushPoint, ImpulsePlugin};
+///
+/// let plugin = ImpulsePlugin::new(FlushPoint::new(PreUpdate))
+///     .with_flush_point(FlushPoint::new(PostUpdate));
+/// ```
+///
+/// # Ordering guarantees
+///
+/// Each flush point runs a complete flush: every operation that is ready when
+/// the flush begins will be executed, along with any operations that become
+/// ready while the flush is running, until the roster is empty or one of the
+/// [`FlushParameters`] limits is reached. Async tasks that finish while the
+/// flush is running are also processed before the flush returns.
+///
+/// Requests are issued through [`Commands`](bevy_ecs::prelude::Commands), so a
+/// flush can only see a request once the commands of the system that issued it
+/// have been applied. Bevy applies commands at the end of every schedule, so a
+/// flush point in a later schedule will always see requests made by systems in
+/// an earlier schedule. To react within the same schedule, order the flush
+/// after your system and after an [`apply_deferred`](bevy_ecs::prelude::apply_deferred)
+/// that follows your system, e.g. by putting the flush point in a system set.
+///
+/// If you do not use this plugin, you can call `flush_impulses` yourself and
+/// configure its relationship to other systems as you see fit. If you do not
+/// have at least one usage of `flush_impulses()` somewhere in your application
+/// then workflows will not work.
+pub struct ImpulsePlugin {
+    flush_points: Vec<FlushPoint>,
+}
+
+impl Default for ImpulsePlugin {
+    fn default() -> Self {
+        Self::new(FlushPoint::new(Update))
+    }
+}
+
+impl ImpulsePlugin {
+    /// Create a plugin that flushes impulses at one flush point.
+    pub fn new(flush_point: FlushPoint) -> Self {
+        Self {
+            flush_points: vec![flush_point],
+        }
+    }
+
+    /// Flush impulses at an additional point in the frame.
+    pub fn with_flush_point(mut self, flush_point: FlushPoint) -> Self {
+        self.flush_points.push(flush_point);
+        self
+    }
+
+    /// Iterate over the points where this plugin will flush impulses.
+    pub fn flush_points(&self) -> impl Iterator<Item = &FlushPoint> {
+        self.flush_points.iter()
+    }
+}
 
 impl Plugin for ImpulsePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, flush_impulses());
+        app.add_event::<UnhandledError>();
+        for point in &self.flush_points {
+            let flush = match point.set {
+                Some(set) => flush_impulses().in_set(set),
+                None => flush_impulses(),
+            };
+            app.add_systems(point.schedule, flush);
+        }
+    }
+}
+
+/// A point in the frame where [`ImpulsePlugin`] will run [`flush_impulses()`].
+#[derive(Clone, Copy, Debug)]
+pub struct FlushPoint {
+    schedule: InternedScheduleLabel,
+    set: Option<InternedSystemSet>,
+}
+
+impl FlushPoint {
+    /// Flush impulses in this schedule.
+    pub fn new(schedule: impl ScheduleLabel) -> Self {
+        Self {
+            schedule: schedule.intern(),
+            set: None,
+        }
+    }
+
+    /// Put the flush into a system set so that it can be ordered relative to
+    /// other systems in the schedule.
+    pub fn in_set(mut self, set: impl SystemSet) -> Self {
+        self.set = Some(set.intern());
+        self
+    }
+
+    pub fn schedule(&self) -> InternedScheduleLabel {
+        self.schedule
+    }
+
+    pub fn set(&self) -> Option<InternedSystemSet> {
+        self.set
     }
 }
 
@@ -382,13 +582,16 @@ pub mod prelude {
             DeliveryInstructions, DeliveryLabel, DeliveryLabelId, IntoAsyncService,
             IntoBlockingService, Service, ServiceDiscovery, SpawnServicesExt,
         },
-        stream::{DynamicallyNamedStream, NamedValue, Stream, StreamFilter, StreamOf, StreamPack},
+        stream::{
+            BackpressurePolicy, DynamicallyNamedStream, NamedValue, Stream, StreamFilter, StreamOf,
+            StreamPack,
+        },
         trim::{TrimBranch, TrimPoint},
         workflow::{DeliverySettings, Scope, ScopeSettings, SpawnWorkflowExt, WorkflowSettings},
         AsyncCallback, AsyncCallbackInput, AsyncMap, AsyncService, AsyncServiceInput,
         BlockingCallback, BlockingCallbackInput, BlockingMap, BlockingService,
-        BlockingServiceInput, ContinuousQuery, ContinuousService, ContinuousServiceInput,
-        ImpulseAppPlugin, ImpulsePlugin,
+        BlockingServiceInput, ComputeService, ComputeServiceInput, ContinuousQuery,
+        ContinuousService, ContinuousServiceInput, FlushPoint, ImpulseAppPlugin, ImpulsePlugin,
     };
 
     pub use bevy_ecs::prelude::In;
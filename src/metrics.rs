@@ -0,0 +1,580 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+//! Per-operation and per-service metrics.
+//!
+//! Add a [`MetricsPlugin`] to your app (or insert the [`WorkflowMetrics`]
+//! resource directly) to start collecting metrics. Nothing is measured while
+//! the resource is absent, so there is no overhead when metrics are not used.
+//!
+//! The metrics can be exported in the [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/)
+//! with [`WorkflowMetrics::to_prometheus`] or [`WorkflowMetrics::write_prometheus`].
+
+use bevy_app::{App, Last, Plugin};
+use bevy_ecs::prelude::{Entity, Resource, World};
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::Write as FmtWrite,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{operation_kind, remove_despawned_system, retain_spawned, OperationLabel};
+
+/// Adds the [`WorkflowMetrics`] resource so that metrics will be collected.
+/// The metrics of operations and services that have been despawned will be
+/// removed at the end of each update.
+#[derive(Default)]
+pub struct MetricsPlugin {}
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorkflowMetrics>().add_systems(
+            Last,
+            remove_despawned_system(WorkflowMetrics::remove_despawned),
+        );
+    }
+}
+
+/// Metrics that have been collected for the operations and services that have
+/// run while this resource was present in the world.
+///
+/// Entries are kept until their entity is despawned and
+/// [`WorkflowMetrics::remove_despawned`] is run, which [`MetricsPlugin`] does
+/// at the end of each update. If you insert this resource without the plugin,
+/// call that method yourself to keep one-off operations from accumulating.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct WorkflowMetrics {
+    operations: HashMap<Entity, OperationMetrics>,
+    services: HashMap<Entity, ServiceMetrics>,
+}
+
+/// Metrics for one operation in a workflow.
+#[derive(Default, Clone, Debug)]
+pub struct OperationMetrics {
+    /// The [label](OperationLabel) of the operation, if it has one.
+    pub label: Option<OperationLabel>,
+    /// The kind of operation, e.g. `BlockingMap`.
+    pub kind: &'static str,
+    /// How many times the operation has been executed.
+    pub executions: u64,
+    /// How many cancellations this operation has triggered.
+    pub cancellations: u64,
+    /// How many disposals this operation has emitted.
+    pub disposals: u64,
+    /// How long each execution of the operation took.
+    pub execution_time: DurationHistogram,
+    /// How long each async task that was spawned by this operation took, from
+    /// being spawned until it finished or was cancelled.
+    pub task_duration: DurationHistogram,
+    /// If the operation is a buffer, this is how many messages it was holding
+    /// for a session the last time a message was pushed into it.
+    pub buffer_occupancy: usize,
+    /// The highest [`Self::buffer_occupancy`] that has been seen.
+    pub peak_buffer_occupancy: usize,
+    /// How many stream messages sent by the async tasks of this operation
+    /// were discarded because of a [`BackpressurePolicy`](crate::BackpressurePolicy).
+    pub stream_drops: u64,
+}
+
+impl OperationMetrics {
+    /// The total time spent by this operation, including its async tasks.
+    pub fn total_time(&self) -> Duration {
+        self.execution_time.sum + self.task_duration.sum
+    }
+}
+
+/// Metrics for one service provider.
+#[derive(Default, Clone, Debug)]
+pub struct ServiceMetrics {
+    /// How many requests are waiting in the serial delivery queue of the
+    /// service. For services with parallel delivery this is the total across
+    /// all delivery labels.
+    pub queue_depth: usize,
+    /// The highest [`Self::queue_depth`] that has been seen.
+    pub peak_queue_depth: usize,
+}
+
+/// The upper bounds, in seconds, of the buckets in a [`DurationHistogram`].
+pub const DURATION_BUCKETS: [f64; 13] = [
+    0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0,
+];
+
+/// A histogram of durations with the fixed bucket bounds of [`DURATION_BUCKETS`].
+#[derive(Default, Clone, Debug)]
+pub struct DurationHistogram {
+    /// The number of samples in each bucket. These are not cumulative. The
+    /// last entry counts samples that were longer than every bucket bound.
+    pub buckets: [u64; DURATION_BUCKETS.len() + 1],
+    /// The total number of samples.
+    pub count: u64,
+    /// The sum of all samples.
+    pub sum: Duration,
+    /// The longest sample.
+    pub max: Duration,
+}
+
+impl DurationHistogram {
+    pub fn record(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum += duration;
+        self.max = self.max.max(duration);
+    }
+
+    /// The average of all samples.
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| {
+            let nanos = self.sum.as_nanos() / self.count as u128;
+            Duration::from_nanos(nanos as u64)
+        })
+    }
+}
+
+impl WorkflowMetrics {
+    /// Get the metrics of an operation.
+    pub fn operation(&self, operation: Entity) -> Option<&OperationMetrics> {
+        self.operations.get(&operation)
+    }
+
+    /// Iterate over the metrics of every operation that has been measured.
+    pub fn operations(&self) -> impl Iterator<Item = (Entity, &OperationMetrics)> {
+        self.operations.iter().map(|(e, m)| (*e, m))
+    }
+
+    /// Find an operation by its label.
+    pub fn labeled(&self, label: &str) -> Option<(Entity, &OperationMetrics)> {
+        self.operations()
+            .find(|(_, m)| m.label.as_ref().is_some_and(|l| l.as_str() == label))
+    }
+
+    /// Get the operations that have spent the most [total time](OperationMetrics::total_time),
+    /// starting with the slowest.
+    pub fn slowest(&self, count: usize) -> Vec<(Entity, &OperationMetrics)> {
+        let mut operations: Vec<_> = self.operations().collect();
+        operations.sort_by_key(|(_, m)| Reverse(m.total_time()));
+        operations.truncate(count);
+        operations
+    }
+
+    /// Get the metrics of a service provider.
+    pub fn service(&self, provider: Entity) -> Option<&ServiceMetrics> {
+        self.services.get(&provider)
+    }
+
+    /// Iterate over the metrics of every service that has been measured.
+    pub fn services(&self) -> impl Iterator<Item = (Entity, &ServiceMetrics)> {
+        self.services.iter().map(|(e, m)| (*e, m))
+    }
+
+    /// Forget all the metrics that have been collected so far.
+    pub fn clear(&mut self) {
+        self.operations.clear();
+        self.services.clear();
+    }
+
+    /// Forget the metrics of operations and services whose entities no longer
+    /// exist in the world.
+    pub fn remove_despawned(&mut self, world: &World) {
+        retain_spawned(&mut self.operations, world);
+        retain_spawned(&mut self.services, world);
+    }
+
+    /// Render the metrics in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut operations: Vec<_> = self.operations().collect();
+        operations.sort_by_key(|(e, _)| *e);
+        let mut services: Vec<_> = self.services().collect();
+        services.sort_by_key(|(e, _)| *e);
+
+        let counters: [CounterMetric; 4] = [
+            (
+                "executions_total",
+                "Number of times the operation was executed.",
+                |m| m.executions,
+            ),
+            (
+                "cancellations_total",
+                "Number of cancellations triggered by the operation.",
+                |m| m.cancellations,
+            ),
+            (
+                "disposals_total",
+                "Number of disposals emitted by the operation.",
+                |m| m.disposals,
+            ),
+            (
+                "stream_drops_total",
+                "Number of stream messages discarded because of backpressure.",
+                |m| m.stream_drops,
+            ),
+        ];
+        for (name, help, get) in counters {
+            header(&mut out, &format!("operation_{name}"), help, "counter");
+            for (operation, metrics) in &operations {
+                let labels = operation_labels(*operation, metrics);
+                let _ = writeln!(out, "{PREFIX}operation_{name}{{{labels}}} {}", get(metrics));
+            }
+        }
+
+        let histograms: [HistogramMetric; 2] = [
+            (
+                "execution_seconds",
+                "Time spent executing the operation.",
+                |m| &m.execution_time,
+            ),
+            (
+                "task_seconds",
+                "Time from spawning an async task until it finished.",
+                |m| &m.task_duration,
+            ),
+        ];
+        for (name, help, get) in histograms {
+            header(&mut out, &format!("operation_{name}"), help, "histogram");
+            for (operation, metrics) in &operations {
+                let histogram = get(metrics);
+                if histogram.count == 0 {
+                    continue;
+                }
+                let labels = operation_labels(*operation, metrics);
+                write_histogram(&mut out, &format!("operation_{name}"), &labels, histogram);
+            }
+        }
+
+        // Each metric family must be written as one contiguous group after
+        // its own header.
+        let buffer_gauges: [GaugeMetric<OperationMetrics>; 2] = [
+            (
+                "buffer_occupancy",
+                "Messages held by a buffer for a session when it last received one.",
+                |m| m.buffer_occupancy,
+            ),
+            (
+                "buffer_peak_occupancy",
+                "Most messages held by a buffer for a session.",
+                |m| m.peak_buffer_occupancy,
+            ),
+        ];
+        for (name, help, get) in buffer_gauges {
+            header(&mut out, name, help, "gauge");
+            for (operation, metrics) in &operations {
+                if metrics.peak_buffer_occupancy == 0 {
+                    continue;
+                }
+                let labels = operation_labels(*operation, metrics);
+                let _ = writeln!(out, "{PREFIX}{name}{{{labels}}} {}", get(metrics));
+            }
+        }
+
+        let service_gauges: [GaugeMetric<ServiceMetrics>; 2] = [
+            (
+                "service_queue_depth",
+                "Requests waiting in the delivery queue of a service.",
+                |m| m.queue_depth,
+            ),
+            (
+                "service_peak_queue_depth",
+                "Most requests that have waited in the delivery queue of a service.",
+                |m| m.peak_queue_depth,
+            ),
+        ];
+        for (name, help, get) in service_gauges {
+            header(&mut out, name, help, "gauge");
+            for (service, metrics) in &services {
+                let _ = writeln!(
+                    out,
+                    "{PREFIX}{name}{{service=\"{service:?}\"}} {}",
+                    get(metrics)
+                );
+            }
+        }
+
+        out
+    }
+
+    /// Write the metrics to a file in the Prometheus text exposition format.
+    /// The file will be overwritten if it already exists.
+    pub fn write_prometheus(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_prometheus())
+    }
+
+    fn operation_mut(&mut self, operation: Entity, world: &World) -> &mut OperationMetrics {
+        let metrics = self.operations.entry(operation).or_default();
+        if metrics.label.is_none() {
+            metrics.label = world.get::<OperationLabel>(operation).cloned();
+        }
+        if metrics.kind.is_empty() {
+            metrics.kind = operation_kind(operation, world);
+        }
+        metrics
+    }
+}
+
+const PREFIX: &str = "bevy_impulse_";
+
+/// The name, help text, and getter of a metric.
+type CounterMetric = (&'static str, &'static str, fn(&OperationMetrics) -> u64);
+type GaugeMetric<M> = (&'static str, &'static str, fn(&M) -> usize);
+type HistogramMetric = (
+    &'static str,
+    &'static str,
+    fn(&OperationMetrics) -> &DurationHistogram,
+);
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {PREFIX}{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}{name} {kind}");
+}
+
+fn operation_labels(operation: Entity, metrics: &OperationMetrics) -> String {
+    let mut labels = format!("operation=\"{operation:?}\",kind=\"{}\"", metrics.kind);
+    if let Some(label) = &metrics.label {
+        let _ = write!(labels, ",label=\"{}\"", escape(label.as_str()));
+    }
+    labels
+}
+
+fn write_histogram(out: &mut String, name: &str, labels: &str, histogram: &DurationHistogram) {
+    let mut cumulative = 0;
+    for (bound, count) in DURATION_BUCKETS.iter().zip(&histogram.buckets) {
+        cumulative += count;
+        let _ = writeln!(
+            out,
+            "{PREFIX}{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{PREFIX}{name}_bucket{{{labels},le=\"+Inf\"}} {}",
+        histogram.count
+    );
+    let _ = writeln!(
+        out,
+        "{PREFIX}{name}_sum{{{labels}}} {}",
+        histogram.sum.as_secs_f64()
+    );
+    let _ = writeln!(out, "{PREFIX}{name}_count{{{labels}}} {}", histogram.count);
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub(crate) fn record_execution(operation: Entity, timer: Option<Instant>, world: &mut World) {
+    let Some(started) = timer else {
+        return;
+    };
+    let elapsed = started.elapsed();
+    with_operation(operation, world, |metrics| {
+        metrics.executions += 1;
+        metrics.execution_time.record(elapsed);
+    });
+}
+
+pub(crate) fn record_cancellation(operation: Entity, world: &mut World) {
+    with_operation(operation, world, |metrics| metrics.cancellations += 1);
+}
+
+pub(crate) fn record_disposal(operation: Entity, world: &mut World) {
+    with_operation(operation, world, |metrics| metrics.disposals += 1);
+}
+
+pub(crate) fn record_task_duration(node: Entity, duration: Duration, world: &mut World) {
+    with_operation(node, world, |metrics| {
+        metrics.task_duration.record(duration)
+    });
+}
+
+pub(crate) fn record_buffer_occupancy(buffer: Entity, occupancy: usize, world: &mut World) {
+    with_operation(buffer, world, |metrics| {
+        metrics.buffer_occupancy = occupancy;
+        metrics.peak_buffer_occupancy = metrics.peak_buffer_occupancy.max(occupancy);
+    });
+}
+
+pub(crate) fn record_stream_drops(operation: Entity, drops: u64, world: &mut World) {
+    with_operation(operation, world, |metrics| metrics.stream_drops += drops);
+}
+
+pub(crate) fn record_queue_depth(provider: Entity, depth: usize, world: &mut World) {
+    let Some(mut metrics) = world.get_resource_mut::<WorkflowMetrics>() else {
+        return;
+    };
+    let metrics = metrics.services.entry(provider).or_default();
+    metrics.queue_depth = depth;
+    metrics.peak_queue_depth = metrics.peak_queue_depth.max(depth);
+}
+
+fn with_operation(operation: Entity, world: &mut World, f: impl FnOnce(&mut OperationMetrics)) {
+    if !world.contains_resource::<WorkflowMetrics>() {
+        return;
+    }
+
+    world.resource_scope::<WorkflowMetrics, _>(|world, mut metrics| {
+        f(metrics.operation_mut(operation, world));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, DurationHistogram, MetricsPlugin, WorkflowMetrics};
+
+    #[test]
+    fn test_metrics_find_slow_node() {
+        let mut context = TestingContext::minimal_plugins();
+        context.app.init_resource::<WorkflowMetrics>();
+        let delay = context.spawn_async_delayed_map(Duration::from_millis(10), |v: u32| v);
+
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .label("increment")
+                .map_block(|value: u32| value + 1)
+                .label("delay")
+                .then(delay)
+                .connect(scope.terminate);
+        });
+
+        for _ in 0..3 {
+            let mut promise =
+                context.command(|commands| commands.request(1_u32, workflow).take_response());
+            context.run_with_conditions(&mut promise, Duration::from_secs(2));
+            assert!(promise.take().available().is_some_and(|v| v == 2));
+        }
+
+        let metrics = context.app.world.resource::<WorkflowMetrics>();
+        let (_, increment) = metrics.labeled("increment").unwrap();
+        assert_eq!(increment.executions, 3);
+        assert_eq!(increment.kind, "BlockingMap");
+
+        let (delay_node, delay_metrics) = metrics.labeled("delay").unwrap();
+        assert_eq!(delay_metrics.task_duration.count, 3);
+        assert!(delay_metrics.task_duration.sum >= Duration::from_millis(30));
+        assert_eq!(metrics.slowest(1)[0].0, delay_node);
+
+        let text = metrics.to_prometheus();
+        assert!(text.contains("bevy_impulse_operation_executions_total{"));
+        assert!(text.contains("label=\"increment\"} 3"), "{text}");
+        assert!(text.contains("bevy_impulse_operation_task_seconds_count{"));
+    }
+
+    #[test]
+    fn test_prometheus_families_are_contiguous() {
+        let mut context = TestingContext::minimal_plugins();
+        context.app.init_resource::<WorkflowMetrics>();
+        let delay = context.spawn_async_delayed_map(Duration::from_millis(1), |v: u32| v);
+
+        let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            let buffer = builder.create_buffer(BufferSettings::keep_all());
+            scope.input.chain(builder).connect(buffer.input_slot());
+            builder
+                .listen(buffer)
+                .consume_buffer::<8>()
+                .map_block(|values| values[0])
+                .then(delay)
+                .connect(scope.terminate);
+        });
+
+        let mut promise =
+            context.command(|commands| commands.request(1_u32, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        assert!(promise.take().available().is_some_and(|v| v == 1));
+
+        let text = context
+            .app
+            .world
+            .resource::<WorkflowMetrics>()
+            .to_prometheus();
+        let mut finished: Vec<&str> = Vec::new();
+        let mut current: Option<&str> = None;
+        for line in text.lines() {
+            let family = if let Some(header) = line.strip_prefix("# HELP ") {
+                header.split(' ').next().unwrap()
+            } else if let Some(header) = line.strip_prefix("# TYPE ") {
+                assert_eq!(Some(header.split(' ').next().unwrap()), current, "{text}");
+                continue;
+            } else {
+                let name = line.split('{').next().unwrap();
+                let family = current.unwrap();
+                assert!(
+                    name.starts_with(family),
+                    "{name} is outside {family}:\n{text}"
+                );
+                continue;
+            };
+            assert!(!finished.contains(&family), "{family} repeated:\n{text}");
+            if let Some(previous) = current.replace(family) {
+                finished.push(previous);
+            }
+        }
+
+        assert!(
+            text.contains("bevy_impulse_buffer_peak_occupancy{"),
+            "{text}"
+        );
+        assert!(text.contains("bevy_impulse_service_queue_depth{"), "{text}");
+    }
+
+    #[test]
+    fn test_duration_histogram_mean() {
+        let mut histogram = DurationHistogram::default();
+        assert!(histogram.mean().is_none());
+
+        histogram.record(Duration::from_millis(1));
+        histogram.record(Duration::from_millis(2));
+        assert_eq!(histogram.mean(), Some(Duration::from_micros(1500)));
+
+        // The sample count must not be truncated when computing the mean.
+        histogram.count = u32::MAX as u64 + 2;
+        histogram.sum = Duration::from_nanos(2 * (u32::MAX as u64 + 2));
+        assert_eq!(histogram.mean(), Some(Duration::from_nanos(2)));
+    }
+
+    #[test]
+    fn test_metrics_of_despawned_operations_are_removed() {
+        let mut context = TestingContext::minimal_plugins();
+        context.app.add_plugins(MetricsPlugin::default());
+
+        let mut promise = context.command(|commands| {
+            commands
+                .provide(1_u32)
+                .map_block(|value: u32| value + 1)
+                .take()
+                .response
+        });
+        context.run_while_pending(&mut promise);
+        assert!(promise.take().available().is_some_and(|v| v == 2));
+
+        // The impulse chain despawns itself once it is finished, so its
+        // metrics should not linger.
+        context.run(1);
+        let metrics = context.app.world.resource::<WorkflowMetrics>();
+        assert_eq!(metrics.operations().count(), 0);
+    }
+}
@@ -17,9 +17,11 @@
 
 use bevy_ecs::prelude::Entity;
 
+use std::sync::Arc;
+
 use crate::{
     AddBranchToForkClone, AddOperation, Builder, Chain, ForkClone, ForkTargetStorage,
-    SingleInputStorage, StreamPack, UnusedTarget,
+    OperationLabel, SingleInputStorage, StreamPack, UnusedTarget,
 };
 
 pub mod dyn_node;
@@ -41,6 +43,18 @@ pub struct Node<Request, Response, Streams: StreamPack = ()> {
     pub streams: Streams::StreamOutputPack,
 }
 
+impl<Request, Response, Streams: StreamPack> Node<Request, Response, Streams> {
+    /// Give a human-readable [label](OperationLabel) to the operation of this
+    /// node.
+    pub fn with_label(self, label: impl Into<Arc<str>>, builder: &mut Builder) -> Self {
+        builder
+            .commands()
+            .entity(self.input.id())
+            .insert(OperationLabel::new(label));
+        self
+    }
+}
+
 /// The slot that receives input for a node. When building a workflow, you can
 /// connect the output of a node to this, as long as the types match.
 ///
@@ -16,8 +16,9 @@
 */
 
 use crate::{
-    try_emit_broken, Broken, Cancel, DeliveryLabelId, InspectInput, SetupFailure, StreamTargetMap,
-    UnhandledErrors,
+    awaken_task_span, execute_operation_span, operation_spans_enabled, record_execution,
+    report_unhandled_error, try_emit_broken, Broken, Cancel, DeliveryLabelId, DisplayFn,
+    InspectInput, OperationLabels, Priority, SetupFailure, StreamTargetMap, WorkflowMetrics,
 };
 
 use bevy_derive::Deref;
@@ -25,11 +26,15 @@ use bevy_ecs::{
     prelude::{Component, Entity, World},
     system::Command,
 };
-use bevy_hierarchy::prelude::BuildWorldChildren;
+use bevy_hierarchy::prelude::{BuildWorldChildren, Parent};
 
 use backtrace::Backtrace;
 
-use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::{Display, Formatter},
+    time::Instant,
+};
 
 use smallvec::SmallVec;
 
@@ -212,6 +217,11 @@ pub(crate) struct UnusedTarget;
 pub struct OperationRoster {
     /// Operation sources that should be triggered
     pub(crate) queue: VecDeque<Entity>,
+    /// Operation sources that should be triggered, whose input belongs to a
+    /// session with a [`Priority`] other than [`Priority::NORMAL`]. These are
+    /// kept in one bucket per priority so that picking the next operation
+    /// does not depend on how long the queue is.
+    pub(crate) prioritized: BTreeMap<Priority, VecDeque<Entity>>,
     /// Tasks that should be awoken. If the task is already despawned, then
     /// it should not be considered an error.
     pub(crate) awake: VecDeque<Entity>,
@@ -230,6 +240,9 @@ pub struct OperationRoster {
     /// Despawn these entities while no other operation is running. This is used
     /// to cleanup detached impulses that receive no input.
     pub(crate) deferred_despawn: Vec<Entity>,
+    /// When this is set, every operation that gets queued is also recorded
+    /// here so the flush can tell which operations feed each other.
+    pub(crate) fed: Option<Vec<Entity>>,
 }
 
 impl OperationRoster {
@@ -239,6 +252,42 @@ impl OperationRoster {
 
     pub fn queue(&mut self, source: Entity) {
         self.queue.push_back(source);
+        if let Some(fed) = &mut self.fed {
+            fed.push(source);
+        }
+    }
+
+    /// Move an operation that is waiting in the regular queue into the queue of
+    /// the given priority.
+    pub(crate) fn requeue_with_priority(&mut self, source: Entity, priority: Priority) {
+        if priority == Priority::NORMAL {
+            return;
+        }
+
+        let Some(index) = self.queue.iter().position(|e| *e == source) else {
+            return;
+        };
+        self.queue.remove(index);
+        self.prioritized
+            .entry(priority)
+            .or_default()
+            .push_back(source);
+    }
+
+    /// Queue an operation whose input belongs to a session with the given
+    /// priority.
+    pub(crate) fn queue_with_priority(&mut self, source: Entity, priority: Priority) {
+        if priority == Priority::NORMAL {
+            return self.queue(source);
+        }
+
+        self.prioritized
+            .entry(priority)
+            .or_default()
+            .push_back(source);
+        if let Some(fed) = &mut self.fed {
+            fed.push(source);
+        }
     }
 
     pub fn awake(&mut self, source: Entity) {
@@ -275,6 +324,7 @@ impl OperationRoster {
 
     pub fn is_empty(&self) -> bool {
         self.queue.is_empty()
+            && self.prioritized.is_empty()
             && self.awake.is_empty()
             && self.deferred_queue.is_empty()
             && self.cancel.is_empty()
@@ -284,18 +334,44 @@ impl OperationRoster {
             && self.deferred_despawn.is_empty()
     }
 
+    /// Count how many items of work are waiting in the roster.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+            + self.prioritized.values().map(VecDeque::len).sum::<usize>()
+            + self.awake.len()
+            + self.deferred_queue.len()
+            + self.cancel.len()
+            + self.unblock.len()
+            + self.disposed.len()
+            + self.cleanup_finished.len()
+            + self.deferred_despawn.len()
+    }
+
     pub fn append(&mut self, other: &mut Self) {
         self.queue.append(&mut other.queue);
+        for (priority, mut queue) in std::mem::take(&mut other.prioritized) {
+            self.prioritized
+                .entry(priority)
+                .or_default()
+                .append(&mut queue);
+        }
+        self.awake.append(&mut other.awake);
+        self.deferred_queue.append(&mut other.deferred_queue);
         self.cancel.append(&mut other.cancel);
         self.unblock.append(&mut other.unblock);
         self.disposed.append(&mut other.disposed);
         self.cleanup_finished.append(&mut other.cleanup_finished);
+        self.deferred_despawn.append(&mut other.deferred_despawn);
     }
 
     /// Remove all instances of the target from the roster. This prevents a
     /// despawned entity from needlessly tripping errors.
     pub fn purge(&mut self, target: Entity) {
         self.queue.retain(|e| *e != target);
+        self.prioritized.retain(|_, queue| {
+            queue.retain(|e| *e != target);
+            !queue.is_empty()
+        });
         self.deferred_queue.retain(|e| *e != target);
     }
 
@@ -352,6 +428,33 @@ impl OperationError {
     pub fn broken_here() -> Self {
         OperationError::Broken(Some(Backtrace::new()))
     }
+
+    /// Display this error as an error of `operation`, including the label of
+    /// the operation if `labels` has one.
+    pub fn display_with<'a>(
+        &'a self,
+        operation: Entity,
+        labels: &'a OperationLabels,
+    ) -> impl Display + 'a {
+        DisplayFn(move |f: &mut Formatter<'_>| {
+            let operation = labels.entity(operation);
+            match self {
+                Self::Broken(_) => write!(f, "operation {operation} is broken"),
+                Self::NotReady => write!(f, "operation {operation} is not ready"),
+            }
+        })
+    }
+}
+
+impl Display for OperationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // The error does not know which operation it came from. Use
+        // display_with to include the operation and its label.
+        match self {
+            Self::Broken(_) => write!(f, "the operation is broken"),
+            Self::NotReady => write!(f, "the operation is not ready"),
+        }
+    }
 }
 
 pub type OperationResult = Result<(), OperationError>;
@@ -585,13 +688,13 @@ pub trait ReportUnhandled {
 impl ReportUnhandled for OperationResult {
     fn report_unhandled(self, source: Entity, world: &mut World) {
         if let Err(OperationError::Broken(backtrace)) = self {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .broken
-                .push(Broken {
+            report_unhandled_error(
+                Broken {
                     node: source,
                     backtrace,
-                });
+                },
+                world,
+            );
         }
     }
 }
@@ -618,13 +721,13 @@ impl<Op: Operation + 'static + Sync + Send> Command for AddOperation<Op> {
             source: self.source,
             world,
         }) {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .setup
-                .push(SetupFailure {
+            report_unhandled_error(
+                SetupFailure {
                     broken_node: self.source,
                     error,
-                });
+                },
+                world,
+            );
         }
 
         let mut source_mut = world.entity_mut(self.source);
@@ -645,13 +748,13 @@ impl<Op: Operation + 'static + Sync + Send> Command for AddOperation<Op> {
                     contents.add_node(self.source);
                 }
                 Err(error) => {
-                    world
-                        .get_resource_or_insert_with(UnhandledErrors::default)
-                        .setup
-                        .push(SetupFailure {
+                    report_unhandled_error(
+                        SetupFailure {
                             broken_node: self.source,
                             error,
-                        });
+                        },
+                        world,
+                    );
                 }
             }
         }
@@ -669,7 +772,46 @@ pub(crate) struct OperationType {
     name: &'static str,
 }
 
+/// The kinds of instrumentation that are active while operations are being
+/// executed. This is checked once at the start of each flush. When nothing is
+/// active, executing an operation skips every instrumentation hook, which makes
+/// it exactly as cheap as running the operator directly.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Instrumentation {
+    /// Tracing spans are being collected.
+    spans: bool,
+    /// The [`WorkflowMetrics`] resource exists.
+    metrics: bool,
+    /// The trace recorder resource exists.
+    trace: bool,
+}
+
+impl Instrumentation {
+    pub(crate) fn of(world: &World) -> Self {
+        Self {
+            spans: operation_spans_enabled(),
+            metrics: world.contains_resource::<WorkflowMetrics>(),
+            #[cfg(feature = "diagram")]
+            trace: world.contains_resource::<crate::diagram::TraceRecorder>(),
+            #[cfg(not(feature = "diagram"))]
+            trace: false,
+        }
+    }
+
+    fn any(&self) -> bool {
+        self.spans || self.metrics || self.trace
+    }
+}
+
 pub fn execute_operation(request: OperationRequest) {
+    let instrumentation = Instrumentation::of(request.world);
+    execute_instrumented_operation(request, instrumentation);
+}
+
+pub(crate) fn execute_instrumented_operation(
+    request: OperationRequest,
+    instrumentation: Instrumentation,
+) {
     let Some(operator) = request.world.get::<OperationExecuteStorage>(request.source) else {
         if request.world.get::<UnusedTarget>(request.source).is_none() {
             // This can happen while using the async channel to issue requests
@@ -679,28 +821,106 @@ pub fn execute_operation(request: OperationRequest) {
             if request.world.get_entity(request.source).is_some() {
                 // The node does not have an operation and is not an unused target,
                 // so this is broken somehow.
-                request
-                    .world
-                    .get_resource_or_insert_with(UnhandledErrors::default)
-                    .broken
-                    .push(Broken {
+                report_unhandled_error(
+                    Broken {
                         node: request.source,
                         backtrace: Some(Backtrace::new()),
-                    });
+                    },
+                    request.world,
+                );
             }
         }
         return;
     };
     let operator = operator.0;
-    operator(request);
+    if !instrumentation.any() {
+        operator(request);
+        return;
+    }
+
+    let _span = instrumentation
+        .spans
+        .then(|| execute_operation_span(request.source, request.world).entered());
+    let timer = instrumentation.metrics.then(Instant::now);
+    let OperationRequest {
+        source,
+        world,
+        roster,
+    } = request;
+    run_traced(
+        operator,
+        source,
+        instrumentation.trace,
+        OperationRequest {
+            source,
+            world: &mut *world,
+            roster,
+        },
+    );
+    record_execution(source, timer, world);
 }
 
 pub fn awaken_task(request: OperationRequest) {
+    let instrumentation = Instrumentation::of(request.world);
+    awaken_instrumented_task(request, instrumentation);
+}
+
+pub(crate) fn awaken_instrumented_task(
+    request: OperationRequest,
+    instrumentation: Instrumentation,
+) {
     let Some(operator) = request.world.get::<OperationExecuteStorage>(request.source) else {
         // If the task is not available, we just accept that it has despawned.
         return;
     };
     let operator = operator.0;
+    if !instrumentation.any() {
+        operator(request);
+        return;
+    }
+
+    let _span = instrumentation
+        .spans
+        .then(|| awaken_task_span(request.source, request.world).entered());
+    // Outputs of a task are attributed to the node that spawned it.
+    let node = request
+        .world
+        .get::<Parent>(request.source)
+        .map(|parent| parent.get())
+        .unwrap_or(request.source);
+    run_traced(operator, node, instrumentation.trace, request);
+}
+
+/// Run an operator while letting the trace recorder know which operation is
+/// producing outputs.
+#[cfg(feature = "diagram")]
+fn run_traced(
+    operator: fn(OperationRequest),
+    operation: Entity,
+    trace: bool,
+    request: OperationRequest,
+) {
+    if !trace {
+        operator(request);
+        return;
+    }
+
+    let OperationRequest {
+        source,
+        world,
+        roster,
+    } = request;
+    let previous = crate::diagram::set_trace_source(Some(operation), world);
+    operator(OperationRequest {
+        source,
+        world: &mut *world,
+        roster,
+    });
+    crate::diagram::set_trace_source(previous, world);
+}
+
+#[cfg(not(feature = "diagram"))]
+fn run_traced(operator: fn(OperationRequest), _: Entity, _: bool, request: OperationRequest) {
     operator(request);
 }
 
@@ -16,8 +16,8 @@
 */
 
 use crate::{
-    Accessing, BufferAccessStorage, ManageDisposal, ManageInput, MiscellaneousFailure,
-    OperationError, OperationResult, OperationRoster, OrBroken, ScopeStorage, UnhandledErrors,
+    report_unhandled_error, Accessing, BufferAccessStorage, ManageDisposal, ManageInput,
+    MiscellaneousFailure, OperationError, OperationResult, OperationRoster, OrBroken, ScopeStorage,
 };
 
 use bevy_ecs::prelude::{Component, Entity, World};
@@ -70,10 +70,7 @@ impl<'a> OperationCleanup<'a> {
             world: self.world,
             roster: self.roster,
         }) {
-            self.world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .operations
-                .push(error);
+            report_unhandled_error(error, self.world);
         }
     }
 
@@ -212,13 +209,13 @@ impl Cleanup {
                 contents.awaiting_cleanup.remove(&self.cleanup_id);
             }
             None => {
-                world
-                    .get_resource_or_insert_with(UnhandledErrors::default)
-                    .miscellaneous
-                    .push(MiscellaneousFailure {
+                report_unhandled_error(
+                    MiscellaneousFailure {
                         error: Arc::new(anyhow!("Failed to clear cleanup tracker: {self:?}")),
                         backtrace: Some(backtrace::Backtrace::new()),
-                    });
+                    },
+                    world,
+                );
             }
         }
 
@@ -230,13 +227,13 @@ impl Cleanup {
             world,
             roster,
         }) {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .miscellaneous
-                .push(MiscellaneousFailure {
+            report_unhandled_error(
+                MiscellaneousFailure {
                     error: Arc::new(anyhow!("Failed to finalize cleanup: {self:?}")),
                     backtrace,
-                })
+                },
+                world,
+            )
         }
     }
 }
@@ -27,9 +27,10 @@ use backtrace::Backtrace;
 use std::sync::Arc;
 
 use crate::{
-    ForkTargetStorage, Input, InputBundle, ManageInput, MiscellaneousFailure, Operation,
-    OperationCleanup, OperationError, OperationReachability, OperationRequest, OperationResult,
-    OperationSetup, OrBroken, ReachabilityResult, SingleInputStorage, UnhandledErrors,
+    report_unhandled_error, ForkTargetStorage, Input, InputBundle, ManageInput,
+    MiscellaneousFailure, Operation, OperationCleanup, OperationError, OperationReachability,
+    OperationRequest, OperationResult, OperationSetup, OrBroken, ReachabilityResult,
+    SingleInputStorage,
 };
 
 pub(crate) struct ForkClone<Response: 'static + Send + Sync + Clone> {
@@ -119,15 +120,13 @@ pub(crate) struct AddBranchToForkClone {
 impl Command for AddBranchToForkClone {
     fn apply(self, world: &mut World) {
         if let Err(OperationError::Broken(backtrace)) = try_add_branch_to_fork_clone(self, world) {
-            world.get_resource_or_insert_with(UnhandledErrors::default)
-                .miscellaneous
-                .push(MiscellaneousFailure {
+            report_unhandled_error(MiscellaneousFailure {
                     error: Arc::new(anyhow!(
                         "Unable to create a new branch for a fork clone, source: {:?}, target: {:?}",
                         self.source, self.target,
                     )),
                     backtrace: Some(backtrace.unwrap_or_else(Backtrace::new))
-                })
+                }, world)
         }
     }
 }
@@ -29,11 +29,12 @@ use backtrace::Backtrace;
 use smallvec::SmallVec;
 
 use crate::{
-    Broken, BufferAccessors, BufferSettings, BufferStorage, DeferredRoster, ForkTargetStorage,
-    Gate, GateActionStorage, Input, InputBundle, InspectBuffer, ManageBuffer, ManageInput,
+    record_buffer_occupancy, report_unhandled_error, AnyBuffer, AnyBufferAccessInterface, Broken,
+    BufferAccessors, BufferSettings, BufferStorage, DeferredRoster, ForkTargetStorage, Gate,
+    GateActionStorage, Input, InputBundle, InspectBuffer, ManageBuffer, ManageInput,
     MiscellaneousFailure, Operation, OperationCleanup, OperationError, OperationReachability,
     OperationRequest, OperationResult, OperationRoster, OperationSetup, OrBroken,
-    ReachabilityResult, SingleInputStorage, UnhandledErrors,
+    ReachabilityResult, SingleInputStorage,
 };
 
 #[derive(Bundle)]
@@ -81,6 +82,9 @@ where
         let Input { session, data } = source_mut.take_input::<T>()?;
         let mut buffer = source_mut.get_mut::<BufferStorage<T>>().or_broken()?;
         buffer.force_push(session, data);
+        let occupancy = buffer.count(session);
+        record_buffer_occupancy(source, occupancy, world);
+        let source_mut = world.get_entity_mut(source).or_broken()?;
 
         if source_mut
             .get::<GateState>()
@@ -240,15 +244,15 @@ impl Command for OnNewBufferValue {
 
 impl OnNewBufferValue {
     fn on_failure(self, world: &mut World) {
-        world
-            .get_resource_or_insert_with(UnhandledErrors::default)
-            .miscellaneous
-            .push(MiscellaneousFailure {
+        report_unhandled_error(
+            MiscellaneousFailure {
                 error: Arc::new(anyhow!(
                     "Unable to add target with OnNewBufferValue: {self:?}"
                 )),
                 backtrace: Some(Backtrace::new()),
-            });
+            },
+            world,
+        );
     }
 }
 
@@ -257,6 +261,7 @@ struct BufferBundle {
     clear: ClearBufferFn,
     size: CheckBufferSizeFn,
     sessions: GetBufferedSessionsFn,
+    interface: AnyBufferInterfaceStorage,
 }
 
 impl BufferBundle {
@@ -265,10 +270,17 @@ impl BufferBundle {
             clear: ClearBufferFn::new::<T>(),
             size: CheckBufferSizeFn::new::<T>(),
             sessions: GetBufferedSessionsFn::new::<T>(),
+            interface: AnyBufferInterfaceStorage(AnyBuffer::interface_for::<T>()),
         }
     }
 }
 
+/// Lets a buffer be viewed without knowing its message type.
+#[derive(Component, Clone, Copy)]
+pub(crate) struct AnyBufferInterfaceStorage(
+    pub(crate) &'static (dyn AnyBufferAccessInterface + Send + Sync),
+);
+
 #[derive(Component)]
 pub struct ClearBufferFn(pub fn(Entity, Entity, &mut World) -> OperationResult);
 
@@ -389,13 +401,13 @@ impl Command for NotifyBufferUpdate {
         };
 
         if let Err(OperationError::Broken(backtrace)) = r {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .broken
-                .push(Broken {
+            report_unhandled_error(
+                Broken {
                     node: self.buffer,
                     backtrace,
-                });
+                },
+                world,
+            );
         }
     }
 }
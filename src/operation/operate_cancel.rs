@@ -15,15 +15,18 @@
  *
 */
 
+use std::sync::Arc;
+
 use crate::{
     Cancellation, Input, InputBundle, ManageCancellation, ManageInput, Operation, OperationCleanup,
     OperationReachability, OperationRequest, OperationResult, OperationSetup, OrBroken,
-    ReachabilityResult, SingleInputStorage,
+    ReachabilityResult, SingleInputStorage, TriggeredCancellation,
 };
 
 /// Create an operation that will cancel a scope. The incoming message will be
-/// included in the cancellation data as a [`String`]. The incoming message type
-/// must support the [`ToString`] trait.
+/// included in the cancellation data as a [`String`], and the message itself
+/// will be kept alongside it. The incoming message type must support the
+/// [`ToString`] trait.
 ///
 /// To trigger a cancellation for types that do not support [`ToString`], convert
 /// the message to a trigger and send it to [`OperateQuietCancel`].
@@ -61,7 +64,12 @@ where
         let mut source_mut = world.get_entity_mut(source).or_broken()?;
         let Input { session, data } = source_mut.take_input::<T>().or_broken()?;
 
-        let cancellation = Cancellation::triggered(source, Some(data.to_string()));
+        let cancellation: Cancellation = TriggeredCancellation {
+            cancelled_at_node: source,
+            value: Some(data.to_string()),
+            message: Some(Arc::new(data)),
+        }
+        .into();
         source_mut.emit_cancel(session, cancellation, roster);
         Ok(())
     }
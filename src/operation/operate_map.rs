@@ -22,8 +22,8 @@ use std::future::Future;
 use crate::{
     async_execution::{spawn_task, task_cancel_sender},
     make_stream_buffers_from_world, ActiveTasksStorage, AsyncMap, BlockingMap, CallAsyncMap,
-    CallBlockingMap, Channel, ChannelQueue, Input, InputBundle, ManageDisposal, ManageInput,
-    OperateTask, Operation, OperationCleanup, OperationReachability, OperationRequest,
+    CallBlockingMap, CancellationToken, Channel, ChannelQueue, Input, InputBundle, ManageDisposal,
+    ManageInput, OperateTask, Operation, OperationCleanup, OperationReachability, OperationRequest,
     OperationResult, OperationSetup, OrBroken, ReachabilityResult, Sendish, SingleInputStorage,
     SingleTargetStorage, StreamPack, UnusedStreams,
 };
@@ -227,6 +227,7 @@ where
         let channel = Channel::new(source, session, sender.clone());
         let streams = channel.for_streams::<Streams>(world)?;
 
+        let cancellation_token = CancellationToken::new();
         let task = spawn_task(
             f.call(AsyncMap {
                 request,
@@ -234,6 +235,7 @@ where
                 channel,
                 source,
                 session,
+                cancellation_token: cancellation_token.clone(),
             }),
             world,
         );
@@ -253,6 +255,7 @@ where
             target,
             task,
             cancel_sender,
+            cancellation_token,
             None,
             sender,
         )
@@ -16,11 +16,11 @@
 */
 
 use crate::{
-    dispatch_service, ActiveContinuousSessions, ActiveTasksStorage, Delivery, DeliveryInstructions,
-    Disposal, DisposalFailure, Input, InputBundle, ManageDisposal, ManageInput, Operation,
-    OperationCleanup, OperationReachability, OperationRequest, OperationResult, OperationRoster,
-    OperationSetup, OrBroken, ReachabilityResult, Service, ServiceRequest, SingleInputStorage,
-    SingleTargetStorage, UnhandledErrors, WorkflowHooks,
+    dispatch_service, report_unhandled_error, ActiveContinuousSessions, ActiveTasksStorage,
+    Delivery, DeliveryInstructions, Disposal, DisposalFailure, Input, InputBundle, ManageDisposal,
+    ManageInput, Operation, OperationCleanup, OperationReachability, OperationRequest,
+    OperationResult, OperationRoster, OperationSetup, OrBroken, ReachabilityResult, Service,
+    ServiceRequest, SingleInputStorage, SingleTargetStorage, WorkflowHooks,
 };
 
 use bevy_ecs::{
@@ -168,14 +168,14 @@ pub(crate) fn dispose_for_despawned_service(
         if let Some(disposer) = disposer {
             (disposer.0)(source, despawned_service, world, roster);
         } else {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .disposals
-                .push(DisposalFailure {
+            report_unhandled_error(
+                DisposalFailure {
                     disposal: Disposal::service_unavailable(despawned_service, source),
                     broken_node: source,
                     backtrace: Some(Backtrace::new()),
-                });
+                },
+                world,
+            );
         }
     }
 }
@@ -203,13 +203,13 @@ fn dispose_for_unavailable_service<T: 'static + Send + Sync>(
             source_mut.emit_disposal(session, disposal.clone(), roster);
         }
     } else {
-        world
-            .get_resource_or_insert_with(UnhandledErrors::default)
-            .disposals
-            .push(DisposalFailure {
+        report_unhandled_error(
+            DisposalFailure {
                 disposal,
                 broken_node: source,
                 backtrace: Some(Backtrace::new()),
-            });
+            },
+            world,
+        );
     }
 }
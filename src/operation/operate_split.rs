@@ -23,10 +23,10 @@ use smallvec::SmallVec;
 use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    Broken, Disposal, ForkTargetStorage, Input, InputBundle, ManageDisposal, ManageInput,
-    MiscellaneousFailure, Operation, OperationCleanup, OperationError, OperationReachability,
-    OperationRequest, OperationResult, OperationSetup, OrBroken, ReachabilityResult,
-    SingleInputStorage, SplitDispatcher, Splittable, UnhandledErrors,
+    report_unhandled_error, Broken, Disposal, ForkTargetStorage, Input, InputBundle,
+    ManageDisposal, ManageInput, MiscellaneousFailure, Operation, OperationCleanup, OperationError,
+    OperationReachability, OperationRequest, OperationResult, OperationSetup, OrBroken,
+    ReachabilityResult, SingleInputStorage, SplitDispatcher, Splittable,
 };
 
 #[derive(Component)]
@@ -146,10 +146,7 @@ impl<T: 'static + Splittable> Command for ConnectToSplit<T> {
     fn apply(self, world: &mut World) {
         let node = self.source;
         if let Err(OperationError::Broken(backtrace)) = self.connect(world) {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .broken
-                .push(Broken { node, backtrace });
+            report_unhandled_error(Broken { node, backtrace }, world);
         }
     }
 }
@@ -180,17 +177,17 @@ impl<T: 'static + Splittable> ConnectToSplit<T> {
             // the workflow because this reverse map is only used to generate
             // disposal messages, but it does indicate a bug is present.
             let reverse_map_size = split.index_to_key.len();
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .miscellaneous
-                .push(MiscellaneousFailure {
+            report_unhandled_error(
+                MiscellaneousFailure {
                     error: Arc::new(anyhow::anyhow!(
                         "Mismatch between reverse map size [{}] and new connection index [{}]",
                         reverse_map_size,
                         index,
                     )),
                     backtrace: Some(backtrace::Backtrace::new()),
-                });
+                },
+                world,
+            );
         } else {
             split
                 .index_to_key
@@ -203,10 +200,7 @@ impl<T: 'static + Splittable> ConnectToSplit<T> {
             let target_storage = world.get::<ForkTargetStorage>(self.source).or_broken()?;
             let previous_target = *target_storage.0.get(previous_index).or_broken()?;
 
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .miscellaneous
-                .push(MiscellaneousFailure {
+            report_unhandled_error(MiscellaneousFailure {
                     error: Arc::new(anyhow::anyhow!(
                         "Double-connected key [{:?}] for split node {:?}. Original target: {:?}, new target: {:?}",
                         self.key,
@@ -215,7 +209,7 @@ impl<T: 'static + Splittable> ConnectToSplit<T> {
                         self.target,
                     )),
                     backtrace: Some(backtrace::Backtrace::new()),
-                });
+                }, world);
         }
 
         Ok(())
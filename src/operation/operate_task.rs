@@ -21,9 +21,12 @@ use bevy_ecs::{
 };
 use bevy_hierarchy::{BuildWorldChildren, DespawnRecursiveExt};
 
-use std::{future::Future, pin::Pin, sync::Arc, task::Context, task::Poll};
+use std::{future::Future, pin::Pin, sync::Arc, task::Context, task::Poll, time::Instant};
 
-use futures::task::{waker_ref, ArcWake};
+use futures::{
+    future::{select, Either},
+    task::{waker_ref, ArcWake},
+};
 
 use tokio::sync::mpsc::{
     unbounded_channel, UnboundedReceiver as TokioReceiver, UnboundedSender as TokioSender,
@@ -33,10 +36,11 @@ use smallvec::SmallVec;
 
 use crate::{
     async_execution::{task_cancel_sender, CancelSender, TaskHandle},
-    emit_disposal, AddOperation, Blocker, Broken, ChannelItem, ChannelQueue, Cleanup, Disposal,
+    emit_disposal, grace_period_expired, record_task_duration, report_unhandled_error,
+    AddOperation, Blocker, Broken, CancellationToken, ChannelItem, ChannelQueue, Cleanup, Disposal,
     ManageInput, Operation, OperationCleanup, OperationError, OperationReachability,
     OperationRequest, OperationResult, OperationRoster, OperationSetup, OrBroken,
-    ReachabilityResult, ScopeStorage, StreamPack, UnhandledErrors,
+    ReachabilityResult, ScopeStorage, StreamPack,
 };
 
 struct JobWaker {
@@ -74,6 +78,7 @@ pub(crate) struct OperateTask<Response: 'static + Send + Sync, Streams: StreamPa
     target: Entity,
     task: Option<TaskHandle<Response>>,
     cancel_sender: CancelSender,
+    cancellation_token: CancellationToken,
     blocker: Option<Blocker>,
     sender: TokioSender<ChannelItem>,
     disposal: Option<Disposal>,
@@ -91,6 +96,7 @@ impl<Response: 'static + Send + Sync, Streams: StreamPack> OperateTask<Response,
         target: Entity,
         task: TaskHandle<Response>,
         cancel_sender: CancelSender,
+        cancellation_token: CancellationToken,
         blocker: Option<Blocker>,
         sender: TokioSender<ChannelItem>,
     ) -> Self {
@@ -101,6 +107,7 @@ impl<Response: 'static + Send + Sync, Streams: StreamPack> OperateTask<Response,
             target,
             task: Some(task),
             cancel_sender,
+            cancellation_token,
             blocker,
             sender,
             disposal: None,
@@ -139,6 +146,7 @@ where
         let session = self.session;
         let node = self.node;
         let task = self.task.take();
+        let cancellation_token = self.cancellation_token.clone();
         let unblock = self.blocker.take();
         let sender = self.sender.clone();
         let disposal = self.disposal.take();
@@ -148,14 +156,16 @@ where
             let mut disposed = false;
             if let Some(task) = task {
                 disposed = true;
-                task.cancel().await;
+                cancel_task(task, cancellation_token).await;
             }
             sender
                 .send(Box::new(
                     move |world: &mut World, roster: &mut OperationRoster| {
                         cleanup_task(source, node, unblock, being_cleaned, world, roster);
 
-                        if disposed {
+                        // If the node is gone then the whole impulse was
+                        // dropped, so there is nobody left to notify.
+                        if disposed && world.get_entity(node).is_some() {
                             let disposal =
                                 disposal.unwrap_or_else(|| Disposal::task_despawned(source, node));
                             emit_disposal(node, session, disposal, world, roster);
@@ -196,6 +206,7 @@ where
             task_id: source,
             session,
             being_cleaned: None,
+            started: Instant::now(),
         });
         Ok(())
     }
@@ -227,6 +238,7 @@ where
         let target = operation.target;
         let session = operation.session;
         let node = operation.node;
+        let cancellation_token = operation.cancellation_token.clone();
         let being_cleaned = operation.being_cleaned;
         // We take out unblock here just in case the entity gets despawned and/or
         // the OperateTask component gets dropped before we reach the end of the
@@ -307,6 +319,7 @@ where
                         target,
                         task,
                         cancel_sender,
+                        cancellation_token,
                         unblock,
                         sender,
                     );
@@ -331,11 +344,12 @@ where
         operation.finished_normally = true;
         let node = operation.node;
         let task = operation.task.take();
+        let cancellation_token = operation.cancellation_token.clone();
         let unblock = operation.blocker.take();
         let sender = operation.sender.clone();
         if let Some(task) = task {
             operation.cancel_sender.send(move || async move {
-                task.cancel().await;
+                cancel_task(task, cancellation_token).await;
                 if let Err(err) = sender.send(Box::new(
                     move |world: &mut World, roster: &mut OperationRoster| {
                         cleanup_task(source, node, unblock, Some(cleanup), world, roster);
@@ -370,6 +384,27 @@ where
     }
 }
 
+/// Let the task know that it is being cancelled, give it the grace period that
+/// it asked for, and then drop it if it still has not finished.
+async fn cancel_task<Response>(task: TaskHandle<Response>, cancellation_token: CancellationToken) {
+    let grace_period = cancellation_token.grace_period();
+    cancellation_token.cancel();
+    let task = match grace_period {
+        Some(grace_period) => {
+            let expired = Box::pin(grace_period_expired(grace_period));
+            match select(task, expired).await {
+                // The task finished cleaning up within its grace period. Its
+                // response is discarded because the session is gone.
+                Either::Left(_) => return,
+                Either::Right((_, task)) => task,
+            }
+        }
+        None => task,
+    };
+
+    task.cancel().await;
+}
+
 fn cleanup_task(
     source: Entity,
     node: Entity,
@@ -382,6 +417,7 @@ fn cleanup_task(
         roster.unblock(unblock);
     }
 
+    let mut task_duration = None;
     if let Some(mut node_mut) = world.get_entity_mut(node) {
         if let Some(mut active_tasks) = node_mut.get_mut::<ActiveTasksStorage>() {
             let mut cleanup_ready = true;
@@ -389,9 +425,11 @@ fn cleanup_task(
                 |ActiveTask {
                      task_id: id,
                      being_cleaned: other_being_cleaned,
+                     started,
                      ..
                  }| {
                     if *id == source {
+                        task_duration = Some(started.elapsed());
                         return false;
                     }
 
@@ -410,16 +448,17 @@ fn cleanup_task(
                 if let Some(being_cleaned) = being_cleaned {
                     let r = being_cleaned.notify_cleaned(world, roster);
                     if let Err(OperationError::Broken(backtrace)) = r {
-                        world
-                            .get_resource_or_insert_with(UnhandledErrors::default)
-                            .broken
-                            .push(Broken { node, backtrace });
+                        report_unhandled_error(Broken { node, backtrace }, world);
                     }
                 }
             }
         };
     };
 
+    if let Some(duration) = task_duration {
+        record_task_duration(node, duration, world);
+    }
+
     if let Some(source_mut) = world.get_entity_mut(source) {
         source_mut.despawn_recursive();
     }
@@ -462,9 +501,26 @@ pub struct ActiveTask {
     task_id: Entity,
     session: Entity,
     being_cleaned: Option<Cleanup>,
+    started: Instant,
 }
 
 impl ActiveTasksStorage {
+    /// Iterate over the tasks that are working on a session.
+    pub(crate) fn tasks_of(&self, session: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.list
+            .iter()
+            .filter(move |task| task.session == session)
+            .map(|task| task.task_id)
+    }
+
+    /// Get the session that an active task is working on.
+    pub(crate) fn session_of(&self, task_id: Entity) -> Option<Entity> {
+        self.list
+            .iter()
+            .find(|task| task.task_id == task_id)
+            .map(|task| task.session)
+    }
+
     pub fn cleanup(clean: &mut OperationCleanup) -> Result<bool, OperationError> {
         let source = clean.source;
         let mut source_mut = clean.world.get_entity_mut(source).or_broken()?;
@@ -475,6 +531,7 @@ impl ActiveTasksStorage {
             task_id: id,
             session,
             being_cleaned,
+            ..
         } in &mut active_tasks.list
         {
             if *session == clean.cleanup.session {
@@ -18,7 +18,7 @@
 use crate::{
     check_reachability,
     dyn_node::{DynInputSlot, DynOutput},
-    execute_operation, is_downstream_of,
+    execute_operation, inherit_priority, is_downstream_of, report_unhandled_error,
     type_info::TypeInfo,
     Accessing, AddOperation, Blocker, Broken, BufferKeyBuilder, Builder, BuilderScopeContext,
     Cancel, Cancellable, Cancellation, Cleanup, CleanupContents, ClearBufferFn, CollectMarker,
@@ -26,15 +26,15 @@ use crate::{
     InspectDisposals, ManageCancellation, ManageInput, NamedTarget, NamedValue, Operation,
     OperationCancel, OperationCleanup, OperationError, OperationReachability, OperationRequest,
     OperationResult, OperationRoster, OperationSetup, OrBroken, ReachabilityResult, ScopeSettings,
-    SingleInputStorage, SingleTargetStorage, StreamEffect, StreamRequest, StreamTargetMap,
-    UnhandledErrors, Unreachability, UnusedTarget,
+    SessionSpan, SingleInputStorage, SingleTargetStorage, StreamEffect, StreamRequest,
+    StreamTargetMap, Unreachability, UnusedTarget,
 };
 
 use backtrace::Backtrace;
 
 use bevy_derive::Deref;
 use bevy_ecs::{
-    prelude::{Commands, Component, Entity, World},
+    prelude::{Commands, Component, Entity, EntityWorldMut, World},
     system::Command,
 };
 use bevy_hierarchy::{BuildChildren, DespawnRecursiveExt};
@@ -62,7 +62,7 @@ impl ParentSession {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SessionStatus {
     Active,
     Cleaning,
@@ -113,13 +113,13 @@ impl Command for InsertTypeSensitiveComponents {
             world,
         });
         if let Err(OperationError::Broken(backtrace)) = r {
-            world
-                .get_resource_or_insert_with(|| UnhandledErrors::default())
-                .broken
-                .push(Broken {
+            report_unhandled_error(
+                Broken {
                     node: self.source,
                     backtrace,
-                });
+                },
+                world,
+            );
         }
     }
 }
@@ -212,12 +212,27 @@ impl ScopedSessionStatus {
 }
 
 #[derive(Component, Default)]
-struct ScopedSessionStorage(SmallVec<[ScopedSession; 8]>);
+pub(crate) struct ScopedSessionStorage(SmallVec<[ScopedSession; 8]>);
+
+impl ScopedSessionStorage {
+    /// Iterate over the `(parent_session, scoped_session)` pairs of this scope.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.0
+            .iter()
+            .map(|pair| (pair.parent_session, pair.scoped_session))
+    }
+}
 
 /// Store the terminating nodes for this scope
 #[derive(Component)]
 pub struct TerminalStorage(Entity);
 
+/// Store the target that should receive the [`Cancellation`] of a scoped
+/// session. When a scope has this component, its cancellations will be passed
+/// to this target as a message instead of cancelling the parent session.
+#[derive(Component)]
+pub(crate) struct CancelTargetStorage(pub(crate) Entity);
+
 impl TerminalStorage {
     pub fn get(&self) -> Entity {
         self.0
@@ -364,6 +379,9 @@ fn dyn_begin_scope<Request: 'static + Send + Sync>(
     let scoped_session = world
         .spawn((ParentSession(input.session), SessionStatus::Active))
         .id();
+    let span = SessionSpan::for_scope(scoped_session, input.session, source, world);
+    world.entity_mut(scoped_session).insert(span);
+    inherit_priority(scoped_session, input.session, [], world);
 
     begin_scope(
         input,
@@ -464,6 +482,12 @@ fn dyn_is_reachable<Request: 'static + Send + Sync>(
         if scoped_reachability.check_upstream(terminal)? {
             return Ok(true);
         }
+
+        if source_ref.contains::<CancelTargetStorage>() {
+            // Even if the scoped session gets cancelled, this scope will still
+            // produce a message for its cancellation target.
+            return Ok(true);
+        }
     }
 
     SingleInputStorage::is_reachable(&mut reachability)
@@ -726,13 +750,38 @@ pub(crate) type IncrementalScopeResponseResult =
 
 impl IncrementalScopeBuilder {
     pub(crate) fn begin(settings: ScopeSettings, builder: &mut Builder) -> IncrementalScopeBuilder {
+        let exit_scope = builder.commands().spawn(UnusedTarget).id();
+        Self::begin_impl(settings, exit_scope, true, builder)
+    }
+
+    /// Begin building a cleanup workflow scope, similar to what
+    /// [`Accessing::on_cleanup_if`] creates. The scope will exit into the
+    /// cleanup finisher of the builder's current scope, so no external output
+    /// will be provided by [`Self::set_response`].
+    ///
+    /// The request type of the scope must be the key type of the buffers given
+    /// to [`begin_cleanup_workflow`](crate::begin_cleanup_workflow).
+    #[cfg(feature = "diagram")]
+    pub(crate) fn begin_cleanup(
+        settings: ScopeSettings,
+        builder: &mut Builder,
+    ) -> IncrementalScopeBuilder {
+        let exit_scope = builder.context.finish_scope_cancel;
+        Self::begin_impl(settings, exit_scope, false, builder)
+    }
+
+    fn begin_impl(
+        settings: ScopeSettings,
+        exit_scope: Entity,
+        has_external_output: bool,
+        builder: &mut Builder,
+    ) -> IncrementalScopeBuilder {
         let parent_scope = builder.scope();
         let commands = builder.commands();
 
         // TODO(@mxgrey): Consider how to refactor this to share an implementation
         // with OperateScope::add.
         let scope_id = commands.spawn(()).id();
-        let exit_scope = commands.spawn(UnusedTarget).id();
         let enter_scope = commands.spawn((EntryForScope(scope_id), UnusedTarget)).id();
         let terminal = commands.spawn(()).set_parent(scope_id).id();
         let finish_scope_cancel = commands
@@ -765,7 +814,7 @@ impl IncrementalScopeBuilder {
                 response: None,
                 already_built: false,
                 begin_scope_not_sent: true,
-                external_output_not_sent: true,
+                external_output_not_sent: has_external_output,
             })),
         }
     }
@@ -857,12 +906,34 @@ impl IncrementalScopeBuilder {
         Ok(response)
     }
 
+    /// Get an output that will receive the [`Cancellation`] of the scope
+    /// whenever a scoped session is cancelled. Once this is used, the
+    /// cancellation of a scoped session will no longer cancel the parent
+    /// session.
+    #[cfg(feature = "diagram")]
+    pub(crate) fn cancellation_output(&mut self, commands: &mut Commands) -> DynOutput {
+        let inner = self.inner.lock().unwrap();
+        let target = commands
+            .spawn((UnusedTarget, SingleInputStorage::new(inner.scope_id)))
+            .id();
+        commands
+            .entity(inner.scope_id)
+            .insert(CancelTargetStorage(target));
+
+        DynOutput::new(inner.parent_scope, target, TypeInfo::of::<Cancellation>())
+    }
+
     pub(crate) fn is_finished(&self) -> Result<(), IncrementalScopeError> {
         let inner = self.inner.lock().unwrap();
-        if inner.begin_scope_not_sent || inner.external_output_not_sent {
+        // We check the request and response themselves instead of the
+        // begin_scope_not_sent and external_output_not_sent flags. For regular
+        // scopes they are equivalent, but a cleanup scope never sends an
+        // external output, so its flag cannot tell us whether the response
+        // was set.
+        if inner.request.is_none() || inner.response.is_none() {
             return Err(IncrementalScopeError::Unfinished {
-                request_set: !inner.begin_scope_not_sent,
-                response_set: !inner.external_output_not_sent,
+                request_set: inner.request.is_some(),
+                response_set: inner.response.is_some(),
             });
         }
 
@@ -939,10 +1010,7 @@ fn receive_cancel(
     for scoped_session in all_scoped_sessions {
         if let Err(error) = cancel_one(scoped_session, source, cancellation.clone(), world, roster)
         {
-            world
-                .get_resource_or_insert_with(UnhandledErrors::default)
-                .operations
-                .push(error);
+            report_unhandled_error(error, world);
         }
     }
 
@@ -1524,6 +1592,9 @@ where
         let cancellation_session = world
             .spawn((ParentSession(scoped_session), SessionStatus::Active))
             .id();
+        let span = SessionSpan::for_scope(cancellation_session, scoped_session, from_scope, world);
+        world.entity_mut(cancellation_session).insert(span);
+        inherit_priority(cancellation_session, scoped_session, [], world);
         world
             .get_entity_mut(target)
             .or_broken()?
@@ -1616,6 +1687,33 @@ impl<T: 'static + Send + Sync> Operation for FinishCleanup<T> {
     }
 }
 
+/// Pass the cancellation of a scoped session along to the parent session of
+/// the scope, for scopes that do not have a target for their cancellations.
+///
+/// The cleanup finisher is not inside of any scope, so emitting a cancel from
+/// it would try to cancel the parent session directly. That only works when
+/// the scope is the root of a workflow. For a nested scope, the parent session
+/// has to be cancelled by the scope that contains it, otherwise the parent
+/// scope never learns about the cancellation and never finishes.
+fn cancel_parent_session(
+    finisher: &mut EntityWorldMut,
+    parent_scope: Option<Entity>,
+    parent_session: Entity,
+    cancellation: Cancellation,
+    roster: &mut OperationRoster,
+) {
+    if let Some(parent_scope) = parent_scope {
+        roster.cancel(Cancel {
+            origin: finisher.id(),
+            target: parent_scope,
+            session: Some(parent_session),
+            cancellation,
+        });
+    } else {
+        finisher.emit_cancel(parent_session, cancellation, roster);
+    }
+}
+
 impl<T: 'static + Send + Sync> FinishCleanup<T> {
     fn receive_cancel(
         OperationCancel {
@@ -1665,10 +1763,7 @@ impl<T: 'static + Send + Sync> FinishCleanup<T> {
                 roster,
                 Some(cancellation.clone()),
             ) {
-                world
-                    .get_resource_or_insert_with(UnhandledErrors::default)
-                    .operations
-                    .push(error);
+                report_unhandled_error(error, world);
             }
         }
 
@@ -1767,8 +1862,10 @@ impl<T: 'static + Send + Sync> FinishCleanup<T> {
             roster,
         }: OperationRequest,
     ) -> OperationResult {
+        let scope = world.get::<FinishCleanupForScope>(source).or_broken()?.0;
+        let cancel_target = world.get::<CancelTargetStorage>(scope).map(|t| t.0);
+        let parent_scope = world.get::<ScopeStorage>(scope).map(|s| s.get());
         let mut source_mut = world.get_entity_mut(source).or_broken()?;
-        let scope = source_mut.get::<FinishCleanupForScope>().or_broken()?.0;
         let mut awaiting = source_mut.get_mut::<AwaitingCleanupStorage>().or_broken()?;
         let a = awaiting.0.get(index).or_broken()?;
         let parent_session = a.info.parent_session;
@@ -1776,13 +1873,27 @@ impl<T: 'static + Send + Sync> FinishCleanup<T> {
         let cleanup_id = cleanup.cleanup_id;
         let scoped_session = a.scoped_session;
         let terminating = a.info.status.is_terminated();
+        let mut cancelled = None;
         if !a.info.status.is_early_cleanup() {
             // We can remove this right away since it's a cancellation or
             // termination, so we don't need to track when to notify the parent
             // of a cleanup.
             let a = awaiting.0.remove(index);
             if let FinishStatus::Cancelled(cancellation) = a.info.status {
-                source_mut.emit_cancel(parent_session, cancellation, roster);
+                if let Some(cancel_target) = cancel_target {
+                    // The scope has a target for its cancellations, so the
+                    // parent session gets to decide how to handle it once the
+                    // cleanup is finished.
+                    cancelled = Some((cancel_target, cancellation));
+                } else {
+                    cancel_parent_session(
+                        &mut source_mut,
+                        parent_scope,
+                        parent_session,
+                        cancellation,
+                        roster,
+                    );
+                }
             }
         }
 
@@ -1845,6 +1956,13 @@ impl<T: 'static + Send + Sync> FinishCleanup<T> {
             cleanup.notify_cleaned(world, roster)?;
         }
 
+        if let Some((cancel_target, cancellation)) = cancelled {
+            world
+                .get_entity_mut(cancel_target)
+                .or_broken()?
+                .give_input(parent_session, cancellation, roster)?;
+        }
+
         let mut scope_mut = world.get_entity_mut(scope).or_broken()?;
         let terminal = scope_mut.get::<TerminalStorage>().or_broken()?.0;
         let (target, blocker) = scope_mut
@@ -2193,6 +2311,7 @@ mod tests {
         operation::{IncrementalScopeError, IncrementalScopeRequest, IncrementalScopeResponse},
         prelude::*,
         testing::*,
+        CancellationCause,
     };
 
     #[test]
@@ -2233,6 +2352,77 @@ mod tests {
         assert_eq!(result, "fast");
     }
 
+    #[test]
+    fn test_nested_scope_cancellation() {
+        let mut context = TestingContext::minimal_plugins();
+
+        let workflow = context.spawn_io_workflow(|scope: Scope<i32, i32>, builder| {
+            let inner_scope = builder.create_io_scope(|scope: Scope<i32, i32>, builder| {
+                builder
+                    .chain(scope.input)
+                    .map_block(|value: i32| (value > 0).then_some(value))
+                    .dispose_on_none()
+                    .connect(scope.terminate);
+            });
+
+            builder.connect(scope.input, inner_scope.input);
+            builder.connect(inner_scope.output, scope.terminate);
+        });
+
+        let mut promise = context.command(|commands| commands.request(4, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(1));
+        assert_eq!(promise.take().available(), Some(4));
+
+        // The terminal of the inner scope becomes unreachable, which cancels
+        // the inner scope, which must cancel the outer workflow.
+        let mut promise =
+            context.command(|commands| commands.request(-4, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(1));
+        assert!(promise.take().is_cancelled());
+        assert!(context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_doubly_nested_scope_cancellation() {
+        let mut context = TestingContext::minimal_plugins();
+
+        let workflow = context.spawn_io_workflow(|scope: Scope<i32, i32>, builder| {
+            let middle_scope = builder.create_io_scope(|scope: Scope<i32, i32>, builder| {
+                let inner_scope = builder.create_io_scope(|scope: Scope<i32, i32>, builder| {
+                    builder
+                        .chain(scope.input)
+                        .map_block(|value: i32| (value > 0).then_some(value))
+                        .cancel_on_none()
+                        .connect(scope.terminate);
+                });
+
+                builder.connect(scope.input, inner_scope.input);
+                builder.connect(inner_scope.output, scope.terminate);
+            });
+
+            builder.connect(scope.input, middle_scope.input);
+            builder.connect(middle_scope.output, scope.terminate);
+        });
+
+        // The cancellation of the innermost scope must pass through every
+        // scope that contains it, keeping its original cause.
+        let mut promise =
+            context.command(|commands| commands.request(-4, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(1));
+        let cancellation = promise.take().cancellation().cloned().unwrap();
+        assert!(matches!(
+            cancellation.cause.as_ref(),
+            CancellationCause::Filtered(_)
+        ));
+        assert!(context.no_unhandled_errors());
+
+        // The scopes are left in a clean state for later sessions.
+        let mut promise = context.command(|commands| commands.request(4, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(1));
+        assert_eq!(promise.take().available(), Some(4));
+        assert!(context.no_unhandled_errors());
+    }
+
     #[test]
     fn test_incremental_scope_race() {
         let mut context = TestingContext::minimal_plugins();
@@ -2373,4 +2563,44 @@ mod tests {
         let result: &'static str = promise.take().available().unwrap();
         assert_eq!(result, "fast");
     }
+
+    #[cfg(feature = "diagram")]
+    #[test]
+    fn test_incremental_cleanup_scope_is_finished() {
+        let mut context = TestingContext::minimal_plugins();
+
+        let workflow = context.spawn_io_workflow(|scope, builder| {
+            let mut incremental_scope_builder =
+                crate::IncrementalScopeBuilder::begin_cleanup(ScopeSettings::default(), builder);
+            assert!(incremental_scope_builder.is_finished().is_err());
+
+            incremental_scope_builder
+                .set_request::<()>(builder.commands())
+                .unwrap();
+            // A cleanup scope has no external output to send, but it is still
+            // unfinished until its response has been set.
+            assert!(matches!(
+                incremental_scope_builder.is_finished(),
+                Err(IncrementalScopeError::Unfinished {
+                    request_set: true,
+                    response_set: false,
+                })
+            ));
+
+            let IncrementalScopeResponse {
+                external_output, ..
+            } = incremental_scope_builder
+                .set_response::<()>(builder.commands())
+                .unwrap();
+            assert!(external_output.is_none());
+            assert!(incremental_scope_builder.is_finished().is_ok());
+
+            builder.connect(scope.input, scope.terminate);
+        });
+
+        let mut promise = context.command(|commands| commands.request(5, workflow).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(1));
+        assert_eq!(promise.take().available(), Some(5));
+        assert!(context.no_unhandled_errors());
+    }
 }
@@ -0,0 +1,300 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use bevy_ecs::prelude::{Component, Entity, Resource, World};
+
+use crate::{DeliveryInstructions, OperationRoster};
+
+/// The priority of a session. When several operations are ready to run, the
+/// operations whose input belongs to a session with a higher priority run
+/// first. Requests waiting in the queue of a serial service are delivered in
+/// order of priority as well.
+///
+/// Operations with the same priority run in the order that they became ready.
+///
+/// A priority can be set for
+/// * a whole workflow, with [`WorkflowSettings::with_priority`](crate::WorkflowSettings::with_priority)
+/// * one request to a service, with [`DeliveryInstructions::with_priority`](crate::DeliveryInstructions::with_priority)
+/// * the session of an impulse chain, with [`Impulse::with_priority`](crate::Impulse::with_priority)
+///
+/// A session that is started by a workflow or a scope takes the priority of
+/// the workflow or request that started it, or otherwise inherits the priority
+/// of its parent session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(pub i32);
+
+impl Priority {
+    pub const LOW: Self = Self(-100);
+    pub const NORMAL: Self = Self(0);
+    pub const HIGH: Self = Self(100);
+    /// Use this for safety-critical work, such as an emergency stop, that
+    /// needs to run before anything else.
+    pub const CRITICAL: Self = Self(i32::MAX);
+}
+
+/// The priority of a session, if it was given one.
+#[derive(Component, Clone, Copy, Debug)]
+pub(crate) struct SessionPriority(pub(crate) Priority);
+
+/// The priority that was set for every session of a workflow.
+#[derive(Component, Clone, Copy, Debug)]
+pub(crate) struct WorkflowPriority(pub(crate) Priority);
+
+/// This is inserted once any session has been given a priority. Until then the
+/// priorities of sessions are not looked up at all so that priorities cost
+/// nothing for applications that do not use them.
+#[derive(Resource, Default)]
+pub(crate) struct PrioritiesInUse;
+
+pub(crate) fn session_priority(session: Entity, world: &World) -> Priority {
+    world
+        .get::<SessionPriority>(session)
+        .map(|p| p.0)
+        .unwrap_or_default()
+}
+
+pub(crate) fn set_session_priority(session: Entity, priority: Priority, world: &mut World) {
+    let Some(mut session_mut) = world.get_entity_mut(session) else {
+        return;
+    };
+    session_mut.insert(SessionPriority(priority));
+    world.init_resource::<PrioritiesInUse>();
+}
+
+/// The priority of a request that is being sent to a service.
+pub(crate) fn request_priority(
+    instructions: Option<&DeliveryInstructions>,
+    session: Entity,
+    world: &World,
+) -> Priority {
+    instructions
+        .and_then(|instructions| instructions.priority)
+        .unwrap_or_else(|| session_priority(session, world))
+}
+
+/// Give a newly started session the priority of whatever started it. If an
+/// explicit priority was given (e.g. by the settings of a workflow or by the
+/// instructions of a request), the highest of those is used. Otherwise the
+/// priority is inherited from the parent session.
+pub(crate) fn inherit_priority(
+    session: Entity,
+    parent_session: Entity,
+    explicit: impl IntoIterator<Item = Option<Priority>>,
+    world: &mut World,
+) {
+    let priority = explicit
+        .into_iter()
+        .flatten()
+        .max()
+        .or_else(|| world.get::<SessionPriority>(parent_session).map(|p| p.0));
+
+    if let Some(priority) = priority {
+        set_session_priority(session, priority, world);
+    }
+}
+
+/// The priority to queue an operation with when it is given an input for
+/// `session`.
+pub(crate) fn queue_priority(session: Entity, world: &World) -> Priority {
+    if !world.contains_resource::<PrioritiesInUse>() {
+        return Priority::NORMAL;
+    }
+
+    session_priority(session, world)
+}
+
+/// Take the next operation that should be executed out of the roster queue.
+/// Operations of a higher priority than [`Priority::NORMAL`] go first, then
+/// the regular queue, then operations of a lower priority.
+pub(crate) fn pop_next_operation(roster: &mut OperationRoster) -> Option<Entity> {
+    if let Some(mut bucket) = roster.prioritized.last_entry() {
+        if *bucket.key() > Priority::NORMAL || roster.queue.is_empty() {
+            let next = bucket.get_mut().pop_front();
+            if bucket.get().is_empty() {
+                bucket.remove();
+            }
+            return next;
+        }
+    }
+
+    roster.queue.pop_front()
+}
+
+/// Find where an input for `session` should be inserted into an input queue
+/// whose newest element is at index 0 and whose oldest element is at the end.
+/// Inputs are taken from the end, so inputs of higher priority sessions are
+/// kept closer to the end.
+pub(crate) fn input_insertion_index(
+    session: Entity,
+    mut queued_sessions: impl DoubleEndedIterator<Item = Entity> + ExactSizeIterator,
+    world: &World,
+) -> usize {
+    if !world.contains_resource::<PrioritiesInUse>() {
+        return 0;
+    }
+
+    let priority = session_priority(session, world);
+    queued_sessions
+        .rposition(|queued| session_priority(queued, world) < priority)
+        .map(|index| index + 1)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, Priority};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_priority_runs_first() {
+        let mut context = TestingContext::minimal_plugins();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let make_workflow = |context: &mut TestingContext, name: &'static str, priority| {
+            let log = Arc::clone(&log);
+            context.spawn_workflow(move |scope: Scope<(), ()>, builder| {
+                scope
+                    .input
+                    .chain(builder)
+                    .map_block(move |_| log.lock().unwrap().push(name))
+                    .connect(scope.terminate);
+                WorkflowSettings::new().with_priority(priority)
+            })
+        };
+
+        let routine = make_workflow(&mut context, "routine", Priority::NORMAL);
+        let stop = make_workflow(&mut context, "stop", Priority::CRITICAL);
+
+        let mut routine_promise =
+            context.command(|commands| commands.request((), routine).take_response());
+        let mut stop_promise =
+            context.command(|commands| commands.request((), stop).take_response());
+
+        context.run_with_conditions(&mut routine_promise, Duration::from_secs(2));
+        context.run_with_conditions(&mut stop_promise, Duration::from_secs(2));
+        assert!(routine_promise.take().available().is_some());
+        assert!(stop_promise.take().available().is_some());
+        assert_eq!(*log.lock().unwrap(), ["stop", "routine"]);
+    }
+
+    #[test]
+    fn test_impulse_priority_applies_to_first_operation() {
+        let mut context = TestingContext::minimal_plugins();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let mut promises = [
+            ("routine", Priority::NORMAL),
+            ("other", Priority::NORMAL),
+            ("stop", Priority::CRITICAL),
+        ]
+        .map(|(name, priority)| {
+            let log = Arc::clone(&log);
+            context.command(|commands| {
+                commands
+                    .request(
+                        name,
+                        (move |name| log.lock().unwrap().push(name)).into_blocking_map_once(),
+                    )
+                    .with_priority(priority)
+                    .take_response()
+            })
+        });
+
+        for promise in &mut promises {
+            context.run_with_conditions(promise, Duration::from_secs(2));
+            assert!(promise.take().available().is_some());
+        }
+        assert_eq!(*log.lock().unwrap(), ["stop", "routine", "other"]);
+    }
+
+    #[test]
+    fn test_low_priority_runs_last() {
+        let mut context = TestingContext::minimal_plugins();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let make_workflow = |context: &mut TestingContext, name: &'static str, priority| {
+            let log = Arc::clone(&log);
+            context.spawn_workflow(move |scope: Scope<(), ()>, builder| {
+                scope
+                    .input
+                    .chain(builder)
+                    .map_block(move |_| log.lock().unwrap().push(name))
+                    .connect(scope.terminate);
+                WorkflowSettings::new().with_priority(priority)
+            })
+        };
+
+        let background = make_workflow(&mut context, "background", Priority::LOW);
+        let routine = make_workflow(&mut context, "routine", Priority::NORMAL);
+        let urgent = make_workflow(&mut context, "urgent", Priority::HIGH);
+
+        let mut promises = [background, routine, urgent].map(|workflow| {
+            context.command(|commands| commands.request((), workflow).take_response())
+        });
+
+        for promise in &mut promises {
+            context.run_with_conditions(promise, Duration::from_secs(2));
+            assert!(promise.take().available().is_some());
+        }
+        assert_eq!(*log.lock().unwrap(), ["urgent", "routine", "background"]);
+    }
+
+    #[test]
+    fn test_priority_in_serial_delivery() {
+        let mut context = TestingContext::minimal_plugins();
+        let delay = context.spawn_async_delayed_map(Duration::from_millis(10), |v: u32| v);
+        let workflow = context.spawn_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .then(delay)
+                .connect(scope.terminate);
+            WorkflowSettings::serial()
+        });
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let request = |context: &mut TestingContext, value: u32, priority| {
+            let log = Arc::clone(&log);
+            let promise = context.command(|commands| {
+                commands
+                    .request(value, workflow)
+                    .with_priority(priority)
+                    .map_block(move |v| log.lock().unwrap().push(v))
+                    .take_response()
+            });
+            // Let the request reach the delivery queue of the workflow.
+            context.run(1);
+            promise
+        };
+
+        let mut promises = [
+            request(&mut context, 1, Priority::NORMAL),
+            request(&mut context, 2, Priority::NORMAL),
+            request(&mut context, 3, Priority::HIGH),
+        ];
+
+        for promise in &mut promises {
+            context.run_with_conditions(promise, Duration::from_secs(2));
+            assert!(promise.take().available().is_some());
+        }
+
+        // The first request was already being delivered when the others
+        // arrived, but the high priority request skips ahead of the second one
+        // in the delivery queue.
+        assert_eq!(*log.lock().unwrap(), [1, 3, 2]);
+    }
+}
@@ -0,0 +1,266 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+//! Call services that live in a different [`World`] of the same process.
+//!
+//! Create a [`RemoteService`] from the world that owns the service, move it
+//! over to the world that wants to use the service, and then spawn a proxy
+//! service there with [`RemoteService::spawn_proxy`]:
+//!
+//! ```
+//! use bevy_impulse::{prelude::*, testing::*, RemoteService};
+//!
+//! let mut controller = TestingContext::minimal_plugins();
+//! let double = controller.spawn_io_workflow(|scope: Scope<f64, f64>, builder| {
+//!     scope.input.chain(builder).map_block(|v| 2.0 * v).connect(scope.terminate);
+//! });
+//! let remote = RemoteService::new(&mut controller.app.world, double);
+//!
+//! let mut sim = TestingContext::minimal_plugins();
+//! let proxy = sim.command(|commands| remote.spawn_proxy(commands));
+//! let mut promise = sim.command(|commands| commands.request(2.0, proxy).take_response());
+//!
+//! while promise.peek().is_pending() {
+//!     sim.run(1);
+//!     controller.run(1);
+//! }
+//! assert_eq!(promise.take().available(), Some(4.0));
+//! ```
+//!
+//! Both worlds need to keep flushing their workflows, e.g. with the
+//! [`ImpulsePlugin`](crate::ImpulsePlugin). Requests are passed into the remote
+//! world through the same channel that async tasks use to reach their world, so
+//! a request begins during the next flush of the remote world, and its response
+//! is delivered during the next flush of the calling world after it arrives.
+//!
+//! Cancellation is propagated in both directions. If the request to the proxy
+//! is cancelled, the request in the remote world is cancelled too. If the
+//! request in the remote world is cancelled or disposed, the request to the
+//! proxy is cancelled with a [`RemoteServiceError`].
+
+use bevy_ecs::prelude::{Commands, World};
+
+use futures::channel::oneshot;
+
+use thiserror::Error as ThisError;
+
+use crate::{
+    Cancellation, ChannelQueue, ChannelSender, OperationRoster, Promise, PromiseState, RequestExt,
+    RunCommandsOnWorldExt, Service, SpawnWorkflowExt,
+};
+
+/// A handle to a [`Service`] that lives in another [`World`]. This can be
+/// freely moved between threads and cloned.
+pub struct RemoteService<Request, Response> {
+    service: Service<Request, Response>,
+    sender: ChannelSender,
+}
+
+impl<Request, Response> Clone for RemoteService<Request, Response> {
+    fn clone(&self) -> Self {
+        Self {
+            service: self.service,
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<Request, Response> RemoteService<Request, Response>
+where
+    Request: 'static + Send + Sync,
+    Response: 'static + Send + Sync + Unpin,
+{
+    /// Make `service`, which lives in `world`, available to other worlds.
+    pub fn new(world: &mut World, service: Service<Request, Response>) -> Self {
+        let sender = world
+            .get_resource_or_insert_with(ChannelQueue::new)
+            .sender
+            .clone();
+        Self { service, sender }
+    }
+
+    /// The service inside of the remote world.
+    pub fn service(&self) -> Service<Request, Response> {
+        self.service
+    }
+
+    /// Spawn a service in the calling world that forwards each of its requests
+    /// to the remote service.
+    pub fn spawn_proxy(&self, commands: &mut Commands) -> Service<Request, Response> {
+        let remote = self.clone();
+        commands.spawn_io_workflow(move |scope, builder| {
+            scope
+                .input
+                .chain(builder)
+                .map_async(move |request| {
+                    let remote = remote.clone();
+                    async move { remote.call(request).await }
+                })
+                .cancel_on_err()
+                .connect(scope.terminate);
+        })
+    }
+
+    /// Send a request to the remote service and wait for its response. If this
+    /// future is dropped before the response arrives, the request in the remote
+    /// world will be cancelled.
+    pub async fn call(&self, request: Request) -> Result<Response, RemoteServiceError> {
+        let service = self.service;
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Box::new(
+                move |world: &mut World, _: &mut OperationRoster| {
+                    let promise = world
+                        .command(|commands| commands.request(request, service).take_response());
+                    // If the caller has stopped waiting then the promise will be
+                    // dropped here, which cancels the request.
+                    sender.send(promise).ok();
+                },
+            ))
+            .map_err(|_| RemoteServiceError::Disconnected)?;
+
+        let promise: Promise<Response> = receiver
+            .await
+            .map_err(|_| RemoteServiceError::Disconnected)?;
+
+        match promise.await {
+            PromiseState::Available(response) => Ok(response),
+            PromiseState::Cancelled(cancellation) => {
+                Err(RemoteServiceError::Cancelled(cancellation))
+            }
+            _ => Err(RemoteServiceError::Disposed),
+        }
+    }
+}
+
+/// The reasons that a request to a [`RemoteService`] can fail.
+#[derive(ThisError, Debug, Clone)]
+pub enum RemoteServiceError {
+    /// The remote world no longer exists.
+    #[error("the world of the remote service is gone")]
+    Disconnected,
+    /// The request was cancelled inside of the remote world.
+    #[error("the request was cancelled in the remote world: {0}")]
+    Cancelled(Cancellation),
+    /// The request was disposed inside of the remote world.
+    #[error("the request was disposed in the remote world")]
+    Disposed,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, RemoteService};
+    use futures::FutureExt;
+    use std::sync::{Arc, Mutex};
+
+    fn run_both<T>(
+        caller: &mut TestingContext,
+        remote: &mut TestingContext,
+        promise: &mut Promise<T>,
+    ) {
+        for _ in 0..100 {
+            if !promise.peek().is_pending() {
+                return;
+            }
+            caller.run(1);
+            remote.run(1);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_remote_service() {
+        let mut controller = TestingContext::minimal_plugins();
+        let at_least_four = controller.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+            scope
+                .input
+                .chain(builder)
+                .map_block(|value: u32| (value >= 4).then_some(value))
+                .cancel_on_none()
+                .connect(scope.terminate);
+        });
+        let remote = RemoteService::new(&mut controller.app.world, at_least_four);
+
+        let mut sim = TestingContext::minimal_plugins();
+        let proxy = sim.command(|commands| remote.spawn_proxy(commands));
+
+        let mut promise = sim.command(|commands| commands.request(4, proxy).take_response());
+        run_both(&mut sim, &mut controller, &mut promise);
+        assert_eq!(promise.take().available(), Some(4));
+
+        // A cancellation inside the remote world cancels the proxy request.
+        let mut promise = sim.command(|commands| commands.request(3, proxy).take_response());
+        run_both(&mut sim, &mut controller, &mut promise);
+        assert!(promise.peek().is_cancelled());
+    }
+
+    #[test]
+    fn test_remote_service_cancellation() {
+        /// Raises its flag when the task that owns it is dropped.
+        struct DropFlag(Arc<Mutex<bool>>);
+        impl Drop for DropFlag {
+            fn drop(&mut self) {
+                *self.0.lock().unwrap() = true;
+            }
+        }
+
+        let mut controller = TestingContext::minimal_plugins();
+        let started = Arc::new(Mutex::new(false));
+        let dropped = Arc::new(Mutex::new(false));
+        let never_finish = {
+            let started = Arc::clone(&started);
+            let dropped = Arc::clone(&dropped);
+            controller.command(|commands| {
+                commands.spawn_service(move |In(_): AsyncServiceInput<()>| {
+                    *started.lock().unwrap() = true;
+                    let flag = DropFlag(Arc::clone(&dropped));
+                    async move {
+                        let _flag = flag;
+                        std::future::pending::<()>().await;
+                    }
+                })
+            })
+        };
+        let remote = RemoteService::new(&mut controller.app.world, never_finish);
+
+        // Send the request to the remote world and let it start.
+        let mut call = Box::pin(remote.call(()));
+        assert!((&mut call).now_or_never().is_none());
+        for _ in 0..100 {
+            if *started.lock().unwrap() {
+                break;
+            }
+            controller.run(1);
+        }
+        assert!(*started.lock().unwrap());
+        assert!(!*dropped.lock().unwrap());
+
+        // Dropping the call, which is what happens when a request to the
+        // proxy is stopped, cancels the request in the remote world and drops
+        // its task.
+        drop(call);
+        for _ in 0..100 {
+            if *dropped.lock().unwrap() {
+                break;
+            }
+            controller.run(1);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(*dropped.lock().unwrap());
+        assert!(controller.no_unhandled_errors());
+    }
+}
@@ -25,7 +25,7 @@ use std::future::Future;
 
 use crate::{
     cancel_impulse, Cancellable, Detached, Impulse, ImpulseMarker, InputCommand, IntoAsyncMap,
-    IntoBlockingMapOnce, ProvideOnce, SessionStatus, StreamPack, UnusedTarget,
+    IntoBlockingMapOnce, ProvideOnce, SessionSpan, SessionStatus, StreamPack, UnusedTarget,
 };
 
 /// Extensions for creating impulse chains by making a request to a provider or
@@ -108,6 +108,7 @@ impl<'w, 's> RequestExt<'w, 's> for Commands<'w, 's> {
             // target gets despawned, this will also be despawned.
             .set_parent(target)
             .id();
+        self.entity(source).insert(SessionSpan::root(source));
 
         provider.connect(None, source, target, self);
         self.add(InputCommand {
@@ -117,6 +118,7 @@ impl<'w, 's> RequestExt<'w, 's> for Commands<'w, 's> {
         });
 
         Impulse {
+            session: source,
             source,
             target,
             commands: self,
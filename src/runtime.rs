@@ -0,0 +1,443 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use bevy_core::TaskPoolOptions;
+use bevy_ecs::{
+    prelude::{Commands, World},
+    schedule::{ExecutorKind, Schedule},
+};
+
+use std::time::Duration;
+
+#[cfg(not(feature = "single_threaded_async"))]
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::JoinHandle,
+};
+
+use crate::{flush_impulses, Promise, ProvideOnce, RequestExt, RunCommandsOnWorldExt, StreamPack};
+
+#[cfg(not(feature = "single_threaded_async"))]
+use crate::{ChannelQueue, DeferredRoster, OperationRoster, WakeQueue};
+
+/// Run workflows without setting up a bevy [`App`](bevy_app::App). The runtime
+/// owns a [`World`] and flushes its workflows whenever [`Self::flush`] is
+/// called, or continuously after [`Self::run_in_background`].
+///
+/// ```
+/// use bevy_impulse::{prelude::*, WorkflowRuntime};
+///
+/// let mut runtime = WorkflowRuntime::new();
+/// let double = runtime.command(|commands| {
+///     commands.spawn_io_workflow(|scope: Scope<i32, i32>, builder| {
+///         scope.input.chain(builder).map_block(|v| 2 * v).connect(scope.terminate);
+///     })
+/// });
+///
+/// let mut promise = runtime.request(5, double);
+/// runtime.run_while_pending(&mut promise);
+/// assert_eq!(promise.take().available(), Some(10));
+/// ```
+///
+/// The runtime has no [`Update`](bevy_app::Update) schedule or any other
+/// schedule besides the flush, so continuous services cannot be used with it.
+/// Those need an [`App`](bevy_app::App) whose schedules run their systems.
+pub struct WorkflowRuntime {
+    world: World,
+    schedule: Schedule,
+}
+
+impl Default for WorkflowRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorkflowRuntime {
+    /// Create a new runtime with an empty world. This also initializes the
+    /// global task pools that async services and compute services run on, if
+    /// they have not been initialized yet.
+    pub fn new() -> Self {
+        TaskPoolOptions::default().create_default_pools();
+        let mut schedule = Schedule::default();
+        schedule
+            .set_executor_kind(ExecutorKind::SingleThreaded)
+            .add_systems(flush_impulses());
+
+        Self {
+            world: World::new(),
+            schedule,
+        }
+    }
+
+    /// Use an existing world for the runtime.
+    pub fn with_world(mut self, world: World) -> Self {
+        self.world = world;
+        self
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Run commands on the world of the runtime, e.g. to spawn services and
+    /// workflows.
+    pub fn command<U>(&mut self, f: impl FnOnce(&mut Commands) -> U) -> U {
+        self.world.command(f)
+    }
+
+    /// Send a request to a provider. The request will begin during the next
+    /// flush. The promise can be awaited, but it will only make progress while
+    /// the runtime is being flushed.
+    pub fn request<P>(&mut self, request: P::Request, provider: P) -> Promise<P::Response>
+    where
+        P: ProvideOnce,
+        P::Request: 'static + Send + Sync,
+        P::Response: 'static + Send + Sync,
+        P::Streams: StreamPack,
+    {
+        self.command(|commands| commands.request(request, provider).take_response())
+    }
+
+    /// Execute everything that is ready to run in the workflows of this
+    /// runtime.
+    pub fn flush(&mut self) {
+        self.schedule.run(&mut self.world);
+    }
+
+    /// Keep flushing until the promise is no longer pending. While the promise
+    /// stays pending, the thread sleeps between flushes for a little longer
+    /// each time, up to a millisecond, so that waiting on async tasks does not
+    /// occupy a whole CPU.
+    pub fn run_while_pending<T>(&mut self, promise: &mut Promise<T>) {
+        let mut backoff = Duration::ZERO;
+        loop {
+            self.flush();
+            if !promise.peek().is_pending() {
+                return;
+            }
+
+            std::thread::sleep(backoff);
+            backoff = (2 * backoff).clamp(MIN_IDLE_BACKOFF, MAX_IDLE_BACKOFF);
+        }
+    }
+
+    /// Move the runtime onto its own thread, which will flush the workflows
+    /// whenever a new command arrives or an async task of the world makes
+    /// progress, and at least once per `period` while nothing happens. Use the
+    /// [`WorkflowRuntimeHandle`] to send requests to the runtime from any
+    /// thread or async context.
+    ///
+    /// This is not available with the `single_threaded_async` feature because
+    /// the async tasks of the world cannot be moved to another thread.
+    #[cfg(not(feature = "single_threaded_async"))]
+    pub fn run_in_background(mut self, period: Duration) -> WorkflowRuntimeHandle {
+        let (sender, receiver) = mpsc::channel::<RuntimeMessage>();
+        let waker = Waker::from(Arc::new(RuntimeWaker(sender.clone())));
+        let thread = std::thread::spawn(move || {
+            let mut cx = Context::from_waker(&waker);
+            loop {
+                if take_task_activity(&mut self.world, &mut cx) {
+                    // Collect any other messages that are already waiting
+                    // without blocking, then flush right away.
+                    while let Ok(message) = receiver.try_recv() {
+                        match message {
+                            RuntimeMessage::Job(job) => job(&mut self.world),
+                            RuntimeMessage::Wake => {}
+                            RuntimeMessage::Stop => return self,
+                        }
+                    }
+                    self.flush();
+                    continue;
+                }
+
+                match receiver.recv_timeout(period) {
+                    Ok(RuntimeMessage::Job(job)) => {
+                        job(&mut self.world);
+                        // Apply everything else that has arrived before
+                        // flushing so a burst of requests shares one flush.
+                        loop {
+                            match receiver.try_recv() {
+                                Ok(RuntimeMessage::Job(job)) => job(&mut self.world),
+                                Ok(RuntimeMessage::Wake) => {}
+                                Ok(RuntimeMessage::Stop) => return self,
+                                Err(_) => break,
+                            }
+                        }
+                    }
+                    Ok(RuntimeMessage::Wake) | Err(RecvTimeoutError::Timeout) => {}
+                    Ok(RuntimeMessage::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+                }
+                self.flush();
+            }
+            self
+        });
+
+        WorkflowRuntimeHandle {
+            sender,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Take in whatever async tasks have sent to the world since the last flush,
+/// so the next flush will handle it. Returns false if nothing has arrived, in
+/// which case the waker of `cx` will be woken as soon as something does.
+#[cfg(not(feature = "single_threaded_async"))]
+fn take_task_activity(world: &mut World, cx: &mut Context) -> bool {
+    let mut roster = OperationRoster::new();
+    let mut active = false;
+    while let Poll::Ready(Some(item)) = world
+        .get_resource_or_insert_with(ChannelQueue::new)
+        .receiver
+        .poll_recv(cx)
+    {
+        (item)(world, &mut roster);
+        active = true;
+    }
+    roster.process_deferals();
+
+    let mut wake_queue = world.get_resource_or_insert_with(WakeQueue::new);
+    while let Poll::Ready(Some(wakeable)) = wake_queue.receiver.poll_recv(cx) {
+        roster.awake(wakeable);
+        active = true;
+    }
+
+    world
+        .get_resource_or_insert_with(DeferredRoster::default)
+        .append(&mut roster);
+    active
+}
+
+/// Wakes the background thread of a runtime when an async task makes progress.
+#[cfg(not(feature = "single_threaded_async"))]
+struct RuntimeWaker(mpsc::Sender<RuntimeMessage>);
+
+#[cfg(not(feature = "single_threaded_async"))]
+impl Wake for RuntimeWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.send(RuntimeMessage::Wake).ok();
+    }
+}
+
+const MIN_IDLE_BACKOFF: Duration = Duration::from_micros(10);
+const MAX_IDLE_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Send commands and requests to a [`WorkflowRuntime`] that is running on a
+/// background thread. Dropping the handle stops the runtime.
+#[cfg(not(feature = "single_threaded_async"))]
+pub struct WorkflowRuntimeHandle {
+    sender: mpsc::Sender<RuntimeMessage>,
+    thread: Option<JoinHandle<WorkflowRuntime>>,
+}
+
+#[cfg(not(feature = "single_threaded_async"))]
+impl WorkflowRuntimeHandle {
+    /// Run commands on the world of the runtime. The promise will deliver the
+    /// output of `f` once the runtime has run it. If the runtime has stopped,
+    /// the promise will be disposed.
+    pub fn command<U>(&self, f: impl FnOnce(&mut Commands) -> U + 'static + Send) -> Promise<U>
+    where
+        U: 'static + Send + Sync,
+    {
+        let (sender, promise) = Promise::new();
+        self.sender
+            .send(RuntimeMessage::Job(Box::new(move |world: &mut World| {
+                sender.send(world.command(f)).ok();
+            })))
+            .ok();
+        promise
+    }
+
+    /// Send a request to a provider in the runtime. The returned promise can
+    /// be awaited from any async context. Dropping the promise before it is
+    /// finished will cancel the request.
+    pub fn request<P>(&self, request: P::Request, provider: P) -> Promise<P::Response>
+    where
+        P: ProvideOnce + 'static + Send,
+        P::Request: 'static + Send + Sync,
+        P::Response: 'static + Send + Sync,
+        P::Streams: StreamPack,
+    {
+        self.command(move |commands| commands.request(request, provider).take_response())
+            .flatten()
+    }
+
+    /// Stop the background thread and get back the runtime.
+    pub fn stop(mut self) -> WorkflowRuntime {
+        self.sender.send(RuntimeMessage::Stop).ok();
+        // The thread is only taken out here and in drop, so it is always
+        // still available at this point.
+        let thread = self
+            .thread
+            .take()
+            .expect("runtime thread was already taken");
+        match thread.join() {
+            Ok(runtime) => runtime,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+#[cfg(not(feature = "single_threaded_async"))]
+impl Drop for WorkflowRuntimeHandle {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.sender.send(RuntimeMessage::Stop).ok();
+            thread.join().ok();
+        }
+    }
+}
+
+#[cfg(not(feature = "single_threaded_async"))]
+enum RuntimeMessage {
+    Job(Box<dyn FnOnce(&mut World) + Send>),
+    Wake,
+    Stop,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, WorkflowRuntime};
+
+    #[test]
+    fn test_workflow_runtime_flush_on_demand() {
+        let mut runtime = WorkflowRuntime::new();
+        let service = runtime.command(|commands| {
+            commands.spawn_service(|In(srv): AsyncServiceInput<u32>| async move { srv.request + 1 })
+        });
+        let workflow = runtime.command(|commands| {
+            commands.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+                scope
+                    .input
+                    .chain(builder)
+                    .then(service)
+                    .map_block(|v| 10 * v)
+                    .connect(scope.terminate);
+            })
+        });
+
+        let mut promise = runtime.request(2, workflow);
+        runtime.run_while_pending(&mut promise);
+        assert_eq!(promise.take().available(), Some(30));
+    }
+
+    #[derive(Resource, Default)]
+    struct FlushCount(usize);
+
+    #[test]
+    fn test_workflow_runtime_waits_between_flushes() {
+        let mut runtime = WorkflowRuntime::new();
+        runtime.world_mut().init_resource::<FlushCount>();
+        runtime
+            .schedule
+            .add_systems(|mut count: ResMut<FlushCount>| count.0 += 1);
+
+        let delay = Duration::from_millis(20);
+        let service = runtime.command(|commands| {
+            commands.spawn_service(move |In(srv): AsyncServiceInput<u32>| async move {
+                let never = async_std::future::pending::<()>();
+                let _ = async_std::future::timeout(delay, never).await;
+                srv.request
+            })
+        });
+
+        let mut promise = runtime.request(5, service);
+        runtime.run_while_pending(&mut promise);
+        assert_eq!(promise.take().available(), Some(5));
+
+        // Flushing without a pause would run the schedule many thousands of
+        // times while waiting for the delay.
+        let flushes = runtime.world().resource::<FlushCount>().0;
+        assert!(flushes < 200, "{flushes}");
+    }
+
+    #[cfg(not(feature = "single_threaded_async"))]
+    #[test]
+    fn test_workflow_runtime_in_background() {
+        let mut runtime = WorkflowRuntime::new();
+        let service = runtime.command(|commands| {
+            commands.spawn_service(|In(srv): AsyncServiceInput<u32>| async move { srv.request * 3 })
+        });
+
+        let handle = runtime.run_in_background(Duration::from_millis(1));
+        let results: Vec<_> = (0..5)
+            .map(|i| handle.request(i, service))
+            .map(|promise| futures::executor::block_on(promise).available())
+            .collect();
+        assert_eq!(results, [0, 3, 6, 9, 12].map(Some));
+
+        // Commands can also be sent into the running world.
+        let doubler = futures::executor::block_on(handle.command(|commands| {
+            commands.spawn_service(|In(v): BlockingServiceInput<u32>| 2 * v.request)
+        }))
+        .available()
+        .unwrap();
+        let doubled = futures::executor::block_on(handle.request(21, doubler));
+        assert_eq!(doubled.available(), Some(42));
+
+        let runtime = handle.stop();
+        assert!(runtime
+            .world()
+            .get_resource::<crate::UnhandledErrors>()
+            .is_none_or(|errors| errors.is_empty()));
+    }
+
+    #[cfg(not(feature = "single_threaded_async"))]
+    #[test]
+    fn test_workflow_runtime_wakes_for_async_tasks() {
+        let mut runtime = WorkflowRuntime::new();
+        let delay = Duration::from_millis(10);
+        let service = runtime.command(|commands| {
+            commands.spawn_service(move |In(srv): AsyncServiceInput<u32>| async move {
+                let never = async_std::future::pending::<()>();
+                let _ = async_std::future::timeout(delay, never).await;
+                srv.request
+            })
+        });
+        let workflow = runtime.command(|commands| {
+            commands.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+                scope
+                    .input
+                    .chain(builder)
+                    .then(service)
+                    .then(service)
+                    .connect(scope.terminate);
+            })
+        });
+
+        // The period is far longer than the test is allowed to take, so the
+        // runtime must be woken up by the tasks themselves.
+        let handle = runtime.run_in_background(Duration::from_secs(60));
+        let start = std::time::Instant::now();
+        let result = futures::executor::block_on(handle.request(7, workflow));
+        assert_eq!(result.available(), Some(7));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -16,8 +16,8 @@
 */
 
 use crate::{
-    AddOperation, OperateService, ProvideOnce, Provider, RunCommandsOnWorldExt, StreamAvailability,
-    StreamOf, StreamPack,
+    AddOperation, OperateService, Priority, ProvideOnce, Provider, RunCommandsOnWorldExt,
+    StreamAvailability, StreamOf, StreamPack,
 };
 
 use bevy_app::prelude::App;
@@ -42,6 +42,9 @@ pub use async_srv::*;
 mod blocking;
 pub use blocking::*;
 
+mod compute;
+pub use compute::*;
+
 mod continuous;
 pub use continuous::*;
 
@@ -264,6 +267,7 @@ pub struct DeliveryInstructions {
     pub(crate) label: DeliveryLabelId,
     pub(crate) preempt: bool,
     pub(crate) ensure: bool,
+    pub(crate) priority: Option<Priority>,
 }
 
 /// Newtype to store types that implement `DeliveryLabel`
@@ -279,6 +283,7 @@ impl DeliveryInstructions {
             label: DeliveryLabelId(label.intern()),
             preempt: false,
             ensure: false,
+            priority: None,
         }
     }
 
@@ -337,6 +342,20 @@ impl DeliveryInstructions {
         self.ensure = ensure;
         self
     }
+
+    /// Give this request a [`Priority`]. Requests that are waiting in the queue
+    /// of a service are delivered in order of priority. When the service is a
+    /// workflow, the session that runs the request will also run with this
+    /// priority.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Check the priority that was set for the request, if any.
+    pub fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
 }
 
 impl<L: DeliveryLabel> From<L> for DeliveryInstructions {
@@ -363,6 +382,11 @@ pub trait AsDeliveryInstructions {
 
     /// Decide at runtime whether to be ensured.
     fn with_ensured(self, ensure: bool) -> DeliveryInstructions;
+
+    /// Give the delivery a [priority][1].
+    ///
+    /// [1]: DeliveryInstructions::with_priority
+    fn with_priority(self, priority: Priority) -> DeliveryInstructions;
 }
 
 impl<T: Into<DeliveryInstructions>> AsDeliveryInstructions for T {
@@ -381,6 +405,10 @@ impl<T: Into<DeliveryInstructions>> AsDeliveryInstructions for T {
     fn with_ensured(self, ensure: bool) -> DeliveryInstructions {
         self.into().with_ensured(ensure)
     }
+
+    fn with_priority(self, priority: Priority) -> DeliveryInstructions {
+        self.into().with_priority(priority)
+    }
 }
 
 /// This trait extends the Commands interface so that services can spawned from
@@ -18,12 +18,13 @@
 use crate::{
     async_execution::{spawn_task, task_cancel_sender},
     dispose_for_despawned_service, emit_disposal, insert_new_order, pop_next_delivery,
+    record_delivery_queue, report_unhandled_error, request_priority,
     service::service_builder::{ParallelChosen, SerialChosen},
-    AsyncService, AsyncServiceInput, Blocker, Channel, ChannelQueue, ChooseAsyncServiceDelivery,
-    Deliver, Delivery, DeliveryOrder, DeliveryUpdate, Disposal, Input, IntoService, ManageInput,
-    OperateTask, OperationError, OperationRequest, OperationResult, OperationRoster, OrBroken,
-    Sendish, ServiceBuilder, ServiceBundle, ServiceRequest, ServiceTrait, SingleTargetStorage,
-    StopTask, StopTaskFailure, StreamPack, UnhandledErrors,
+    AsyncService, AsyncServiceInput, Blocker, CancellationToken, Channel, ChannelQueue,
+    ChooseAsyncServiceDelivery, Deliver, Delivery, DeliveryOrder, DeliveryUpdate, Disposal, Input,
+    IntoService, ManageInput, OperateTask, OperationError, OperationRequest, OperationResult,
+    OperationRoster, OrBroken, Sendish, ServiceBuilder, ServiceBundle, ServiceRequest,
+    ServiceTrait, SingleTargetStorage, StopTask, StopTaskFailure, StreamBackpressure, StreamPack,
 };
 
 use bevy_ecs::{
@@ -113,6 +114,7 @@ where
             data: request,
         } = source_mut.take_input::<Request>()?;
         let task_id = world.spawn(()).id();
+        let priority = request_priority(instructions.as_ref(), session, world);
 
         let Some(mut delivery) = world.get_mut::<Delivery<Request>>(provider) else {
             // The async service's Delivery component has been removed so we should treat the request as cancelled.
@@ -128,8 +130,10 @@ where
                 task_id,
                 request,
                 instructions,
+                priority,
             },
         );
+        record_delivery_queue::<Request>(provider, world);
 
         let (request, blocker) = match update {
             DeliveryUpdate::Immediate { blocking, request } => {
@@ -175,13 +179,13 @@ where
                         });
 
                     if let Err(OperationError::Broken(backtrace)) = result {
-                        world
-                            .get_resource_or_insert_with(UnhandledErrors::default)
-                            .stop_tasks
-                            .push(StopTaskFailure {
+                        report_unhandled_error(
+                            StopTaskFailure {
                                 task: stop.task_id,
                                 backtrace,
-                            });
+                            },
+                            world,
+                        );
 
                         // Immediately queue up an unblocking, otherwise the next
                         // task will never be able to run.
@@ -282,8 +286,14 @@ where
         .get_resource_or_insert_with(ChannelQueue::new)
         .sender
         .clone();
-    let channel = Channel::new(source, session, sender.clone());
+    let backpressure = world
+        .get::<StreamBackpressure>(provider)
+        .map(|b| b.0)
+        .unwrap_or_default();
+    let channel =
+        Channel::new(source, session, sender.clone()).with_stream_backpressure(backpressure);
     let streams = channel.for_streams::<Streams>(world)?;
+    let cancellation_token = CancellationToken::new();
     let job = service.run(
         AsyncService {
             request,
@@ -292,6 +302,7 @@ where
             provider,
             source,
             session,
+            cancellation_token: cancellation_token.clone(),
         },
         world,
     );
@@ -320,6 +331,7 @@ where
         target,
         task,
         cancel_sender,
+        cancellation_token,
         blocker,
         sender,
     )
@@ -0,0 +1,201 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use bevy_ecs::{
+    prelude::{In, Res, Resource},
+    system::{EntityCommands, IntoSystem},
+    world::EntityWorldMut,
+};
+use bevy_tasks::{ComputeTaskPool, Task, TaskPool};
+
+use std::sync::Arc;
+
+use crate::{
+    AsyncService, AsyncServiceInput, ComputeService, IntoService, IsAsyncService, StreamPack,
+};
+
+/// Insert this resource to run the functions of compute services on a
+/// dedicated thread pool instead of the shared
+/// [`ComputeTaskPool`](bevy_tasks::ComputeTaskPool).
+///
+/// ```
+/// use bevy_impulse::ComputeServicePool;
+/// use bevy_tasks::TaskPoolBuilder;
+///
+/// let pool = ComputeServicePool::new(
+///     TaskPoolBuilder::new()
+///         .num_threads(2)
+///         .thread_name("planning".to_owned())
+///         .build(),
+/// );
+/// ```
+#[derive(Resource, Clone)]
+pub struct ComputeServicePool(Arc<TaskPool>);
+
+impl ComputeServicePool {
+    pub fn new(pool: TaskPool) -> Self {
+        Self(Arc::new(pool))
+    }
+}
+
+pub struct Compute<M>(std::marker::PhantomData<fn(M)>);
+
+impl<Request, Response, Streams, Job, M, Sys>
+    IntoService<Compute<(Request, Response, Streams, Job, M)>> for Sys
+where
+    Sys: IntoSystem<ComputeService<Request, Streams>, Job, M>,
+    Job: FnOnce() -> Response + 'static + Send,
+    Request: 'static + Send + Sync,
+    Response: 'static + Send + Sync,
+    Streams: StreamPack,
+{
+    type Request = Request;
+    type Response = Response;
+    type Streams = Streams;
+    type DefaultDeliver = ();
+
+    fn insert_service_commands(self, entity_commands: &mut EntityCommands) {
+        peel_compute::<Request, Streams>
+            .pipe(self)
+            .pipe(spawn_compute::<Job, Response>)
+            .insert_service_commands(entity_commands)
+    }
+
+    fn insert_service_mut(self, entity_mut: &mut EntityWorldMut) {
+        peel_compute::<Request, Streams>
+            .pipe(self)
+            .pipe(spawn_compute::<Job, Response>)
+            .insert_service_mut(entity_mut)
+    }
+}
+
+impl<Request, Response, Streams, Job, M, Sys>
+    IsAsyncService<Compute<(Request, Response, Streams, Job, M)>> for Sys
+where
+    Sys: IntoSystem<ComputeService<Request, Streams>, Job, M>,
+    Job: FnOnce() -> Response + 'static + Send,
+    Request: 'static + Send + Sync,
+    Response: 'static + Send + Sync,
+    Streams: StreamPack,
+{
+}
+
+fn peel_compute<Request, Streams: StreamPack>(
+    In(AsyncService {
+        request,
+        streams,
+        provider,
+        source,
+        session,
+        cancellation_token,
+        ..
+    }): AsyncServiceInput<Request, Streams>,
+) -> ComputeService<Request, Streams> {
+    ComputeService {
+        request,
+        streams,
+        provider,
+        source,
+        session,
+        cancellation_token,
+    }
+}
+
+/// Dropping the task before the job begins will prevent the job from running,
+/// so cancelled requests do not use up the pool.
+fn spawn_compute<Job, Response>(
+    In(job): In<Job>,
+    pool: Option<Res<ComputeServicePool>>,
+) -> Task<Response>
+where
+    Job: FnOnce() -> Response + 'static + Send,
+    Response: 'static + Send,
+{
+    let future = async move { job() };
+    match pool {
+        Some(pool) => pool.0.spawn(future),
+        None => ComputeTaskPool::get().spawn(future),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, ComputeServicePool};
+    use bevy_ecs::prelude::{Res, Resource};
+    use bevy_tasks::TaskPoolBuilder;
+
+    #[derive(Resource)]
+    struct Offset(u32);
+
+    #[test]
+    fn test_compute_service() {
+        let mut context = TestingContext::minimal_plugins();
+        context.app.insert_resource(Offset(10));
+
+        let service = context.command(|commands| {
+            commands.spawn_service(
+                |In(srv): ComputeServiceInput<u32, StreamOf<u32>>, offset: Res<Offset>| {
+                    let offset = offset.0;
+                    move || {
+                        srv.streams.send(srv.request);
+                        srv.request + offset
+                    }
+                },
+            )
+        });
+
+        let mut recipient = context.command(|commands| commands.request(5_u32, service).take());
+        context.run_with_conditions(&mut recipient.response, Duration::from_secs(2));
+        assert!(recipient
+            .response
+            .take()
+            .available()
+            .is_some_and(|v| v == 15));
+        assert_eq!(recipient.streams.try_recv().unwrap(), 5);
+        assert!(context.no_unhandled_errors());
+    }
+
+    #[test]
+    fn test_compute_service_on_dedicated_pool() {
+        let mut context = TestingContext::minimal_plugins();
+        context.app.insert_resource(ComputeServicePool::new(
+            TaskPoolBuilder::new()
+                .num_threads(1)
+                .thread_name("compute test".to_owned())
+                .build(),
+        ));
+
+        let service = context.command(|commands| {
+            commands.spawn_service(
+                (|In(srv): ComputeServiceInput<u32>| {
+                    move || {
+                        let name = std::thread::current().name().map(str::to_owned);
+                        (srv.request * 2, name)
+                    }
+                })
+                .serial(),
+            )
+        });
+
+        let mut promise =
+            context.command(|commands| commands.request(4_u32, service).take_response());
+        context.run_with_conditions(&mut promise, Duration::from_secs(2));
+        let (value, thread) = promise.take().available().unwrap();
+        assert_eq!(value, 8);
+        assert!(thread.is_some_and(|name| name.starts_with("compute test")));
+    }
+}
@@ -28,13 +28,14 @@ use smallvec::SmallVec;
 use std::collections::HashMap;
 
 use crate::{
-    dispose_for_despawned_service, emit_disposal, insert_new_order, pop_next_delivery, Blocker,
-    Broken, ContinuousService, ContinuousServiceInput, DeferredRoster, Deliver, Delivery,
-    DeliveryOrder, DeliveryUpdate, Disposal, Input, IntoContinuousService, IntoServiceBuilder,
-    ManageInput, OperationCleanup, OperationError, OperationReachability, OperationRequest,
-    OperationResult, OperationRoster, OrBroken, ProviderStorage, ReachabilityResult, ScopeStorage,
-    ServiceBuilder, ServiceBundle, ServiceRequest, ServiceTrait, SingleTargetStorage, StreamOf,
-    StreamPack, StreamTargetMap, UnhandledErrors,
+    dispose_for_despawned_service, emit_disposal, insert_new_order, pop_next_delivery,
+    record_delivery_queue, report_unhandled_error, request_priority, Blocker, Broken,
+    ContinuousService, ContinuousServiceInput, DeferredRoster, Deliver, Delivery, DeliveryOrder,
+    DeliveryUpdate, Disposal, Input, IntoContinuousService, IntoServiceBuilder, ManageInput,
+    OperationCleanup, OperationError, OperationReachability, OperationRequest, OperationResult,
+    OperationRoster, OrBroken, ProviderStorage, ReachabilityResult, ScopeStorage, ServiceBuilder,
+    ServiceBundle, ServiceRequest, ServiceTrait, SingleTargetStorage, StreamOf, StreamPack,
+    StreamTargetMap,
 };
 
 pub use bevy_ecs::schedule::SystemConfigs;
@@ -626,13 +627,13 @@ where
                 remove.push((index, provider));
                 let r = try_give_response(source, session, data, world, &mut deferred);
                 if let Err(OperationError::Broken(backtrace)) = r {
-                    world
-                        .get_resource_or_insert_with(UnhandledErrors::default)
-                        .broken
-                        .push(Broken {
+                    report_unhandled_error(
+                        Broken {
                             node: provider,
                             backtrace,
-                        });
+                        },
+                        world,
+                    );
                 }
 
                 if Streams::has_streams() {
@@ -663,13 +664,13 @@ where
             for (index, provider) in remove {
                 let r = try_retire_request::<Request>(provider, index, world, &mut deferred);
                 if let Err(OperationError::Broken(backtrace)) = r {
-                    world
-                        .get_resource_or_insert_with(UnhandledErrors::default)
-                        .broken
-                        .push(Broken {
+                    report_unhandled_error(
+                        Broken {
                             node: provider,
                             backtrace,
-                        });
+                        },
+                        world,
+                    );
                 }
             }
         });
@@ -739,6 +740,7 @@ where
             data: request,
         } = source_mut.take_input::<Request>()?;
         let task_id = world.spawn(()).set_parent(source).id();
+        let priority = request_priority(instructions.as_ref(), session, world);
 
         let Some(mut delivery) = world.get_mut::<Delivery<Request>>(provider) else {
             dispose_for_despawned_service(provider, world, roster);
@@ -753,8 +755,10 @@ where
                 task_id,
                 request,
                 instructions,
+                priority,
             },
         );
+        record_delivery_queue::<Request>(provider, world);
 
         let (request, blocker) = match update {
             DeliveryUpdate::Immediate { blocking, request } => {
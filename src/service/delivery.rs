@@ -16,8 +16,9 @@
 */
 
 use crate::{
-    Blocker, DeliveryInstructions, DeliveryLabelId, OperationCleanup, OperationReachability,
-    OperationResult, OperationRoster, OrBroken, ProviderStorage, ReachabilityResult,
+    record_queue_depth, Blocker, DeliveryInstructions, DeliveryLabelId, OperationCleanup,
+    OperationReachability, OperationResult, OperationRoster, OrBroken, Priority, ProviderStorage,
+    ReachabilityResult,
 };
 
 use bevy_ecs::prelude::{Component, Entity, World};
@@ -36,7 +37,7 @@ where
     Request: 'static + Send + Sync,
 {
     let mut delivery = world.get_mut::<Delivery<Request>>(provider)?;
-    match &mut *delivery {
+    let next = match &mut *delivery {
         Delivery::Serial(serial) => pop_next_delivery_impl::<Request>(provider, serial, serve_next),
         Delivery::Parallel(parallel) => {
             let label = label.expect(
@@ -50,7 +51,23 @@ where
             );
             pop_next_delivery_impl::<Request>(provider, serial, serve_next)
         }
-    }
+    };
+
+    record_delivery_queue::<Request>(provider, world);
+    next
+}
+
+/// Let the [`WorkflowMetrics`](crate::WorkflowMetrics) know how many requests
+/// are waiting in the delivery queue of a provider.
+pub(crate) fn record_delivery_queue<Request>(provider: Entity, world: &mut World)
+where
+    Request: 'static + Send + Sync,
+{
+    let Some(delivery) = world.get::<Delivery<Request>>(provider) else {
+        return;
+    };
+    let depth = delivery.queue_depth();
+    record_queue_depth(provider, depth, world);
 }
 
 fn pop_next_delivery_impl<Request>(
@@ -71,6 +88,7 @@ where
         task_id,
         request,
         instructions,
+        priority: _,
     } = serial.queue.pop_front()?;
 
     let blocker = Blocker {
@@ -110,6 +128,8 @@ pub(crate) struct DeliveryOrder<Request> {
     pub(crate) task_id: Entity,
     pub(crate) request: Request,
     pub(crate) instructions: Option<DeliveryInstructions>,
+    /// Orders with a higher priority are delivered first.
+    pub(crate) priority: Priority,
 }
 
 struct ActiveDelivery {
@@ -138,6 +158,18 @@ impl<Request> Delivery<Request> {
         Delivery::Parallel(ParallelDelivery::<Request>::default())
     }
 
+    /// The number of requests that are waiting to be delivered.
+    pub(crate) fn queue_depth(&self) -> usize {
+        match self {
+            Self::Serial(serial) => serial.queue.len(),
+            Self::Parallel(parallel) => parallel
+                .labeled
+                .values()
+                .map(|serial| serial.queue.len())
+                .sum(),
+        }
+    }
+
     pub(crate) fn contains_session(r: &OperationReachability) -> ReachabilityResult
     where
         Request: 'static + Send + Sync,
@@ -176,6 +208,7 @@ impl<Request> Delivery<Request> {
             Delivery::Parallel(parallel) => parallel.cleanup(clean.cleanup.session),
         }
 
+        record_delivery_queue::<Request>(provider, clean.world);
         Ok(())
     }
 }
@@ -192,6 +225,15 @@ impl<Request> SerialDelivery<Request> {
     fn cleanup(&mut self, session: Entity) {
         self.queue.retain(|order| order.session != session);
     }
+    /// Queue an order behind every order with the same or higher priority.
+    fn insert_by_priority(&mut self, order: DeliveryOrder<Request>) {
+        let index = self
+            .queue
+            .iter()
+            .position(|queued| queued.priority < order.priority)
+            .unwrap_or(self.queue.len());
+        self.queue.insert(index, order);
+    }
 }
 
 impl<Request> Default for SerialDelivery<Request> {
@@ -296,7 +338,7 @@ fn insert_serial_order<Request>(
     };
 
     let Some(incoming_instructions) = order.instructions else {
-        serial.queue.push_back(order);
+        serial.insert_by_priority(order);
         return DeliveryUpdate::Queued {
             cancelled: SmallVec::new(),
             stop: None,
@@ -334,7 +376,7 @@ fn insert_serial_order<Request>(
         }
     }
 
-    serial.queue.push_back(order);
+    serial.insert_by_priority(order);
     let label = Some(incoming_instructions.label);
 
     DeliveryUpdate::Queued {
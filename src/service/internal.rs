@@ -26,8 +26,9 @@ use anyhow::anyhow;
 use std::{collections::VecDeque, sync::Arc};
 
 use crate::{
-    dispose_for_despawned_service, DeliveryInstructions, MiscellaneousFailure, OperationError,
-    OperationRequest, OperationRoster, PendingOperationRequest, ServiceTrait, UnhandledErrors,
+    dispose_for_despawned_service, report_unhandled_error, DeliveryInstructions,
+    MiscellaneousFailure, OperationError, OperationRequest, OperationRoster,
+    PendingOperationRequest, ServiceTrait,
 };
 
 pub struct ServiceRequest<'a> {
@@ -171,14 +172,12 @@ fn service_hook<Srv: ServiceTrait>(
             // Do nothing
         }
         Err(OperationError::Broken(backtrace)) => {
-            world.get_resource_or_insert_with(UnhandledErrors::default)
-                .miscellaneous
-                .push(MiscellaneousFailure {
+            report_unhandled_error(MiscellaneousFailure {
                     error: Arc::new(anyhow!(
                         "Failed to serve: provider {provider:?}, source {source:?}, target {target:?}",
                     )),
                     backtrace,
-                });
+                }, world);
         }
     }
 }
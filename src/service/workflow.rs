@@ -16,12 +16,13 @@
 */
 
 use crate::{
-    begin_scope, dispose_for_despawned_service, emit_disposal, insert_new_order, pop_next_delivery,
-    Blocker, Cancel, Cancellation, Deliver, Delivery, DeliveryOrder, DeliveryUpdate, Disposal,
-    ExitTarget, ExitTargetStorage, Input, ManageInput, OperationCleanup, OperationError,
-    OperationReachability, OperationRequest, OperationResult, OperationRoster, OrBroken,
-    ParentSession, ProviderStorage, ReachabilityResult, Service, ServiceRequest, ServiceTrait,
-    SessionStatus, SingleTargetStorage, StreamPack,
+    begin_scope, dispose_for_despawned_service, emit_disposal, inherit_priority, insert_new_order,
+    pop_next_delivery, record_delivery_queue, request_priority, Blocker, Cancel, Cancellation,
+    Deliver, Delivery, DeliveryOrder, DeliveryUpdate, Disposal, ExitTarget, ExitTargetStorage,
+    Input, ManageInput, OperationCleanup, OperationError, OperationReachability, OperationRequest,
+    OperationResult, OperationRoster, OrBroken, ParentSession, ProviderStorage, ReachabilityResult,
+    Service, ServiceRequest, ServiceTrait, SessionSpan, SessionStatus, SingleTargetStorage,
+    StreamPack, WorkflowPriority,
 };
 
 use bevy_ecs::prelude::{Component, Entity, World};
@@ -80,6 +81,10 @@ impl WorkflowStorage {
     pub(crate) fn new(scope: Entity) -> Self {
         Self { scope }
     }
+
+    pub(crate) fn scope(&self) -> Entity {
+        self.scope
+    }
 }
 
 pub(crate) struct WorkflowService<Request, Response, Streams> {
@@ -121,6 +126,8 @@ where
         let scoped_session = world
             .spawn((ParentSession::new(session), SessionStatus::Active))
             .id();
+        let span = SessionSpan::new(scoped_session, session, Some(provider), world);
+        world.entity_mut(scoped_session).insert(span);
 
         let result = serve_workflow_impl::<Request, Response, Streams>(
             request,
@@ -170,6 +177,14 @@ where
     Streams: StreamPack,
 {
     let workflow = *world.get::<WorkflowStorage>(provider).or_broken()?;
+    let workflow_priority = world.get::<WorkflowPriority>(provider).map(|p| p.0);
+    inherit_priority(
+        scoped_session,
+        parent_session,
+        [workflow_priority, instructions.and_then(|i| i.priority)],
+        world,
+    );
+    let priority = request_priority(instructions.as_ref(), parent_session, world);
     let Some(mut delivery) = world.get_mut::<Delivery<Request>>(provider) else {
         // The workflow has been despawned, so we should treat the request
         // as cancelled.
@@ -185,8 +200,10 @@ where
             task_id: scoped_session,
             request,
             instructions,
+            priority,
         },
     );
+    record_delivery_queue::<Request>(provider, world);
 
     let (request, blocker) = match update {
         DeliveryUpdate::Immediate { blocking, request } => {
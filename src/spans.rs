@@ -0,0 +1,383 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+//! Structured [`tracing`] spans for sessions and operations.
+//!
+//! Every session gets an `INFO` level `session` span which stays open until
+//! the session entity is despawned. The execution of each operation gets a
+//! `DEBUG` level span whose parent is the span of the session that the
+//! operation is working on, so a tracing subscriber will show each session as
+//! a nested trace of operation executions. Cancellations and disposals get
+//! `DEBUG` level spans as well.
+//!
+//! Every span carries these fields when they are known:
+//! * `workflow`: the service entity of the workflow that the operation belongs to
+//! * `session`: the session that is being worked on
+//! * `operation`: the entity of the operation
+//! * `kind`: the type of operation, e.g. `BlockingMap`
+//! * `label`: the [`OperationLabel`](crate::OperationLabel) of the operation
+
+use bevy_ecs::prelude::{Component, Entity, World};
+use bevy_hierarchy::Parent;
+
+use tracing::{span::Id, Span};
+
+use crate::{
+    ActiveTasksStorage, CancellationCause, DisposalCause, InputTypeIndicator, OperationLabel,
+    OperationType, ScopeStorage, WorkflowStorage,
+};
+
+/// Keeps the span of a session open for as long as the session exists.
+#[derive(Component)]
+pub(crate) struct SessionSpan(Span);
+
+impl SessionSpan {
+    /// Create the span for a new session. The new span will be nested inside
+    /// the span of its parent session.
+    pub(crate) fn new(
+        session: Entity,
+        parent_session: Entity,
+        workflow: Option<Entity>,
+        world: &World,
+    ) -> Self {
+        Self(tracing::info_span!(
+            parent: session_span_id(parent_session, world),
+            "session",
+            workflow = ?workflow,
+            ?session,
+        ))
+    }
+
+    /// Create the span for a new session of a scope.
+    pub(crate) fn for_scope(
+        session: Entity,
+        parent_session: Entity,
+        scope: Entity,
+        world: &World,
+    ) -> Self {
+        Self::new(session, parent_session, find_workflow(scope, world), world)
+    }
+
+    /// Create the span for a session that does not have a parent, e.g. the
+    /// session of an impulse chain.
+    pub(crate) fn root(session: Entity) -> Self {
+        Self(tracing::info_span!(parent: None, "session", ?session))
+    }
+}
+
+/// Check whether operation spans would be collected right now.
+pub(crate) fn operation_spans_enabled() -> bool {
+    tracing::enabled!(tracing::Level::DEBUG)
+}
+
+/// Create a span for executing an operation.
+pub(crate) fn execute_operation_span(source: Entity, world: &World) -> Span {
+    if !operation_spans_enabled() {
+        return Span::none();
+    }
+
+    let session = peek_session(source, world);
+    tracing::debug_span!(
+        parent: session.and_then(|session| session_span_id(session, world)),
+        "execute_operation",
+        workflow = ?find_workflow(source, world),
+        session = ?session,
+        operation = ?source,
+        kind = operation_kind(source, world),
+        label = operation_label(source, world),
+    )
+}
+
+/// Create a span for waking up an async task.
+pub(crate) fn awaken_task_span(task: Entity, world: &World) -> Span {
+    if !operation_spans_enabled() {
+        return Span::none();
+    }
+
+    let node = world.get::<Parent>(task).map(|parent| parent.get());
+    let session = node.and_then(|node| {
+        world
+            .get::<ActiveTasksStorage>(node)
+            .and_then(|tasks| tasks.session_of(task))
+    });
+
+    tracing::debug_span!(
+        parent: session.and_then(|session| session_span_id(session, world)),
+        "awaken_task",
+        workflow = ?node.and_then(|node| find_workflow(node, world)),
+        session = ?session,
+        operation = ?node,
+        task = ?task,
+        kind = node.map(|node| operation_kind(node, world)).unwrap_or("unknown"),
+        label = node.and_then(|node| operation_label(node, world)),
+    )
+}
+
+/// Create a span for delivering a cancellation to its target.
+pub(crate) fn cancel_span(
+    origin: Entity,
+    target: Entity,
+    session: Option<Entity>,
+    cause: &CancellationCause,
+    world: &World,
+) -> Span {
+    tracing::debug_span!(
+        parent: session.and_then(|session| session_span_id(session, world)),
+        "cancel",
+        workflow = ?find_workflow(target, world),
+        session = ?session,
+        operation = ?origin,
+        kind = operation_kind(origin, world),
+        label = operation_label(origin, world),
+        target = ?target,
+        cause = cancellation_cause_kind(cause),
+    )
+}
+
+/// Create a span for emitting a disposal.
+pub(crate) fn disposal_span(
+    source: Entity,
+    session: Entity,
+    cause: &DisposalCause,
+    world: &World,
+) -> Span {
+    tracing::debug_span!(
+        parent: session_span_id(session, world),
+        "disposal",
+        workflow = ?find_workflow(source, world),
+        session = ?session,
+        operation = ?source,
+        kind = operation_kind(source, world),
+        label = operation_label(source, world),
+        cause = disposal_cause_kind(cause),
+    )
+}
+
+fn session_span_id(session: Entity, world: &World) -> Option<Id> {
+    world
+        .get::<SessionSpan>(session)
+        .and_then(|span| span.0.id())
+}
+
+/// Find the session of the oldest input waiting for an operation, which is the
+/// input that the operation will take next.
+pub(crate) fn peek_session(source: Entity, world: &World) -> Option<Entity> {
+    let source_ref = world.get_entity(source)?;
+    source_ref
+        .get::<InputTypeIndicator>()
+        .and_then(|indicator| indicator.peek_session(&source_ref))
+}
+
+/// Find the service entity of the workflow that an operation belongs to.
+pub(crate) fn find_workflow(source: Entity, world: &World) -> Option<Entity> {
+    let mut scope = source;
+    while let Some(parent_scope) = world.get::<ScopeStorage>(scope) {
+        scope = parent_scope.get();
+    }
+
+    // The root scope of a workflow is a child of the workflow service.
+    let service = world.get::<Parent>(scope)?.get();
+    world.get::<WorkflowStorage>(service).map(|_| service)
+}
+
+/// Get a human-readable name for the kind of operation, e.g. `BlockingMap`
+/// instead of `bevy_impulse::operation::OperateBlockingMap<..>`.
+pub(crate) fn operation_kind(source: Entity, world: &World) -> &'static str {
+    let Some(operation_type) = world.get::<OperationType>(source) else {
+        return "unknown";
+    };
+
+    let name: &'static str = **operation_type;
+    let name = name.split('<').next().unwrap_or(name);
+    let name = name.rsplit("::").next().unwrap_or(name);
+    name.strip_prefix("Operate").unwrap_or(name)
+}
+
+fn operation_label(source: Entity, world: &World) -> Option<&str> {
+    world
+        .get::<OperationLabel>(source)
+        .map(|label| label.as_str())
+}
+
+fn cancellation_cause_kind(cause: &CancellationCause) -> &'static str {
+    match cause {
+        CancellationCause::TargetDropped(_) => "target_dropped",
+        CancellationCause::Unreachable(_) => "unreachable",
+        CancellationCause::Filtered(_) => "filtered",
+        CancellationCause::Triggered(_) => "triggered",
+        CancellationCause::Supplanted(_) => "supplanted",
+        CancellationCause::InvalidSpan(_) => "invalid_span",
+        CancellationCause::CircularCollect(_) => "circular_collect",
+        CancellationCause::Livelock(_) => "livelock",
+        CancellationCause::Undeliverable => "undeliverable",
+        CancellationCause::PoisonedMutexInPromise => "poisoned_mutex_in_promise",
+        CancellationCause::Broken(_) => "broken",
+    }
+}
+
+fn disposal_cause_kind(cause: &DisposalCause) -> &'static str {
+    match cause {
+        DisposalCause::Supplanted(_) => "supplanted",
+        DisposalCause::Filtered(_) => "filtered",
+        DisposalCause::Branching(_) => "branching",
+        DisposalCause::BufferKey(_) => "buffer_key",
+        DisposalCause::ServiceUnavailable(_) => "service_unavailable",
+        DisposalCause::TaskDespawned(_) => "task_despawned",
+        DisposalCause::PoisonedMutex(_) => "poisoned_mutex",
+        DisposalCause::Scope(_) => "scope",
+        DisposalCause::UnusedStreams(_) => "unused_streams",
+        DisposalCause::Trimming(_) => "trimming",
+        DisposalCause::ClosedGate(_) => "closed_gate",
+        DisposalCause::EmptySpread(_) => "empty_spread",
+        DisposalCause::DeficientCollection(_) => "deficient_collection",
+        DisposalCause::IncompleteSplit(_) => "incomplete_split",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*};
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id},
+        subscriber::with_default,
+        Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+    #[derive(Debug, Clone, Default)]
+    struct RecordedSpan {
+        name: &'static str,
+        parent: Option<&'static str>,
+        fields: HashMap<&'static str, String>,
+    }
+
+    #[derive(Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<RecordedSpan>>>);
+
+    struct FieldVisitor<'a>(&'a mut HashMap<&'static str, String>);
+
+    impl<'a> Visit for FieldVisitor<'a> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name(), value.to_owned());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name(), format!("{value:?}"));
+        }
+    }
+
+    impl<S> Layer<S> for Recorder
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let mut fields = HashMap::new();
+            attrs.record(&mut FieldVisitor(&mut fields));
+            let parent = ctx
+                .span(id)
+                .and_then(|span| span.parent())
+                .map(|parent| parent.name());
+            self.0.lock().unwrap().push(RecordedSpan {
+                name: attrs.metadata().name(),
+                parent,
+                fields,
+            });
+        }
+    }
+
+    #[test]
+    fn test_operation_spans_nest_in_sessions() {
+        let recorder = Recorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+
+        with_default(subscriber, || {
+            let mut context = TestingContext::minimal_plugins();
+            let workflow = context.spawn_io_workflow(|scope, builder| {
+                scope
+                    .input
+                    .chain(builder)
+                    .map_block(|value: u32| value + 1)
+                    .connect(scope.terminate);
+            });
+
+            let mut promise =
+                context.command(|commands| commands.request(5_u32, workflow).take_response());
+            context.run_with_conditions(&mut promise, Duration::from_secs(2));
+            assert_eq!(promise.take().available(), Some(6));
+            assert!(context.no_unhandled_errors());
+        });
+
+        let spans = recorder.0.lock().unwrap();
+        let map_span = spans
+            .iter()
+            .find(|span| {
+                span.name == "execute_operation"
+                    && span
+                        .fields
+                        .get("kind")
+                        .is_some_and(|kind| kind == "BlockingMap")
+            })
+            .expect("missing span for the map operation");
+
+        assert_eq!(map_span.parent, Some("session"));
+        assert!(map_span.fields.get("workflow").is_some_and(|w| w != "None"));
+        assert!(map_span.fields.get("session").is_some_and(|s| s != "None"));
+        assert!(map_span.fields.contains_key("operation"));
+
+        // The workflow session should be nested inside the session of the
+        // request that was made for it.
+        assert!(spans
+            .iter()
+            .any(|span| span.name == "session" && span.parent == Some("session")));
+    }
+
+    #[test]
+    fn test_cancel_span() {
+        let recorder = Recorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+
+        with_default(subscriber, || {
+            let mut context = TestingContext::minimal_plugins();
+            let workflow = context.spawn_io_workflow(|scope: Scope<u32, u32>, builder| {
+                scope
+                    .input
+                    .chain(builder)
+                    .map_block(|_: u32| Err::<u32, _>(std::fmt::Error))
+                    .cancel_on_err()
+                    .connect(scope.terminate);
+            });
+
+            let mut promise =
+                context.command(|commands| commands.request(5_u32, workflow).take_response());
+            context.run_with_conditions(&mut promise, Duration::from_secs(2));
+            assert!(promise.take().is_cancelled());
+        });
+
+        let spans = recorder.0.lock().unwrap();
+        let cancel_span = spans
+            .iter()
+            .find(|span| span.name == "cancel")
+            .expect("missing span for the cancellation");
+        assert_eq!(cancel_span.fields.get("cause").unwrap(), "filtered");
+        assert_eq!(cancel_span.parent, Some("session"));
+    }
+}
@@ -33,6 +33,9 @@ pub use named_stream::*;
 mod stream_availability;
 pub use stream_availability::*;
 
+mod stream_backpressure;
+pub use stream_backpressure::*;
+
 mod stream_buffer;
 pub use stream_buffer::*;
 
@@ -23,12 +23,12 @@ use tokio::sync::mpsc::unbounded_channel;
 
 use crate::{
     dyn_node::{DynStreamInputPack, DynStreamOutputPack},
-    send_named_stream, AddImpulse, AddOperation, Builder, DefaultStreamBufferContainer,
-    InnerChannel, InputSlot, NamedStreamRedirect, NamedStreamTargets, NamedTarget, NamedValue,
-    OperationResult, OperationRoster, OrBroken, Output, Push, Receiver, RedirectScopeStream,
-    RedirectWorkflowStream, ReportUnhandled, SendNamedStreams, SingleInputStorage,
-    StreamAvailability, StreamEffect, StreamPack, StreamRequest, StreamTargetMap, TakenStream,
-    UnusedStreams, UnusedTarget,
+    send_named_stream, AddImpulse, AddOperation, BackpressurePolicy, Builder, ChannelItem,
+    DefaultStreamBufferContainer, InnerChannel, InputSlot, NamedStreamRedirect, NamedStreamTargets,
+    NamedTarget, NamedValue, OperationResult, OperationRoster, OrBroken, Output, Push, Receiver,
+    RedirectScopeStream, RedirectWorkflowStream, ReportUnhandled, SendNamedStreams,
+    SingleInputStorage, StreamAvailability, StreamBound, StreamEffect, StreamPack, StreamRequest,
+    StreamTargetMap, TakenStream, UnusedStreams, UnusedTarget,
 };
 
 /// A wrapper to turn any stream type into a named stream. Each item that moves
@@ -229,26 +229,57 @@ impl<S: StreamEffect> StreamPack for DynamicallyNamedStream<S> {
 pub struct DynamicallyNamedStreamChannel<S> {
     targets: Arc<NamedStreamTargets>,
     inner: Arc<InnerChannel>,
+    bound: Option<Arc<StreamBound>>,
     _ignore: std::marker::PhantomData<fn(S)>,
 }
 
 impl<S: StreamEffect> DynamicallyNamedStreamChannel<S> {
     pub fn send(&self, data: NamedValue<S::Input>) {
+        StreamBound::send(self.bound.as_ref(), &self.inner, self.message(data));
+    }
+
+    /// Send an instance of data out over a stream. If the stream uses
+    /// [`BackpressurePolicy::Block`] then this will wait until there is room
+    /// for the data. Otherwise this is the same as [`Self::send`].
+    pub async fn send_async(&self, data: NamedValue<S::Input>) {
+        StreamBound::send_async(self.bound.as_ref(), &self.inner, self.message(data)).await;
+    }
+
+    /// Use a different [`BackpressurePolicy`] for this stream. Clones of the
+    /// returned channel share the same limit.
+    pub fn with_backpressure(self, policy: BackpressurePolicy) -> Self {
+        Self {
+            bound: StreamBound::new(policy, &self.inner),
+            ..self
+        }
+    }
+
+    /// The [`BackpressurePolicy`] of this stream.
+    pub fn backpressure(&self) -> BackpressurePolicy {
+        StreamBound::policy(self.bound.as_ref())
+    }
+
+    /// How many messages this stream has discarded because of its
+    /// [`BackpressurePolicy`].
+    pub fn dropped(&self) -> u64 {
+        StreamBound::dropped(self.bound.as_ref())
+    }
+
+    fn message(&self, data: NamedValue<S::Input>) -> ChannelItem {
         let NamedValue { name, value } = data;
-        let f = send_named_stream::<S>(
+        Box::new(send_named_stream::<S>(
             self.inner.source,
             self.inner.session,
             Arc::clone(&self.targets),
             name,
             value,
-        );
-
-        self.inner.sender.send(Box::new(f)).ok();
+        ))
     }
 
     fn new(targets: Arc<NamedStreamTargets>, inner: Arc<InnerChannel>) -> Self {
         Self {
             targets,
+            bound: StreamBound::new(inner.backpressure, &inner),
             inner,
             _ignore: Default::default(),
         }
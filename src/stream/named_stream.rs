@@ -26,12 +26,12 @@ use std::{borrow::Cow, cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
 use tokio::sync::mpsc::unbounded_channel;
 
 use crate::{
-    AddImpulse, AddOperation, Builder, DefaultStreamBufferContainer, DeferredRoster,
-    ExitTargetStorage, InnerChannel, Input, InputBundle, InputSlot, ManageInput, OperationRequest,
-    OperationResult, OperationRoster, OperationSetup, OrBroken, Output, Push, Receiver,
-    RedirectScopeStream, RedirectWorkflowStream, ReportUnhandled, ScopeStorage, SingleInputStorage,
-    StreamEffect, StreamRedirect, StreamRequest, StreamTargetMap, TakenStream, UnusedStreams,
-    UnusedTarget,
+    AddImpulse, AddOperation, BackpressurePolicy, Builder, ChannelItem,
+    DefaultStreamBufferContainer, DeferredRoster, ExitTargetStorage, InnerChannel, Input,
+    InputBundle, InputSlot, ManageInput, OperationRequest, OperationResult, OperationRoster,
+    OperationSetup, OrBroken, Output, Push, Receiver, RedirectScopeStream, RedirectWorkflowStream,
+    ReportUnhandled, ScopeStorage, SingleInputStorage, StreamBound, StreamEffect, StreamRedirect,
+    StreamRequest, StreamTargetMap, TakenStream, UnusedStreams, UnusedTarget,
 };
 
 pub struct NamedStream<S: StreamEffect>(std::marker::PhantomData<fn(S)>);
@@ -473,20 +473,50 @@ pub struct NamedStreamChannel<S> {
     name: Cow<'static, str>,
     targets: Arc<NamedStreamTargets>,
     inner: Arc<InnerChannel>,
+    bound: Option<Arc<StreamBound>>,
     _ignore: std::marker::PhantomData<fn(S)>,
 }
 
 impl<S: StreamEffect> NamedStreamChannel<S> {
     pub fn send(&self, data: S::Input) {
-        let f = send_named_stream::<S>(
+        StreamBound::send(self.bound.as_ref(), &self.inner, self.message(data));
+    }
+
+    /// Send an instance of data out over a stream. If the stream uses
+    /// [`BackpressurePolicy::Block`] then this will wait until there is room
+    /// for the data. Otherwise this is the same as [`Self::send`].
+    pub async fn send_async(&self, data: S::Input) {
+        StreamBound::send_async(self.bound.as_ref(), &self.inner, self.message(data)).await;
+    }
+
+    /// Use a different [`BackpressurePolicy`] for this stream. Clones of the
+    /// returned channel share the same limit.
+    pub fn with_backpressure(self, policy: BackpressurePolicy) -> Self {
+        Self {
+            bound: StreamBound::new(policy, &self.inner),
+            ..self
+        }
+    }
+
+    /// The [`BackpressurePolicy`] of this stream.
+    pub fn backpressure(&self) -> BackpressurePolicy {
+        StreamBound::policy(self.bound.as_ref())
+    }
+
+    /// How many messages this stream has discarded because of its
+    /// [`BackpressurePolicy`].
+    pub fn dropped(&self) -> u64 {
+        StreamBound::dropped(self.bound.as_ref())
+    }
+
+    fn message(&self, data: S::Input) -> ChannelItem {
+        Box::new(send_named_stream::<S>(
             self.inner.source,
             self.inner.session,
             Arc::clone(&self.targets),
             self.name.clone(),
             data,
-        );
-
-        self.inner.sender.send(Box::new(f)).ok();
+        ))
     }
 
     fn new(
@@ -497,6 +527,7 @@ impl<S: StreamEffect> NamedStreamChannel<S> {
         Self {
             name,
             targets,
+            bound: StreamBound::new(inner.backpressure, &inner),
             inner,
             _ignore: Default::default(),
         }
@@ -509,6 +540,7 @@ impl<S> Clone for NamedStreamChannel<S> {
             name: self.name.clone(),
             targets: Arc::clone(&self.targets),
             inner: Arc::clone(&self.inner),
+            bound: self.bound.clone(),
             _ignore: Default::default(),
         }
     }
@@ -0,0 +1,333 @@
+/*
+ * Copyright (C) 2025 Open Source Robotics Foundation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+*/
+
+use bevy_ecs::prelude::{Component, Entity, World};
+
+use tokio::sync::Notify;
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::{record_stream_drops, ChannelItem, ChannelSender, InnerChannel, OperationRoster};
+
+/// Describes what a stream channel should do when messages are being sent
+/// faster than the workflow can deliver them. This mirrors the
+/// [`RetentionPolicy`](crate::RetentionPolicy) of buffers.
+///
+/// The limit applies to messages that have been sent but not yet delivered.
+/// Messages are delivered each time the workflows are flushed. A limit of 0 is
+/// treated as 1.
+///
+/// The default value is [`BackpressurePolicy::Unbounded`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum BackpressurePolicy {
+    /// Do not limit how many messages can be waiting for delivery.
+    #[default]
+    Unbounded,
+    /// Keep the last N messages that were sent. Once the limit is reached, the
+    /// oldest message will be discarded any time a new message is sent.
+    DropOldest(usize),
+    /// Keep the first N messages that were sent. Once the limit is reached, any
+    /// new message that is sent will be discarded.
+    DropNewest(usize),
+    /// Once the limit is reached, `send_async` will wait until there is room
+    /// instead of discarding anything. `send` cannot wait, so it will discard
+    /// the new message the same way as [`Self::DropNewest`].
+    Block(usize),
+}
+
+impl BackpressurePolicy {
+    fn capacity(&self) -> Option<usize> {
+        match self {
+            Self::Unbounded => None,
+            Self::DropOldest(n) | Self::DropNewest(n) | Self::Block(n) => Some((*n).max(1)),
+        }
+    }
+}
+
+/// Insert this component on a service provider to choose the default
+/// [`BackpressurePolicy`] for every stream of the service. Individual streams
+/// can still override it with `with_backpressure`.
+///
+/// Callbacks and maps do not have a provider entity, so their streams can only
+/// be bounded with `with_backpressure`.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct StreamBackpressure(pub BackpressurePolicy);
+
+/// Holds the deliveries of a bounded stream channel until the flush performs
+/// them.
+pub(crate) struct StreamBound {
+    policy: BackpressurePolicy,
+    capacity: usize,
+    source: Entity,
+    sender: ChannelSender,
+    messages: Mutex<VecDeque<ChannelItem>>,
+    dropped: AtomicU64,
+    unreported_drops: AtomicU64,
+    room: Notify,
+}
+
+impl StreamBound {
+    pub(crate) fn new(policy: BackpressurePolicy, inner: &InnerChannel) -> Option<Arc<Self>> {
+        let capacity = policy.capacity()?;
+        Some(Arc::new(Self {
+            policy,
+            capacity,
+            source: inner.source,
+            sender: inner.sender.clone(),
+            messages: Default::default(),
+            dropped: AtomicU64::new(0),
+            unreported_drops: AtomicU64::new(0),
+            room: Notify::new(),
+        }))
+    }
+
+    /// Send a stream message through the channel, respecting the limit of the
+    /// bound if there is one.
+    pub(crate) fn send(bound: Option<&Arc<Self>>, inner: &InnerChannel, message: ChannelItem) {
+        let Some(bound) = bound else {
+            inner.sender.send(message).ok();
+            return;
+        };
+
+        if bound.push(message) {
+            inner.sender.send(bound.deliver_next(inner.source)).ok();
+        }
+    }
+
+    /// Same as [`Self::send`] except it waits for room if the policy is
+    /// [`BackpressurePolicy::Block`].
+    pub(crate) async fn send_async(
+        bound: Option<&Arc<Self>>,
+        inner: &InnerChannel,
+        message: ChannelItem,
+    ) {
+        let Some(bound) = bound else {
+            inner.sender.send(message).ok();
+            return;
+        };
+
+        if bound.push_when_ready(message).await {
+            inner.sender.send(bound.deliver_next(inner.source)).ok();
+        }
+    }
+
+    pub(crate) fn policy(bound: Option<&Arc<Self>>) -> BackpressurePolicy {
+        bound.map(|bound| bound.policy).unwrap_or_default()
+    }
+
+    /// How many messages have been discarded.
+    pub(crate) fn dropped(bound: Option<&Arc<Self>>) -> u64 {
+        bound
+            .map(|bound| bound.dropped.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Add a message. Returns true if a new delivery needs to be scheduled for
+    /// it.
+    fn push(&self, message: ChannelItem) -> bool {
+        let mut messages = self.messages.lock().unwrap();
+        if messages.len() < self.capacity {
+            messages.push_back(message);
+            return true;
+        }
+
+        match self.policy {
+            BackpressurePolicy::DropOldest(_) => {
+                // The delivery that was scheduled for the discarded message
+                // will deliver the new one instead.
+                messages.pop_front();
+                messages.push_back(message);
+                self.record_drop();
+                false
+            }
+            BackpressurePolicy::DropNewest(_) | BackpressurePolicy::Block(_) => {
+                self.record_drop();
+                false
+            }
+            BackpressurePolicy::Unbounded => {
+                messages.push_back(message);
+                true
+            }
+        }
+    }
+
+    async fn push_when_ready(&self, message: ChannelItem) -> bool {
+        if !matches!(self.policy, BackpressurePolicy::Block(_)) {
+            return self.push(message);
+        }
+
+        loop {
+            let room = self.room.notified();
+            tokio::pin!(room);
+            // Register for the notification before checking for room so that
+            // a delivery in between cannot be missed.
+            room.as_mut().enable();
+            {
+                let mut messages = self.messages.lock().unwrap();
+                if messages.len() < self.capacity {
+                    messages.push_back(message);
+                    return true;
+                }
+            }
+            room.await;
+        }
+    }
+
+    /// Create a delivery that will perform whichever message is the oldest in
+    /// this bound by the time the delivery happens.
+    fn deliver_next(self: &Arc<Self>, source: Entity) -> ChannelItem {
+        let bound = Arc::clone(self);
+        Box::new(move |world: &mut World, roster: &mut OperationRoster| {
+            let message = bound.messages.lock().unwrap().pop_front();
+            bound.room.notify_waiters();
+
+            let drops = bound.unreported_drops.swap(0, Ordering::Relaxed);
+            if drops > 0 {
+                record_stream_drops(source, drops, world);
+            }
+
+            if let Some(message) = message {
+                message(world, roster);
+            }
+        })
+    }
+
+    fn record_drop(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        self.unreported_drops.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Drop for StreamBound {
+    fn drop(&mut self) {
+        // Deliveries report the drops that happened before them. Once the
+        // stream is closed there will be no more deliveries, so report
+        // whatever is left.
+        let drops = *self.unreported_drops.get_mut();
+        if drops > 0 {
+            let source = self.source;
+            self.sender
+                .send(Box::new(
+                    move |world: &mut World, _: &mut OperationRoster| {
+                        record_stream_drops(source, drops, world);
+                    },
+                ))
+                .ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing::*, BackpressurePolicy, StreamBackpressure, WorkflowMetrics};
+    use bevy_ecs::system::EntityCommands;
+
+    fn spawn_burst(
+        context: &mut TestingContext,
+        policy: BackpressurePolicy,
+    ) -> Service<u32, (), StreamOf<u32>> {
+        context.command(|commands| {
+            commands.spawn_service(
+                (|In(srv): AsyncServiceInput<u32, StreamOf<u32>>| {
+                    // Send the whole burst before the flush gets a chance to
+                    // deliver any of it.
+                    for i in 0..srv.request {
+                        srv.streams.send(i);
+                    }
+                    async move {}
+                })
+                .with(move |entity: &mut EntityCommands| {
+                    entity.insert(StreamBackpressure(policy));
+                }),
+            )
+        })
+    }
+
+    fn collect(context: &mut TestingContext, service: Service<u32, (), StreamOf<u32>>) -> Vec<u32> {
+        let mut recipient = context.command(|commands| commands.request(10, service).take());
+        context.run_with_conditions(&mut recipient.response, Duration::from_secs(2));
+        assert!(recipient.response.take().available().is_some());
+
+        let mut received = Vec::new();
+        while let Ok(value) = recipient.streams.try_recv() {
+            received.push(value);
+        }
+        received
+    }
+
+    #[test]
+    fn test_stream_backpressure_policies() {
+        let mut context = TestingContext::minimal_plugins();
+        context.app.init_resource::<WorkflowMetrics>();
+
+        let unbounded = spawn_burst(&mut context, BackpressurePolicy::Unbounded);
+        assert_eq!(
+            collect(&mut context, unbounded),
+            (0..10).collect::<Vec<_>>()
+        );
+
+        let drop_oldest = spawn_burst(&mut context, BackpressurePolicy::DropOldest(3));
+        assert_eq!(collect(&mut context, drop_oldest), [7, 8, 9]);
+
+        let drop_newest = spawn_burst(&mut context, BackpressurePolicy::DropNewest(3));
+        assert_eq!(collect(&mut context, drop_newest), [0, 1, 2]);
+
+        // A plain send cannot wait for room, so it discards instead.
+        let block = spawn_burst(&mut context, BackpressurePolicy::Block(3));
+        assert_eq!(collect(&mut context, block), [0, 1, 2]);
+
+        let metrics = context.app.world.resource::<WorkflowMetrics>();
+        let drops: u64 = metrics.operations().map(|(_, m)| m.stream_drops).sum();
+        assert_eq!(drops, 21);
+    }
+
+    #[test]
+    fn test_stream_backpressure_block() {
+        let mut context = TestingContext::minimal_plugins();
+        let service = context.command(|commands| {
+            commands.spawn_service(
+                |In(srv): AsyncServiceInput<u32, StreamOf<u32>>| async move {
+                    let stream = srv.streams.with_backpressure(BackpressurePolicy::Block(2));
+                    for i in 0..srv.request {
+                        stream.send_async(i).await;
+                    }
+                    stream.dropped()
+                },
+            )
+        });
+
+        let mut recipient = context.command(|commands| commands.request(10, service).take());
+        context.run_with_conditions(&mut recipient.response, Duration::from_secs(2));
+        assert!(recipient
+            .response
+            .take()
+            .available()
+            .is_some_and(|d| d == 0));
+
+        let mut received = Vec::new();
+        while let Ok(value) = recipient.streams.try_recv() {
+            received.push(value);
+        }
+        assert_eq!(received, (0..10).collect::<Vec<_>>());
+    }
+}
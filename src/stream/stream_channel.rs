@@ -19,44 +19,75 @@ use bevy_ecs::prelude::{Entity, World};
 
 use std::sync::Arc;
 
-use crate::{InnerChannel, OperationRoster, ReportUnhandled, StreamEffect, StreamRequest};
+use crate::{
+    BackpressurePolicy, ChannelItem, InnerChannel, OperationRoster, ReportUnhandled, StreamBound,
+    StreamEffect, StreamRequest,
+};
 
 /// Use this channel to stream data using the [`StreamChannel::send`] method.
 pub struct StreamChannel<S> {
     target: Option<Entity>,
     inner: Arc<InnerChannel>,
+    bound: Option<Arc<StreamBound>>,
     _ignore: std::marker::PhantomData<fn(S)>,
 }
 
 impl<S: StreamEffect> StreamChannel<S> {
     /// Send an instance of data out over a stream.
     pub fn send(&self, data: S::Input) {
+        StreamBound::send(self.bound.as_ref(), &self.inner, self.message(data));
+    }
+
+    /// Send an instance of data out over a stream. If the stream uses
+    /// [`BackpressurePolicy::Block`] then this will wait until there is room
+    /// for the data. Otherwise this is the same as [`Self::send`].
+    pub async fn send_async(&self, data: S::Input) {
+        StreamBound::send_async(self.bound.as_ref(), &self.inner, self.message(data)).await;
+    }
+
+    /// Use a different [`BackpressurePolicy`] for this stream. Clones of the
+    /// returned channel share the same limit.
+    pub fn with_backpressure(self, policy: BackpressurePolicy) -> Self {
+        Self {
+            bound: StreamBound::new(policy, &self.inner),
+            ..self
+        }
+    }
+
+    /// The [`BackpressurePolicy`] of this stream.
+    pub fn backpressure(&self) -> BackpressurePolicy {
+        StreamBound::policy(self.bound.as_ref())
+    }
+
+    /// How many messages this stream has discarded because of its
+    /// [`BackpressurePolicy`].
+    pub fn dropped(&self) -> u64 {
+        StreamBound::dropped(self.bound.as_ref())
+    }
+
+    fn message(&self, data: S::Input) -> ChannelItem {
         let source = self.inner.source;
         let session = self.inner.session;
         let target = self.target;
-        self.inner
-            .sender
-            .send(Box::new(
-                move |world: &mut World, roster: &mut OperationRoster| {
-                    let mut request = StreamRequest {
-                        source,
-                        session,
-                        target,
-                        world,
-                        roster,
-                    };
+        Box::new(move |world: &mut World, roster: &mut OperationRoster| {
+            let mut request = StreamRequest {
+                source,
+                session,
+                target,
+                world,
+                roster,
+            };
 
-                    S::side_effect(data, &mut request)
-                        .and_then(|output| request.send_output(output))
-                        .report_unhandled(source, world);
-                },
-            ))
-            .ok();
+            S::side_effect(data, &mut request)
+                .and_then(|output| request.send_output(output))
+                .report_unhandled(source, world);
+        })
     }
 
     pub(crate) fn new(target: Option<Entity>, inner: Arc<InnerChannel>) -> Self {
         Self {
             target,
+            bound: StreamBound::new(inner.backpressure, &inner),
             inner,
             _ignore: Default::default(),
         }
@@ -68,6 +99,7 @@ impl<S> Clone for StreamChannel<S> {
         Self {
             target: ::std::clone::Clone::clone(&self.target),
             inner: Arc::clone(&self.inner),
+            bound: self.bound.clone(),
             _ignore: Default::default(),
         }
     }
@@ -23,7 +23,7 @@ use std::{
     collections::{hash_map::Entry, HashMap},
 };
 
-use crate::{DuplicateStream, NamedTarget, NamedValue, UnhandledErrors};
+use crate::{report_unhandled_error, DuplicateStream, NamedTarget, NamedValue};
 
 /// The actual entity target of the stream is held in this component which does
 /// not have any generic parameters. This means it is possible to lookup the
@@ -48,14 +48,14 @@ impl StreamTargetMap {
             }
             Entry::Occupied(_) => {
                 commands.add(move |world: &mut World| {
-                    world
-                        .get_resource_or_insert_with(|| UnhandledErrors::default())
-                        .duplicate_streams
-                        .push(DuplicateStream {
+                    report_unhandled_error(
+                        DuplicateStream {
                             target,
                             type_name: std::any::type_name::<T>(),
                             stream_name: None,
-                        })
+                        },
+                        world,
+                    )
                 });
             }
         }
@@ -73,14 +73,14 @@ impl StreamTargetMap {
             }
             Entry::Occupied(_) => {
                 commands.add(move |world: &mut World| {
-                    world
-                        .get_resource_or_insert_with(|| UnhandledErrors::default())
-                        .duplicate_streams
-                        .push(DuplicateStream {
+                    report_unhandled_error(
+                        DuplicateStream {
                             target,
                             type_name: std::any::type_name::<T>(),
                             stream_name: Some(name.clone()),
-                        });
+                        },
+                        world,
+                    );
                 });
             }
         }
@@ -34,10 +34,10 @@ use smallvec::SmallVec;
 use crate::{
     flush_impulses, Accessing, AddContinuousServicesExt, AnyBuffer, AsAnyBuffer, AsyncServiceInput,
     BlockingMap, BlockingServiceInput, Buffer, BufferKey, BufferKeyLifecycle, Bufferable,
-    Buffering, Builder, ContinuousQuery, ContinuousQueueView, ContinuousService, FlushParameters,
-    GetBufferedSessionsFn, Joining, OperationError, OperationResult, OperationRoster, Promise,
-    RunCommandsOnWorldExt, Scope, Service, SpawnWorkflowExt, StreamOf, StreamPack, UnhandledErrors,
-    WorkflowSettings,
+    Buffering, Builder, ContinuousQuery, ContinuousQueueView, ContinuousService, CoveragePlugin,
+    CoverageReport, FlushParameters, GetBufferedSessionsFn, Joining, OperationError,
+    OperationResult, OperationRoster, Promise, RunCommandsOnWorldExt, Scope, Service,
+    SpawnWorkflowExt, StreamOf, StreamPack, UnhandledErrors, WorkflowCoverage, WorkflowSettings,
 };
 
 pub struct TestingContext {
@@ -159,6 +159,28 @@ impl TestingContext {
         self.app.world.get_resource::<UnhandledErrors>()
     }
 
+    /// Start recording which operations receive inputs. Use
+    /// [`Self::coverage_report`] to see the results.
+    pub fn enable_coverage(&mut self) {
+        if !self.app.is_plugin_added::<CoveragePlugin>() {
+            self.app.add_plugins(CoveragePlugin::default());
+        }
+    }
+
+    /// Get a report of which labeled operations have been reached since
+    /// [`Self::enable_coverage`] was called.
+    pub fn coverage_report(&mut self) -> Option<CoverageReport> {
+        if !self.app.world.contains_resource::<WorkflowCoverage>() {
+            return None;
+        }
+
+        let report = self
+            .app
+            .world
+            .resource_scope::<WorkflowCoverage, _>(|world, coverage| coverage.report(world));
+        Some(report)
+    }
+
     // Check that all buffers in the world are empty
     pub fn confirm_buffers_empty(&mut self) -> Result<(), Vec<Entity>> {
         let mut query = self.app.world.query::<(Entity, &GetBufferedSessionsFn)>();
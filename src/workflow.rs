@@ -22,9 +22,9 @@ use bevy_ecs::{
 use bevy_hierarchy::BuildChildren;
 
 use crate::{
-    Builder, BuilderScopeContext, DeliveryChoice, InputSlot, OperateScope, Output, ScopeEndpoints,
-    ScopeSettingsStorage, Service, ServiceBundle, StreamAvailability, StreamPack, WorkflowService,
-    WorkflowStorage,
+    Builder, BuilderScopeContext, DeliveryChoice, InputSlot, OperateScope, Output, Priority,
+    ScopeEndpoints, ScopeSettingsStorage, Service, ServiceBundle, StreamAvailability, StreamPack,
+    WorkflowPriority, WorkflowService, WorkflowStorage,
 };
 
 mod internal;
@@ -92,6 +92,7 @@ pub struct Scope<Request, Response, Streams: StreamPack = ()> {
 pub struct WorkflowSettings {
     delivery: DeliverySettings,
     scope: ScopeSettings,
+    priority: Option<Priority>,
 }
 
 impl WorkflowSettings {
@@ -137,6 +138,20 @@ impl WorkflowSettings {
         self.scope.set_uninterruptible(true);
         self
     }
+
+    /// Run every session of this workflow with a [`Priority`]. A request that
+    /// has its own [priority](crate::DeliveryInstructions::with_priority) will
+    /// use whichever priority is higher.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// The priority that every session of this workflow will run with, if one
+    /// was set.
+    pub fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
 }
 
 impl From<()> for WorkflowSettings {
@@ -269,6 +284,9 @@ impl<'w, 's> SpawnWorkflowExt for Commands<'w, 's> {
         settings
             .delivery
             .apply_entity_commands::<Request>(&mut service);
+        if let Some(priority) = settings.priority {
+            service.insert(WorkflowPriority(priority));
+        }
         let service = service.id();
         self.entity(scope_id)
             .insert(ScopeSettingsStorage(settings.scope))